@@ -0,0 +1,336 @@
+pub mod rules;
+pub mod sinks;
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, watch};
+
+use crate::config::Config;
+use crate::engine::state::{MarketSnapshot, MarketState};
+use rules::{AlertKind, AlertRule, RuleTracker};
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+// alerts queued for the TUI, later ones are dropped while it's full
+const TOAST_CAPACITY: usize = 64;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Alert {
+    // unix ms
+    pub ts: u64,
+    pub symbol: String,
+    pub rule: String,
+    pub kind: AlertKind,
+    pub message: String,
+}
+
+// what a symbol looked like at the previous check, to spot events since
+#[derive(Default)]
+struct SymbolProgress {
+    resyncs: Option<u64>,
+    // seq of the newest significant trade already checked
+    last_significant: u64,
+}
+
+// Checks the configured alert rules against every symbol's published snapshot
+// and delivers the alerts they raise. Rules can change while running, which
+// starts their tracking over.
+pub struct AlertMonitor {
+    states: Vec<Arc<MarketState>>,
+    conf: Arc<Config>,
+    // [rule][symbol]
+    trackers: Vec<Vec<RuleTracker>>,
+    progress: Vec<SymbolProgress>,
+}
+
+impl AlertMonitor {
+    pub fn new(states: Vec<Arc<MarketState>>, conf: Arc<Config>) -> Self {
+        let mut monitor = Self {
+            progress: states.iter().map(|_| SymbolProgress::default()).collect(),
+            states,
+            conf: conf.clone(),
+            trackers: Vec::new(),
+        };
+        monitor.set_config(conf);
+        monitor
+    }
+
+    fn set_config(&mut self, conf: Arc<Config>) {
+        if self.trackers.is_empty() || conf.alerts != self.conf.alerts {
+            self.trackers = conf
+                .alerts
+                .iter()
+                .map(|_| self.states.iter().map(|_| RuleTracker::default()).collect())
+                .collect();
+        }
+        self.conf = conf;
+    }
+
+    // runs until the task is dropped. alerts for the TUI come out of the receiver
+    pub fn spawn(
+        mut self,
+        mut config_updates: watch::Receiver<Arc<Config>>,
+    ) -> (tokio::task::JoinHandle<()>, mpsc::Receiver<Alert>) {
+        let (toast_tx, toast_rx) = mpsc::channel(TOAST_CAPACITY);
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if config_updates.has_changed().unwrap_or(false) {
+                    let conf = config_updates.borrow_and_update().clone();
+                    self.set_config(conf);
+                }
+                for alert in self.check(Instant::now()) {
+                    sinks::deliver(&alert, &self.conf);
+                    let _ = toast_tx.try_send(alert);
+                }
+            }
+        });
+        (handle, toast_rx)
+    }
+
+    pub fn check(&mut self, now: Instant) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for (idx, state) in self.states.iter().enumerate() {
+            let snapshot = state.load();
+            let progress = &mut self.progress[idx];
+
+            let resyncs = snapshot.metrics.resyncs;
+            let resynced = progress.resyncs.is_some_and(|seen| resyncs > seen);
+            progress.resyncs = Some(resyncs);
+
+            let new_significant: Vec<_> = snapshot
+                .significant_trades
+                .iter()
+                .filter(|t| t.seq > progress.last_significant)
+                .collect();
+            if let Some(newest) = new_significant.iter().map(|t| t.seq).max() {
+                progress.last_significant = newest;
+            }
+
+            for (rule, trackers) in self.conf.alerts.iter().zip(self.trackers.iter_mut()) {
+                if !rule.applies_to(&state.symbol) {
+                    continue;
+                }
+                let tracker = &mut trackers[idx];
+                let message = match rule.kind {
+                    AlertKind::SpreadTicks | AlertKind::Imbalance | AlertKind::FeedLagMs => {
+                        // a missing value resets the breach timer, so it's always observed
+                        let value = level(rule.kind, &snapshot);
+                        tracker
+                            .observe_level(rule, value, now)
+                            .then(|| level_message(rule, value.unwrap_or_default()))
+                    }
+                    AlertKind::SignificantTrade => new_significant
+                        .iter()
+                        .filter(|t| {
+                            t.notional_value.to_f64().unwrap_or(0.0) >= rule.above.unwrap_or(0.0)
+                        })
+                        .max_by_key(|t| t.notional_value)
+                        .filter(|_| tracker.observe_event(rule, now))
+                        .map(|t| {
+                            format!(
+                                "{} {} @ {} ({:.2} notional): {}",
+                                t.side(),
+                                t.trade.quantity,
+                                t.trade.price,
+                                t.notional_value,
+                                t.reasons()
+                            )
+                        }),
+                    AlertKind::Resync => (resynced && tracker.observe_event(rule, now))
+                        .then(|| format!("book resynced ({} so far)", resyncs)),
+                };
+
+                if let Some(message) = message {
+                    alerts.push(Alert {
+                        ts: unix_ms(),
+                        symbol: state.symbol.clone(),
+                        rule: rule.title().to_string(),
+                        kind: rule.kind,
+                        message,
+                    });
+                }
+            }
+        }
+        alerts
+    }
+}
+
+fn level(kind: AlertKind, snapshot: &MarketSnapshot) -> Option<f64> {
+    match kind {
+        AlertKind::SpreadTicks => snapshot.book.spread().map(|ticks| ticks as f64),
+        AlertKind::Imbalance => snapshot
+            .metrics
+            .imbalance_ratio
+            .and_then(|ratio| ratio.to_f64())
+            .map(|ratio| ratio.max(1.0 - ratio)),
+        AlertKind::FeedLagMs => snapshot.metrics.orderbook_lag_ms.map(|lag| lag as f64),
+        AlertKind::SignificantTrade | AlertKind::Resync => None,
+    }
+}
+
+fn level_message(rule: &AlertRule, value: f64) -> String {
+    let above = rule.above.unwrap_or_default();
+    let held = match rule.for_secs {
+        0 => String::new(),
+        secs => format!(" for {secs}s"),
+    };
+    match rule.kind {
+        AlertKind::SpreadTicks => format!("spread {value} ticks, above {above}{held}"),
+        AlertKind::Imbalance => {
+            format!(
+                "imbalance {:.0}% one-sided, above {:.0}%{held}",
+                value * 100.0,
+                above * 100.0
+            )
+        }
+        _ => format!("feed lag {value}ms, above {above}ms{held}"),
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::{DepthSnapshot, SignificanceReason, SignificantTrade, Trade};
+    use crate::book::orderbook::OrderBook;
+    use crate::book::scaler::Scaler;
+    use rust_decimal::Decimal;
+
+    fn state() -> Arc<MarketState> {
+        let scaler = Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3));
+        let book = OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![["100.00".to_string(), "1.000".to_string()]],
+                asks: vec![["100.08".to_string(), "1.000".to_string()]],
+            },
+            &scaler,
+        )
+        .unwrap();
+        Arc::new(MarketState::new(book, "BTCUSDT".to_string(), scaler))
+    }
+
+    fn rule(kind: AlertKind, above: Option<f64>) -> AlertRule {
+        AlertRule {
+            kind,
+            name: None,
+            symbol: None,
+            above,
+            clear: None,
+            for_secs: 0,
+            cooldown_secs: 60,
+        }
+    }
+
+    #[test]
+    fn raises_level_and_event_alerts() {
+        let state = state();
+        let conf = Config {
+            alerts: vec![
+                rule(AlertKind::SpreadTicks, Some(5.0)),
+                rule(AlertKind::Resync, None),
+                rule(AlertKind::SignificantTrade, Some(1_000.0)),
+                AlertRule {
+                    symbol: Some("ETHUSDT".to_string()),
+                    ..rule(AlertKind::SpreadTicks, Some(1.0))
+                },
+            ],
+            ..Config::default()
+        };
+        let mut monitor = AlertMonitor::new(vec![state.clone()], Arc::new(conf));
+        let now = Instant::now();
+
+        // the 8 tick spread is already wide, nothing has happened yet
+        let first = monitor.check(now);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].rule, "spread_ticks");
+        assert_eq!(first[0].message, "spread 8 ticks, above 5");
+
+        let mut snapshot = (*state.load()).clone();
+        snapshot.metrics.resyncs = 1;
+        for (qty, seq) in [(Decimal::ONE, 1), (Decimal::from(20), 2)] {
+            let trade = Trade {
+                price: Decimal::from(100),
+                quantity: qty,
+                trade_time: seq,
+                is_buyer_maker: false,
+            };
+            snapshot.significant_trades.push_back(SignificantTrade::new(
+                trade,
+                qty * Decimal::from(100),
+                vec![SignificanceReason::LargeNotional],
+                seq,
+            ));
+        }
+        state.snapshot.store(Arc::new(snapshot));
+
+        // the spread alert is still active, only the events fire
+        let second = monitor.check(now);
+        let rules: Vec<&str> = second.iter().map(|a| a.rule.as_str()).collect();
+        assert_eq!(rules, ["resync", "significant_trade"]);
+        assert_eq!(second[0].message, "book resynced (1 so far)");
+        assert_eq!(
+            second[1].message,
+            "BUY 20 @ 100 (2000.00 notional): Notional"
+        );
+
+        assert!(monitor.check(now).is_empty());
+    }
+
+    #[test]
+    fn alerts_each_significant_trade_sharing_a_trade_time() {
+        let state = state();
+        let conf = Config {
+            alerts: vec![AlertRule {
+                cooldown_secs: 0,
+                ..rule(AlertKind::SignificantTrade, Some(1_000.0))
+            }],
+            ..Config::default()
+        };
+        let mut monitor = AlertMonitor::new(vec![state.clone()], Arc::new(conf));
+        let now = Instant::now();
+
+        let significant = |qty: i64, is_buyer_maker: bool, seq: u64| {
+            let trade = Trade {
+                price: Decimal::from(100),
+                quantity: Decimal::from(qty),
+                trade_time: 5,
+                is_buyer_maker,
+            };
+            SignificantTrade::new(
+                trade,
+                Decimal::from(qty * 100),
+                vec![SignificanceReason::LargeNotional],
+                seq,
+            )
+        };
+
+        let mut snapshot = (*state.load()).clone();
+        snapshot
+            .significant_trades
+            .push_back(significant(20, false, 1));
+        state.snapshot.store(Arc::new(snapshot.clone()));
+        assert_eq!(monitor.check(now).len(), 1);
+
+        // a second taker order in the same millisecond, seen by the next check
+        snapshot
+            .significant_trades
+            .push_back(significant(30, true, 2));
+        state.snapshot.store(Arc::new(snapshot));
+        let second = monitor.check(now);
+        assert_eq!(second.len(), 1);
+        assert_eq!(
+            second[0].message,
+            "SELL 30 @ 100 (3000.00 notional): Notional"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+// what an alert rule watches
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    // best ask minus best bid, in ticks
+    SpreadTicks,
+    // the larger side's share of resting size over the imbalance depth, 0.5..1
+    Imbalance,
+    // exchange event time to processing of depth updates
+    FeedLagMs,
+    // a significant trade with at least `above` notional
+    SignificantTrade,
+    // the book was discarded to wait for a fresh snapshot
+    Resync,
+}
+
+impl AlertKind {
+    // kinds that track a value over time, the others fire on events
+    pub fn is_level(self) -> bool {
+        matches!(
+            self,
+            AlertKind::SpreadTicks | AlertKind::Imbalance | AlertKind::FeedLagMs
+        )
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AlertKind::SpreadTicks => "spread_ticks",
+            AlertKind::Imbalance => "imbalance",
+            AlertKind::FeedLagMs => "feed_lag_ms",
+            AlertKind::SignificantTrade => "significant_trade",
+            AlertKind::Resync => "resync",
+        }
+    }
+}
+
+fn default_cooldown_secs() -> u64 {
+    60
+}
+
+// one `[[alerts]]` table in config.toml
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    pub kind: AlertKind,
+    // shown in place of the kind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // every symbol when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above: Option<f64>,
+    // level rules re-arm once the value is back at or below this, `above` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clear: Option<f64>,
+    // how long a level has to stay above the threshold
+    #[serde(default)]
+    pub for_secs: u64,
+    // minimum time between two alerts from this rule for one symbol
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl AlertRule {
+    pub fn title(&self) -> &str {
+        self.name.as_deref().unwrap_or(self.kind.label())
+    }
+
+    pub fn applies_to(&self, symbol: &str) -> bool {
+        self.symbol
+            .as_ref()
+            .is_none_or(|wanted| wanted.eq_ignore_ascii_case(symbol))
+    }
+
+    // why the rule can't work, if it can't
+    pub fn problem(&self) -> Option<String> {
+        let above = match (self.kind, self.above) {
+            (AlertKind::Resync, Some(_)) => return Some("resync takes no `above`".to_string()),
+            (AlertKind::Resync, None) => return None,
+            (_, None) => return Some("needs `above`".to_string()),
+            (_, Some(above)) => above,
+        };
+        if self.kind == AlertKind::Imbalance && !(0.5..1.0).contains(&above) {
+            return Some("imbalance `above` must be in [0.5, 1)".to_string());
+        }
+        if above.is_nan() || above < 0.0 {
+            return Some("`above` must not be negative".to_string());
+        }
+        match self.clear {
+            Some(_) if !self.kind.is_level() => {
+                Some(format!("{} takes no `clear`", self.kind.label()))
+            }
+            Some(clear) if clear.is_nan() || clear > above => {
+                Some("`clear` must not exceed `above`".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+// Per rule and symbol state. Level rules fire once the value has been above
+// the threshold for `for_secs`, then stay quiet until it drops back to `clear`.
+// Either kind fires at most once per cooldown.
+#[derive(Debug, Default)]
+pub struct RuleTracker {
+    breached_since: Option<Instant>,
+    // fired, or held back by the cooldown, and not cleared since
+    active: bool,
+    last_fired: Option<Instant>,
+}
+
+impl RuleTracker {
+    // true when `value` should raise an alert
+    pub fn observe_level(&mut self, rule: &AlertRule, value: Option<f64>, now: Instant) -> bool {
+        let (Some(value), Some(above)) = (value, rule.above) else {
+            self.breached_since = None;
+            return false;
+        };
+
+        if self.active {
+            if value <= rule.clear.unwrap_or(above) {
+                self.active = false;
+            }
+            return false;
+        }
+        if value <= above {
+            self.breached_since = None;
+            return false;
+        }
+
+        let since = *self.breached_since.get_or_insert(now);
+        if now.duration_since(since) < Duration::from_secs(rule.for_secs) {
+            return false;
+        }
+        self.breached_since = None;
+        self.active = true;
+        self.fire(rule, now)
+    }
+
+    // true when an event should raise an alert
+    pub fn observe_event(&mut self, rule: &AlertRule, now: Instant) -> bool {
+        self.fire(rule, now)
+    }
+
+    fn fire(&mut self, rule: &AlertRule, now: Instant) -> bool {
+        let cooling = self
+            .last_fired
+            .is_some_and(|last| now.duration_since(last) < Duration::from_secs(rule.cooldown_secs));
+        if cooling {
+            return false;
+        }
+        self.last_fired = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spread_rule() -> AlertRule {
+        AlertRule {
+            kind: AlertKind::SpreadTicks,
+            name: None,
+            symbol: None,
+            above: Some(5.0),
+            clear: Some(2.0),
+            for_secs: 0,
+            cooldown_secs: 0,
+        }
+    }
+
+    #[test]
+    fn level_rules_rearm_only_below_clear() {
+        let rule = spread_rule();
+        let mut tracker = RuleTracker::default();
+        let now = Instant::now();
+
+        let fired: Vec<bool> = [3.0, 6.0, 7.0, 4.0, 6.0, 2.0, 6.0]
+            .into_iter()
+            .map(|value| tracker.observe_level(&rule, Some(value), now))
+            .collect();
+        assert_eq!(fired, [false, true, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn level_rules_wait_for_the_breach_to_last() {
+        let rule = AlertRule {
+            for_secs: 10,
+            ..spread_rule()
+        };
+        let mut tracker = RuleTracker::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(!tracker.observe_level(&rule, Some(6.0), at(0)));
+        assert!(!tracker.observe_level(&rule, Some(6.0), at(9)));
+        // dipping back under restarts the clock
+        assert!(!tracker.observe_level(&rule, Some(4.0), at(10)));
+        assert!(!tracker.observe_level(&rule, Some(6.0), at(11)));
+        assert!(tracker.observe_level(&rule, Some(6.0), at(21)));
+    }
+
+    #[test]
+    fn cooldown_limits_how_often_a_rule_fires() {
+        let rule = AlertRule {
+            kind: AlertKind::Resync,
+            above: None,
+            clear: None,
+            cooldown_secs: 30,
+            ..spread_rule()
+        };
+        let mut tracker = RuleTracker::default();
+        let start = Instant::now();
+
+        assert!(tracker.observe_event(&rule, start));
+        assert!(!tracker.observe_event(&rule, start + Duration::from_secs(29)));
+        assert!(tracker.observe_event(&rule, start + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn reports_rules_that_cannot_work() {
+        let missing = AlertRule {
+            above: None,
+            ..spread_rule()
+        };
+        assert_eq!(missing.problem().as_deref(), Some("needs `above`"));
+
+        let inverted = AlertRule {
+            clear: Some(8.0),
+            ..spread_rule()
+        };
+        assert!(inverted.problem().is_some());
+
+        let imbalance = AlertRule {
+            kind: AlertKind::Imbalance,
+            above: Some(0.3),
+            clear: None,
+            ..spread_rule()
+        };
+        assert!(imbalance.problem().is_some());
+        assert_eq!(spread_rule().problem(), None);
+    }
+}
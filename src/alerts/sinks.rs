@@ -0,0 +1,70 @@
+use std::io::Write;
+use std::time::Duration;
+
+use crate::alerts::Alert;
+use crate::config::Config;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Hands `alert` to every sink enabled in `conf`. The log line always goes
+// out, the command and webhook run in the background so a slow one can't hold
+// up the next check.
+pub fn deliver(alert: &Alert, conf: &Config) {
+    tracing::warn!(
+        "[ALERT] [{}] {}: {}",
+        alert.symbol,
+        alert.rule,
+        alert.message
+    );
+
+    if conf.alert_bell {
+        // stderr is the terminal in both modes, stdout carries the headless JSON
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(b"\x07");
+        let _ = stderr.flush();
+    }
+
+    if let Some(command) = &conf.alert_command {
+        let spawned = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("ALERT_SYMBOL", &alert.symbol)
+            .env("ALERT_RULE", &alert.rule)
+            .env("ALERT_KIND", alert.kind.label())
+            .env("ALERT_MESSAGE", &alert.message)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+        match spawned {
+            // reaped in the background
+            Ok(mut child) => {
+                tokio::spawn(async move {
+                    if let Ok(status) = child.wait().await
+                        && !status.success()
+                    {
+                        tracing::warn!("Alert command exited with {}", status);
+                    }
+                });
+            }
+            Err(e) => tracing::warn!("Failed to run alert command: {}", e),
+        }
+    }
+
+    if let Some(url) = &conf.alert_webhook_url {
+        let url = url.clone();
+        let alert = alert.clone();
+        tokio::spawn(async move {
+            let sent = reqwest::Client::new()
+                .post(&url)
+                .timeout(WEBHOOK_TIMEOUT)
+                .json(&alert)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = sent {
+                tracing::warn!("Failed to post alert to {}: {}", url, e);
+            }
+        });
+    }
+}
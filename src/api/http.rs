@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+// requests are a line and a few headers, anything bigger isn't for us
+const MAX_REQUEST_BYTES: usize = 8192;
+
+// The parts of an HTTP/1.1 request head the local servers look at
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    // lowercase names
+    pub headers: HashMap<String, String>,
+}
+
+impl Request {
+    pub fn parse(head: &str) -> Option<Self> {
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?;
+
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+
+        let headers = lines
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        Some(Self {
+            method,
+            path: path.to_string(),
+            query,
+            headers,
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+// reads up to the end of the request head, None if the client went away or sent garbage
+pub async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_BYTES {
+            return None;
+        }
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+    Request::parse(&String::from_utf8_lossy(&head))
+}
+
+pub async fn write_response(socket: &mut TcpStream, status: &str, content_type: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_path_query_and_headers() {
+        let request = Request::parse(
+            "GET /v1/BTCUSDT/depth?limit=5&x HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\r\n",
+        )
+        .unwrap();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/v1/BTCUSDT/depth");
+        assert_eq!(request.query.get("limit").map(String::as_str), Some("5"));
+        assert_eq!(request.query.get("x").map(String::as_str), Some(""));
+        assert_eq!(request.header("upgrade"), Some("websocket"));
+        assert!(Request::parse("").is_none());
+    }
+}
@@ -0,0 +1,226 @@
+pub mod http;
+pub mod rest;
+pub mod stream;
+
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+use crate::engine::events::BookEvent;
+use crate::engine::state::MarketState;
+
+// Serves the engine's books to other local processes: JSON over plain HTTP
+// for one-off reads, and a websocket at /v1/stream that pushes the deltas and
+// trades the engine applied. Bound up front like the metrics exporter.
+pub struct ApiServer {
+    listener: TcpListener,
+    states: Vec<Arc<MarketState>>,
+    events: broadcast::Sender<BookEvent>,
+}
+
+impl ApiServer {
+    pub async fn bind(
+        addr: &str,
+        states: Vec<Arc<MarketState>>,
+        events: broadcast::Sender<BookEvent>,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind API server to {addr}"))?;
+        Ok(Self {
+            listener,
+            states,
+            events,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    // runs until the task is dropped, open websocket sessions end with the engine
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let Self {
+                listener,
+                states,
+                events,
+            } = self;
+            let states = Arc::new(states);
+            loop {
+                match listener.accept().await {
+                    Ok((socket, _)) => {
+                        tokio::spawn(serve(socket, states.clone(), events.clone()));
+                    }
+                    Err(e) => tracing::warn!("API server failed to accept: {}", e),
+                }
+            }
+        })
+    }
+}
+
+async fn serve(
+    mut socket: TcpStream,
+    states: Arc<Vec<Arc<MarketState>>>,
+    events: broadcast::Sender<BookEvent>,
+) {
+    let Some(request) = http::read_request(&mut socket).await else {
+        return;
+    };
+
+    if request.method == "GET" && request.path == "/v1/stream" {
+        stream::serve(socket, &request, &states, &events).await;
+        return;
+    }
+
+    let (status, body) = rest::handle(&request, &states);
+    http::write_response(&mut socket, status, "application/json", &body).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::{DepthSnapshot, DepthUpdate, Trade};
+    use crate::book::orderbook::OrderBook;
+    use crate::book::scaler::Scaler;
+    use futures_util::StreamExt;
+    use rust_decimal::Decimal;
+    use tokio_tungstenite::tungstenite::Message;
+
+    fn state() -> Arc<MarketState> {
+        let scaler = Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3));
+        let book = OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 7,
+                bids: vec![["100.00".to_string(), "1.500".to_string()]],
+                asks: vec![["100.02".to_string(), "0.250".to_string()]],
+            },
+            &scaler,
+        )
+        .unwrap();
+        let state = MarketState::new(book, "BTCUSDT".to_string(), scaler);
+
+        let mut snapshot = (*state.load()).clone();
+        snapshot.last_update_id = Some(7);
+        snapshot.is_syncing = false;
+        state.snapshot.store(Arc::new(snapshot));
+        Arc::new(state)
+    }
+
+    fn depth(first_update_id: u64, final_update_id: u64) -> BookEvent {
+        BookEvent::Depth {
+            symbol: "BTCUSDT".to_string(),
+            update: DepthUpdate {
+                event_time: 0,
+                first_update_id,
+                final_update_id,
+                prev_final_update_id: None,
+                b: vec![["100.01".to_string(), "1.000".to_string()]],
+                a: vec![],
+            },
+        }
+    }
+
+    async fn next_json(
+        ws: &mut (impl StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin),
+    ) -> serde_json::Value {
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_rest_endpoints_over_http() {
+        let (events, _) = broadcast::channel(16);
+        let server = ApiServer::bind("127.0.0.1:0", vec![state()], events)
+            .await
+            .unwrap();
+        let base = format!("http://{}", server.local_addr().unwrap());
+        let handle = server.spawn();
+
+        let response = reqwest::get(format!("{base}/v1/BTCUSDT/depth"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let depth: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(depth["bids"], serde_json::json!([["100.00", "1.500"]]));
+
+        let missing = reqwest::get(format!("{base}/v1/ETHUSDT/metrics"))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn streams_snapshot_then_newer_deltas_and_trades() {
+        let (events, _) = broadcast::channel(16);
+        let server = ApiServer::bind("127.0.0.1:0", vec![state()], events.clone())
+            .await
+            .unwrap();
+        let url = format!(
+            "ws://{}/v1/stream?symbols=btcusdt",
+            server.local_addr().unwrap()
+        );
+        let handle = server.spawn();
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let snapshot = next_json(&mut ws).await;
+        assert_eq!(snapshot["type"], "snapshot");
+        assert_eq!(snapshot["last_update_id"], 7);
+        assert_eq!(snapshot["asks"], serde_json::json!([["100.02", "0.250"]]));
+
+        // the first delta is already in the snapshot
+        events.send(depth(6, 7)).unwrap();
+        events.send(depth(8, 9)).unwrap();
+        events
+            .send(BookEvent::Trade {
+                symbol: "BTCUSDT".to_string(),
+                trade: Trade {
+                    price: Decimal::new(10001, 2),
+                    quantity: Decimal::ONE,
+                    trade_time: 5,
+                    is_buyer_maker: true,
+                },
+            })
+            .unwrap();
+
+        let delta = next_json(&mut ws).await;
+        assert_eq!(delta["type"], "depth");
+        assert_eq!(delta["final_update_id"], 9);
+        assert_eq!(delta["bids"], serde_json::json!([["100.01", "1.000"]]));
+
+        let trade = next_json(&mut ws).await;
+        assert_eq!(trade["type"], "trade");
+        assert_eq!(trade["side"], "SELL");
+        assert_eq!(trade["price"], "100.01");
+
+        events
+            .send(BookEvent::Reset {
+                symbol: "BTCUSDT".to_string(),
+            })
+            .unwrap();
+        assert_eq!(next_json(&mut ws).await["type"], "snapshot");
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn rejects_streams_for_unknown_symbols() {
+        let (events, _) = broadcast::channel(16);
+        let server = ApiServer::bind("127.0.0.1:0", vec![state()], events)
+            .await
+            .unwrap();
+        let url = format!(
+            "ws://{}/v1/stream?symbols=ETHUSDT",
+            server.local_addr().unwrap()
+        );
+        let handle = server.spawn();
+
+        assert!(tokio_tungstenite::connect_async(url).await.is_err());
+        handle.abort();
+    }
+}
@@ -0,0 +1,244 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::api::http::Request;
+use crate::binance::types::Trade;
+use crate::book::health::BookHealth;
+use crate::engine::metrics::MarketMetrics;
+use crate::engine::state::{DisplayDepthLevel, MarketSnapshot, MarketState};
+
+const DEFAULT_DEPTH_LIMIT: usize = 20;
+const DEFAULT_TAKER_ORDERS_LIMIT: usize = 100;
+
+#[derive(Serialize)]
+struct SymbolRecord<'a> {
+    symbol: &'a str,
+    syncing: bool,
+    health: BookHealth,
+}
+
+// the top of one symbol's book, also the websocket's snapshot message
+#[derive(Serialize)]
+pub struct BookRecord<'a> {
+    pub symbol: &'a str,
+    pub last_update_id: Option<u64>,
+    pub syncing: bool,
+    pub health: BookHealth,
+    pub bids: Vec<DisplayDepthLevel>,
+    pub asks: Vec<DisplayDepthLevel>,
+}
+
+impl<'a> BookRecord<'a> {
+    pub fn new(state: &'a MarketState, snapshot: &MarketSnapshot, limit: usize) -> Self {
+        let (bids, asks) = snapshot.top_n_depth(limit, &state.scaler);
+        Self {
+            symbol: &state.symbol,
+            last_update_id: snapshot.last_update_id,
+            syncing: snapshot.is_syncing,
+            health: snapshot.health,
+            bids,
+            asks,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MetricsRecord<'a> {
+    symbol: &'a str,
+    metrics: &'a MarketMetrics,
+}
+
+#[derive(Serialize)]
+pub struct TradeRecord {
+    pub trade_time: u64,
+    pub side: String,
+    pub price: Decimal,
+    pub qty: Decimal,
+}
+
+impl From<&Trade> for TradeRecord {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            trade_time: trade.trade_time,
+            side: trade.side().to_string(),
+            price: trade.price,
+            qty: trade.quantity,
+        }
+    }
+}
+
+// same-time, same-side prints merged into one order at its average price,
+// unlike the websocket's `trade` messages which are single prints
+#[derive(Serialize)]
+struct TakerOrdersRecord<'a> {
+    symbol: &'a str,
+    // newest first
+    taker_orders: Vec<TradeRecord>,
+}
+
+// (status line, JSON body) for every request that isn't a websocket upgrade
+pub fn handle(request: &Request, states: &[Arc<MarketState>]) -> (&'static str, String) {
+    if request.method != "GET" {
+        return error("405 Method Not Allowed", "only GET is supported");
+    }
+
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["v1", "symbols"] => {
+            let records: Vec<SymbolRecord> = states
+                .iter()
+                .map(|state| {
+                    let snapshot = state.load();
+                    SymbolRecord {
+                        symbol: &state.symbol,
+                        syncing: snapshot.is_syncing,
+                        health: snapshot.health,
+                    }
+                })
+                .collect();
+            ok(&records)
+        }
+        ["v1", symbol, endpoint] => {
+            let Some(state) = find_symbol(states, symbol) else {
+                return error("404 Not Found", &format!("unknown symbol {symbol}"));
+            };
+            let snapshot = state.load();
+            match *endpoint {
+                "depth" => match limit(request, DEFAULT_DEPTH_LIMIT) {
+                    Ok(limit) => ok(&BookRecord::new(state, &snapshot, limit)),
+                    Err(response) => response,
+                },
+                "metrics" => ok(&MetricsRecord {
+                    symbol: &state.symbol,
+                    metrics: &snapshot.metrics,
+                }),
+                "taker_orders" => match limit(request, DEFAULT_TAKER_ORDERS_LIMIT) {
+                    Ok(limit) => ok(&TakerOrdersRecord {
+                        symbol: &state.symbol,
+                        taker_orders: snapshot
+                            .recent_trades
+                            .iter()
+                            .rev()
+                            .take(limit)
+                            .map(TradeRecord::from)
+                            .collect(),
+                    }),
+                    Err(response) => response,
+                },
+                _ => error("404 Not Found", "no such endpoint"),
+            }
+        }
+        _ => error("404 Not Found", "no such endpoint"),
+    }
+}
+
+pub fn find_symbol<'a>(states: &'a [Arc<MarketState>], symbol: &str) -> Option<&'a MarketState> {
+    states
+        .iter()
+        .find(|state| state.symbol.eq_ignore_ascii_case(symbol))
+        .map(Arc::as_ref)
+}
+
+fn limit(request: &Request, default: usize) -> Result<usize, (&'static str, String)> {
+    match request.query.get("limit") {
+        None => Ok(default),
+        Some(value) => match value.parse::<usize>() {
+            Ok(limit) if limit > 0 => Ok(limit),
+            _ => Err(error("400 Bad Request", "limit must be a positive integer")),
+        },
+    }
+}
+
+fn ok(body: &impl Serialize) -> (&'static str, String) {
+    match serde_json::to_string(body) {
+        Ok(body) => ("200 OK", body),
+        Err(e) => error("500 Internal Server Error", &e.to_string()),
+    }
+}
+
+pub fn error(status: &'static str, message: &str) -> (&'static str, String) {
+    (status, serde_json::json!({ "error": message }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+    use crate::book::orderbook::OrderBook;
+    use crate::book::scaler::Scaler;
+
+    fn state() -> Arc<MarketState> {
+        let scaler = Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3));
+        let book = OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 7,
+                bids: vec![
+                    ["100.00".to_string(), "1.500".to_string()],
+                    ["99.99".to_string(), "2.000".to_string()],
+                ],
+                asks: vec![["100.02".to_string(), "0.250".to_string()]],
+            },
+            &scaler,
+        )
+        .unwrap();
+        let state = MarketState::new(book, "BTCUSDT".to_string(), scaler);
+
+        let mut snapshot = (*state.load()).clone();
+        snapshot.last_update_id = Some(7);
+        snapshot.is_syncing = false;
+        for (price, trade_time) in [(10000, 1), (10001, 2)] {
+            snapshot.recent_trades.push_back(Trade {
+                price: Decimal::new(price, 2),
+                quantity: Decimal::ONE,
+                trade_time,
+                is_buyer_maker: false,
+            });
+        }
+        state.snapshot.store(Arc::new(snapshot));
+        Arc::new(state)
+    }
+
+    fn get(target: &str, states: &[Arc<MarketState>]) -> (&'static str, serde_json::Value) {
+        let request = Request::parse(&format!("GET {target} HTTP/1.1\r\n\r\n")).unwrap();
+        let (status, body) = handle(&request, states);
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[test]
+    fn serves_depth_taker_orders_and_metrics() {
+        let states = vec![state()];
+
+        let (status, depth) = get("/v1/btcusdt/depth?limit=1", &states);
+        assert_eq!(status, "200 OK");
+        assert_eq!(depth["last_update_id"], 7);
+        assert_eq!(depth["syncing"], false);
+        assert_eq!(depth["bids"], serde_json::json!([["100.00", "1.500"]]));
+        assert_eq!(depth["asks"], serde_json::json!([["100.02", "0.250"]]));
+
+        let (_, orders) = get("/v1/BTCUSDT/taker_orders?limit=1", &states);
+        assert_eq!(orders["taker_orders"][0]["price"], "100.01");
+        assert_eq!(orders["taker_orders"][0]["side"], "BUY");
+        assert_eq!(orders["taker_orders"].as_array().unwrap().len(), 1);
+        // raw prints are only streamed, so there is no endpoint that mixes the two
+        assert_eq!(get("/v1/BTCUSDT/trades", &states).0, "404 Not Found");
+
+        let (status, metrics) = get("/v1/BTCUSDT/metrics", &states);
+        assert_eq!(status, "200 OK");
+        assert_eq!(metrics["metrics"]["total_trades"], 0);
+
+        let (_, symbols) = get("/v1/symbols", &states);
+        assert_eq!(symbols[0]["symbol"], "BTCUSDT");
+    }
+
+    #[test]
+    fn rejects_unknown_symbols_and_bad_limits() {
+        let states = vec![state()];
+
+        assert_eq!(get("/v1/ETHUSDT/depth", &states).0, "404 Not Found");
+        assert_eq!(get("/v1/BTCUSDT/candles", &states).0, "404 Not Found");
+        let (status, body) = get("/v1/BTCUSDT/depth?limit=0", &states);
+        assert_eq!(status, "400 Bad Request");
+        assert_eq!(body["error"], "limit must be a positive integer");
+    }
+}
@@ -0,0 +1,210 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+
+use crate::api::http::{self, Request};
+use crate::api::rest::{self, BookRecord, TradeRecord};
+use crate::binance::types::DepthUpdate;
+use crate::engine::events::BookEvent;
+use crate::engine::state::MarketState;
+
+// Everything a stream client is sent. A client keeps its own copy of the book
+// by applying `depth` messages to the last `snapshot` of that symbol, the same
+// way the engine applies Binance's diff stream to a REST snapshot.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamMessage<'a> {
+    Snapshot(BookRecord<'a>),
+    Depth {
+        symbol: &'a str,
+        first_update_id: u64,
+        final_update_id: u64,
+        // [price, qty] as sent by Binance, a qty of 0 removes the level
+        bids: &'a [[String; 2]],
+        asks: &'a [[String; 2]],
+    },
+    // one print as received from Binance, never clustered into taker orders
+    Trade {
+        symbol: &'a str,
+        #[serde(flatten)]
+        trade: TradeRecord,
+    },
+}
+
+// `GET /v1/stream?symbols=BTCUSDT,ETHUSDT`, every symbol when none are given
+pub async fn serve(
+    mut socket: TcpStream,
+    request: &Request,
+    states: &[Arc<MarketState>],
+    events: &broadcast::Sender<BookEvent>,
+) {
+    let subscribed = match subscribed_symbols(request, states) {
+        Ok(subscribed) => subscribed,
+        Err((status, body)) => {
+            http::write_response(&mut socket, status, "application/json", &body).await;
+            return;
+        }
+    };
+
+    let key = match (
+        request.header("upgrade"),
+        request.header("sec-websocket-key"),
+    ) {
+        (Some(upgrade), Some(key)) if upgrade.eq_ignore_ascii_case("websocket") => key,
+        _ => {
+            let (status, body) =
+                rest::error("426 Upgrade Required", "expected a websocket upgrade");
+            http::write_response(&mut socket, status, "application/json", &body).await;
+            return;
+        }
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    );
+    if socket.write_all(response.as_bytes()).await.is_err() {
+        return;
+    }
+
+    let ws = WebSocketStream::from_raw_socket(socket, Role::Server, None).await;
+    let symbols: Vec<&str> = subscribed.iter().map(|s| s.symbol.as_str()).collect();
+    tracing::info!("API stream client subscribed to {}", symbols.join(","));
+    if let Err(e) = Session::new(ws, subscribed).run(events.subscribe()).await {
+        tracing::debug!("API stream client dropped: {}", e);
+    }
+}
+
+fn subscribed_symbols<'a>(
+    request: &Request,
+    states: &'a [Arc<MarketState>],
+) -> Result<Vec<&'a MarketState>, (&'static str, String)> {
+    let Some(symbols) = request.query.get("symbols").filter(|s| !s.is_empty()) else {
+        return Ok(states.iter().map(Arc::as_ref).collect());
+    };
+    symbols
+        .split(',')
+        .map(|symbol| {
+            rest::find_symbol(states, symbol)
+                .ok_or_else(|| rest::error("404 Not Found", &format!("unknown symbol {symbol}")))
+        })
+        .collect()
+}
+
+struct Session<'a> {
+    ws: WebSocketStream<TcpStream>,
+    subscribed: Vec<&'a MarketState>,
+    // last update id of the snapshot each symbol's client book started from
+    snapshot_ids: HashMap<&'a str, Option<u64>>,
+}
+
+impl<'a> Session<'a> {
+    fn new(ws: WebSocketStream<TcpStream>, subscribed: Vec<&'a MarketState>) -> Self {
+        Self {
+            ws,
+            subscribed,
+            snapshot_ids: HashMap::new(),
+        }
+    }
+
+    // Subscribed before the snapshots are taken, and the engine publishes a
+    // snapshot before emitting the deltas it contains, so every delta is either
+    // part of the snapshot sent or still to be received
+    async fn run(mut self, mut events: broadcast::Receiver<BookEvent>) -> anyhow::Result<()> {
+        for state in self.subscribed.clone() {
+            self.send_snapshot(state).await?;
+        }
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.forward(event).await?,
+                    Err(RecvError::Lagged(missed)) => {
+                        // the client's books are missing deltas, start them over
+                        tracing::warn!("API stream client fell {} events behind, resending snapshots", missed);
+                        for state in self.subscribed.clone() {
+                            self.send_snapshot(state).await?;
+                        }
+                    }
+                    Err(RecvError::Closed) => {
+                        let _ = self.ws.close(None).await;
+                        return Ok(());
+                    }
+                },
+                incoming = self.ws.next() => match incoming {
+                    None | Some(Ok(Message::Close(_))) => return Ok(()),
+                    Some(Err(e)) => return Err(e.into()),
+                    // pings are answered by tungstenite, anything else is ignored
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+
+    async fn forward(&mut self, event: BookEvent) -> anyhow::Result<()> {
+        match event {
+            BookEvent::Depth { symbol, update } => {
+                let Some(&snapshot_id) = self.snapshot_ids.get(symbol.as_str()) else {
+                    return Ok(());
+                };
+                // still waiting for the engine's snapshot, or already part of the one sent
+                if snapshot_id.is_none_or(|id| update.final_update_id <= id) {
+                    return Ok(());
+                }
+                self.send(&depth_message(&symbol, &update)).await
+            }
+            BookEvent::Trade { symbol, trade } => {
+                if !self.snapshot_ids.contains_key(symbol.as_str()) {
+                    return Ok(());
+                }
+                let message = StreamMessage::Trade {
+                    symbol: &symbol,
+                    trade: TradeRecord::from(&trade),
+                };
+                self.send(&message).await
+            }
+            BookEvent::Reset { symbol } => {
+                let state = self.subscribed.iter().find(|s| s.symbol == symbol).copied();
+                match state {
+                    Some(state) => self.send_snapshot(state).await,
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    async fn send_snapshot(&mut self, state: &'a MarketState) -> anyhow::Result<()> {
+        let snapshot = state.load();
+        let levels = snapshot.book.bids().len().max(snapshot.book.asks().len());
+        self.snapshot_ids
+            .insert(&state.symbol, snapshot.last_update_id);
+        self.send(&StreamMessage::Snapshot(BookRecord::new(
+            state, &snapshot, levels,
+        )))
+        .await
+    }
+
+    async fn send(&mut self, message: &StreamMessage<'_>) -> anyhow::Result<()> {
+        let text = serde_json::to_string(message)?;
+        self.ws.send(Message::text(text)).await?;
+        Ok(())
+    }
+}
+
+fn depth_message<'a>(symbol: &'a str, update: &'a DepthUpdate) -> StreamMessage<'a> {
+    StreamMessage::Depth {
+        symbol,
+        first_update_id: update.first_update_id,
+        final_update_id: update.final_update_id,
+        bids: &update.b,
+        asks: &update.a,
+    }
+}
@@ -2,16 +2,15 @@ use anyhow::{Context, Result};
 use rust_decimal::Decimal;
 use serde::Deserialize;
 
-const EXCHANGE_INFO_URL: &str = "https://api.binance.com/api/v3/exchangeInfo";
+use crate::binance::market::Market;
 
 #[derive(Debug, Deserialize)]
 struct ExchangeInfoResponse {
-    symbols: Vec<SymbolInfo>,
+    symbols: Vec<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
 struct SymbolInfo {
-    symbol: String,
     filters: Vec<Filter>,
 }
 
@@ -25,8 +24,18 @@ struct Filter {
     step_size: Option<String>,
 }
 
-pub async fn fetch_tick_and_step_sizes(symbol: &str) -> Result<(Decimal, Decimal)> {
-    let url = format!("{}?symbol={}", EXCHANGE_INFO_URL, symbol.to_uppercase());
+// the raw exchangeInfo entry for one symbol
+async fn fetch_symbol_info(
+    base_url: &str,
+    market: Market,
+    symbol: &str,
+) -> Result<serde_json::Value> {
+    let url = format!(
+        "{}{}?symbol={}",
+        base_url,
+        market.exchange_info_path(),
+        symbol.to_uppercase()
+    );
     let response = reqwest::get(&url).await?;
     let json_value: serde_json::Value = response.json().await?;
 
@@ -41,11 +50,37 @@ pub async fn fetch_tick_and_step_sizes(symbol: &str) -> Result<(Decimal, Decimal
     let info: ExchangeInfoResponse =
         serde_json::from_value(json_value).context("Failed to parse exchange info response")?;
 
-    let symbol_info = info
-        .symbols
+    info.symbols
         .into_iter()
-        .find(|s| s.symbol.eq_ignore_ascii_case(symbol))
-        .with_context(|| format!("Symbol \"{}\" not found in exchange info", symbol))?;
+        .find(|s| {
+            s.get("symbol")
+                .and_then(|name| name.as_str())
+                .is_some_and(|name| name.eq_ignore_ascii_case(symbol))
+        })
+        .with_context(|| format!("Symbol \"{}\" not found in exchange info", symbol))
+}
+
+// every filter (PRICE_FILTER, LOT_SIZE, ...) as returned by the exchange
+pub async fn fetch_symbol_filters(
+    base_url: &str,
+    market: Market,
+    symbol: &str,
+) -> Result<Vec<serde_json::Value>> {
+    let symbol_info = fetch_symbol_info(base_url, market, symbol).await?;
+    match symbol_info.get("filters") {
+        Some(serde_json::Value::Array(filters)) => Ok(filters.clone()),
+        _ => anyhow::bail!("No filters found for symbol \"{}\"", symbol),
+    }
+}
+
+pub async fn fetch_tick_and_step_sizes(
+    base_url: &str,
+    market: Market,
+    symbol: &str,
+) -> Result<(Decimal, Decimal)> {
+    let symbol_info: SymbolInfo =
+        serde_json::from_value(fetch_symbol_info(base_url, market, symbol).await?)
+            .context("Failed to parse exchange info response")?;
 
     let tick_size_str = symbol_info
         .filters
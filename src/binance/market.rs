@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+// which Binance product the terminal connects to. spot and the two futures
+// markets share message formats but live on different hosts and paths
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Market {
+    #[default]
+    Spot,
+    // USDⓈ-M perpetuals and futures (fapi)
+    UsdM,
+    // COIN-M perpetuals and futures (dapi)
+    CoinM,
+}
+
+impl Market {
+    pub fn is_futures(self) -> bool {
+        !matches!(self, Market::Spot)
+    }
+
+    pub fn rest_base_url(self) -> &'static str {
+        match self {
+            Market::Spot => "https://api.binance.com",
+            Market::UsdM => "https://fapi.binance.com",
+            Market::CoinM => "https://dapi.binance.com",
+        }
+    }
+
+    pub fn ws_base_url(self) -> &'static str {
+        match self {
+            Market::Spot => "wss://stream.binance.com:9443",
+            Market::UsdM => "wss://fstream.binance.com",
+            Market::CoinM => "wss://dstream.binance.com",
+        }
+    }
+
+    pub fn depth_snapshot_path(self) -> &'static str {
+        match self {
+            Market::Spot => "/api/v3/depth",
+            Market::UsdM => "/fapi/v1/depth",
+            Market::CoinM => "/dapi/v1/depth",
+        }
+    }
+
+    pub fn exchange_info_path(self) -> &'static str {
+        match self {
+            Market::Spot => "/api/v3/exchangeInfo",
+            Market::UsdM => "/fapi/v1/exchangeInfo",
+            Market::CoinM => "/dapi/v1/exchangeInfo",
+        }
+    }
+
+    // futures depth endpoints reject limits above 1000
+    pub fn max_snapshot_depth(self) -> u16 {
+        match self {
+            Market::Spot => 5000,
+            Market::UsdM | Market::CoinM => 1000,
+        }
+    }
+
+    // the only `limit` values the depth endpoints accept
+    pub fn snapshot_depth_limits(self) -> &'static [u16] {
+        match self {
+            Market::Spot => &[5, 10, 20, 50, 100, 500, 1000, 5000],
+            Market::UsdM | Market::CoinM => &[5, 10, 20, 50, 100, 500, 1000],
+        }
+    }
+
+    // stream names subscribed per symbol. futures only publish aggregate trades
+    pub fn streams(self, symbol: &str, trades: TradeStream) -> Vec<String> {
+        let symbol = symbol.to_lowercase();
+        match self {
+            Market::Spot => vec![
+                format!("{}@depth@100ms", symbol),
+                format!("{}@{}", symbol, trades.stream_name()),
+            ],
+            Market::UsdM | Market::CoinM => vec![
+                format!("{}@depth@100ms", symbol),
+                format!("{}@aggTrade", symbol),
+                format!("{}@markPrice@1s", symbol),
+            ],
+        }
+    }
+}
+
+impl std::fmt::Display for Market {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Market::Spot => write!(f, "Spot"),
+            Market::UsdM => write!(f, "USD-M"),
+            Market::CoinM => write!(f, "COIN-M"),
+        }
+    }
+}
+
+// spot trade stream: every individual print, or prints aggregated per taker
+// order and price level
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeStream {
+    #[default]
+    Trade,
+    AggTrade,
+}
+
+impl TradeStream {
+    fn stream_name(self) -> &'static str {
+        match self {
+            TradeStream::Trade => "trade",
+            TradeStream::AggTrade => "aggTrade",
+        }
+    }
+}
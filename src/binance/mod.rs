@@ -1,4 +1,6 @@
 pub mod exchange_info;
+pub mod market;
 pub mod snapshot;
+pub mod source;
 pub mod stream;
 pub mod types;
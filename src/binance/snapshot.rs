@@ -1,10 +1,20 @@
+use crate::binance::market::Market;
 use crate::binance::types::DepthSnapshot;
 use anyhow::Result;
 
-const DEPTH_SNAPSHOT_URL: &str = "https://api.binance.com/api/v3/depth";
-
-pub async fn fetch_snapshot(symbol: &str, limit: u16) -> Result<DepthSnapshot> {
-    let url = format!("{}?symbol={}&limit={}", DEPTH_SNAPSHOT_URL, symbol, limit);
+pub async fn fetch_snapshot(
+    base_url: &str,
+    market: Market,
+    symbol: &str,
+    limit: u16,
+) -> Result<DepthSnapshot> {
+    let url = format!(
+        "{}{}?symbol={}&limit={}",
+        base_url,
+        market.depth_snapshot_path(),
+        symbol,
+        limit.min(market.max_snapshot_depth())
+    );
     let response = reqwest::get(&url).await?;
 
     let json_value: serde_json::Value = response.json().await?;
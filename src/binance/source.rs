@@ -0,0 +1,114 @@
+use anyhow::Result;
+use futures_util::StreamExt;
+
+use crate::binance::market::{Market, TradeStream};
+use crate::binance::types::DepthSnapshot;
+use crate::binance::{exchange_info, snapshot, stream};
+use crate::config::Config;
+use crate::recording::recorder::Recorder;
+use crate::source::{EventStream, InstrumentInfo, MarketDataSource};
+
+pub struct BinanceSource {
+    market: Market,
+    trades: TradeStream,
+    rest_base_url: String,
+    ws_base_url: String,
+    recorder: Option<Recorder>,
+}
+
+impl BinanceSource {
+    pub fn new(
+        market: Market,
+        rest_base_url: impl Into<String>,
+        ws_base_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            market,
+            trades: TradeStream::default(),
+            rest_base_url: rest_base_url.into(),
+            ws_base_url: ws_base_url.into(),
+            recorder: None,
+        }
+    }
+
+    // production endpoints of the configured market unless overridden
+    pub fn from_config(conf: &Config) -> Self {
+        let market = conf.market;
+        Self::new(
+            market,
+            conf.rest_base_url
+                .as_deref()
+                .unwrap_or(market.rest_base_url()),
+            conf.ws_base_url.as_deref().unwrap_or(market.ws_base_url()),
+        )
+        .with_trade_stream(conf.trade_stream)
+    }
+
+    pub fn market(&self) -> Market {
+        self.market
+    }
+
+    pub async fn fetch_symbol_filters(&self, symbol: &str) -> Result<Vec<serde_json::Value>> {
+        exchange_info::fetch_symbol_filters(&self.rest_base_url, self.market, symbol).await
+    }
+
+    // which spot trade stream to subscribe to, futures always use aggTrade
+    pub fn with_trade_stream(mut self, trades: TradeStream) -> Self {
+        self.trades = trades;
+        self
+    }
+
+    // record raw frames, snapshots and instrument metadata for later replay
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+}
+
+impl Default for BinanceSource {
+    fn default() -> Self {
+        let market = Market::default();
+        Self::new(market, market.rest_base_url(), market.ws_base_url())
+    }
+}
+
+impl MarketDataSource for BinanceSource {
+    async fn fetch_snapshot(&self, symbol: &str, limit: u16) -> Result<DepthSnapshot> {
+        let snapshot =
+            snapshot::fetch_snapshot(&self.rest_base_url, self.market, symbol, limit).await?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record_snapshot(symbol, &snapshot);
+        }
+        Ok(snapshot)
+    }
+
+    // replay would apply a recorded audit snapshot as a resync
+    async fn fetch_audit_snapshot(&self, symbol: &str, limit: u16) -> Result<DepthSnapshot> {
+        snapshot::fetch_snapshot(&self.rest_base_url, self.market, symbol, limit).await
+    }
+
+    async fn fetch_instrument(&self, symbol: &str) -> Result<InstrumentInfo> {
+        let (tick_size, step_size) =
+            exchange_info::fetch_tick_and_step_sizes(&self.rest_base_url, self.market, symbol)
+                .await?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record_instrument(symbol, tick_size, step_size);
+        }
+        Ok(InstrumentInfo {
+            tick_size,
+            step_size,
+        })
+    }
+
+    async fn connect(&self, symbols: &[String]) -> Result<EventStream> {
+        let stream = stream::connect_market_stream(
+            &self.ws_base_url,
+            self.market,
+            self.trades,
+            symbols,
+            self.recorder.clone(),
+        )
+        .await?;
+        Ok(stream.boxed())
+    }
+}
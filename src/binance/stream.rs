@@ -1,52 +1,85 @@
+use crate::binance::market::{Market, TradeStream};
 use crate::binance::types::{
-    CombinedStreamMessage, DepthUpdate, MarketEvent, ReceivedDepthUpdate, ReceivedTrade, Trade,
+    CombinedStreamMessage, DepthUpdate, MarkPriceUpdate, MarketEvent, ReceivedDepthUpdate,
+    ReceivedMarkPrice, ReceivedTrade, Trade,
 };
+use crate::recording::recorder::Recorder;
 use anyhow::Result;
 use futures_util::StreamExt;
+use std::time;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
-// connect to a combined stream that delivrs both depth updates and trades
+// connect to a combined stream that delivrs both depth updates and trades for every symbol
+// (plus mark price on futures). raw frames are handed to the recorder (if any) before parsing
 pub async fn connect_market_stream(
-    symbol: &str,
-) -> Result<impl StreamExt<Item = Result<MarketEvent>>> {
-    let symbol_lower = symbol.to_lowercase();
-    let url = format!(
-        "wss://stream.binance.com:9443/stream?streams={}@depth@100ms/{}@trade",
-        symbol_lower, symbol_lower
-    );
+    base_url: &str,
+    market: Market,
+    trades: TradeStream,
+    symbols: &[String],
+    recorder: Option<Recorder>,
+) -> Result<impl StreamExt<Item = Result<MarketEvent>> + use<>> {
+    let streams = symbols
+        .iter()
+        .flat_map(|symbol| market.streams(symbol, trades))
+        .collect::<Vec<_>>()
+        .join("/");
+    let url = format!("{}/stream?streams={}", base_url, streams);
     let (ws_stream, _) = connect_async(url).await?;
     let (_, read) = ws_stream.split();
 
-    Ok(read.filter_map(|msg| async move {
-        match msg {
-            Ok(Message::Text(text)) => {
-                let received_at = std::time::Instant::now();
-                let combined: CombinedStreamMessage = match serde_json::from_str(&text) {
-                    Ok(c) => c,
-                    Err(e) => return Some(Err(e.into())),
-                };
-
-                if combined.stream.ends_with("@depth@100ms") {
-                    match serde_json::from_value::<DepthUpdate>(combined.data) {
-                        Ok(update) => Some(Ok(MarketEvent::Depth(ReceivedDepthUpdate {
-                            update,
-                            received_at,
-                        }))),
-                        Err(e) => Some(Err(e.into())),
-                    }
-                } else if combined.stream.ends_with("@trade") {
-                    match serde_json::from_value::<Trade>(combined.data) {
-                        Ok(trade) => {
-                            Some(Ok(MarketEvent::Trade(ReceivedTrade { trade, received_at })))
-                        }
-                        Err(e) => Some(Err(e.into())),
+    Ok(read.filter_map(move |msg| {
+        let recorder = recorder.clone();
+        async move {
+            match msg {
+                Ok(Message::Text(text)) => {
+                    let received_at = time::Instant::now();
+                    if let Some(recorder) = &recorder {
+                        recorder.record_frame(&text);
                     }
-                } else {
-                    tracing::warn!("Unknown stream type: {}", combined.stream);
-                    None
+                    parse_frame(&text, received_at)
                 }
+                _ => None,
             }
-            _ => None,
         }
     }))
 }
+
+// parse one raw combined stream frame, None for stream types we don't handle
+pub fn parse_frame(text: &str, received_at: time::Instant) -> Option<Result<MarketEvent>> {
+    let combined: CombinedStreamMessage = match serde_json::from_str(text) {
+        Ok(c) => c,
+        Err(e) => return Some(Err(e.into())),
+    };
+    let symbol = combined.symbol().to_string();
+
+    if combined.stream.ends_with("@depth@100ms") {
+        match serde_json::from_value::<DepthUpdate>(combined.data) {
+            Ok(update) => Some(Ok(MarketEvent::Depth(ReceivedDepthUpdate {
+                symbol,
+                update,
+                received_at,
+            }))),
+            Err(e) => Some(Err(e.into())),
+        }
+    } else if combined.stream.ends_with("@trade") || combined.stream.ends_with("@aggTrade") {
+        match serde_json::from_value::<Trade>(combined.data) {
+            Ok(trade) => Some(Ok(MarketEvent::Trade(ReceivedTrade {
+                symbol,
+                trade,
+                received_at,
+            }))),
+            Err(e) => Some(Err(e.into())),
+        }
+    } else if combined.stream.contains("@markPrice") {
+        match serde_json::from_value::<MarkPriceUpdate>(combined.data) {
+            Ok(update) => Some(Ok(MarketEvent::MarkPrice(ReceivedMarkPrice {
+                symbol,
+                update,
+            }))),
+            Err(e) => Some(Err(e.into())),
+        }
+    } else {
+        tracing::warn!("Unknown stream type: {}", combined.stream);
+        None
+    }
+}
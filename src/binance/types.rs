@@ -1,9 +1,9 @@
 use rand::Rng;
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::time;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DepthSnapshot {
     #[serde(rename = "lastUpdateId")]
     pub last_update_id: u64,
@@ -46,7 +46,7 @@ impl DepthSnapshot {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct DepthUpdate {
     #[serde(rename = "E")]
     pub event_time: u64,
@@ -54,12 +54,16 @@ pub struct DepthUpdate {
     pub first_update_id: u64,
     #[serde(rename = "u")]
     pub final_update_id: u64,
+    // futures only, final update id of the previous event on this stream
+    #[serde(rename = "pu", default)]
+    pub prev_final_update_id: Option<u64>,
     pub b: Vec<[String; 2]>, // bids
     pub a: Vec<[String; 2]>, // asks
 }
 
 #[derive(Debug)]
 pub struct ReceivedDepthUpdate {
+    pub symbol: String,
     pub update: DepthUpdate,
     pub received_at: time::Instant,
 }
@@ -112,6 +116,7 @@ impl DepthUpdate {
             event_time: 0,
             first_update_id: last_update_id + 1,
             final_update_id: last_update_id + n_levels as u64 - 1,
+            prev_final_update_id: None,
             b: bids,
             a: asks,
         }
@@ -145,6 +150,7 @@ pub struct Trade {
 
 #[derive(Debug, Clone)]
 pub struct ReceivedTrade {
+    pub symbol: String,
     pub trade: Trade,
     pub received_at: time::Instant,
 }
@@ -166,21 +172,76 @@ pub struct CombinedStreamMessage {
     pub data: serde_json::Value,
 }
 
+impl CombinedStreamMessage {
+    // stream names look like "btcusdt@depth@100ms", symbol is everything before the first '@'
+    pub fn symbol(&self) -> &str {
+        self.stream.split('@').next().unwrap_or_default()
+    }
+}
+
+// futures <symbol>@markPrice stream
+#[derive(Debug, Deserialize, Clone)]
+pub struct MarkPriceUpdate {
+    #[serde(rename = "p")]
+    pub mark_price: Decimal,
+    #[serde(rename = "i")]
+    pub index_price: Decimal,
+    // empty for delivery contracts, which have no funding
+    #[serde(rename = "r", default, deserialize_with = "decimal_or_empty")]
+    pub funding_rate: Option<Decimal>,
+    #[serde(rename = "T")]
+    pub next_funding_time: u64,
+}
+
+fn decimal_or_empty<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    if s.is_empty() {
+        return Ok(None);
+    }
+    s.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug)]
+pub struct ReceivedMarkPrice {
+    pub symbol: String,
+    pub update: MarkPriceUpdate,
+}
+
+// depth snapshot delivered in-band, e.g. when replaying a recording
+#[derive(Debug)]
+pub struct ReceivedSnapshot {
+    pub symbol: String,
+    pub snapshot: DepthSnapshot,
+}
+
 #[derive(Debug)]
 pub enum MarketEvent {
     Depth(ReceivedDepthUpdate),
     Trade(ReceivedTrade),
+    MarkPrice(ReceivedMarkPrice),
+    Snapshot(ReceivedSnapshot),
 }
 
 #[derive(Clone, Debug)]
 pub enum SignificanceReason {
     HighVolumePercent(f64), // % of 1min volume
+    LargeNotional,
+    SizeZScore(f64),   // std devs above the mean 1min trade size
+    LevelSweep(usize), // resting levels the trade went through
+    FarFromMid(f64),   // bps from the mid
 }
 
 impl SignificanceReason {
     pub fn display(&self) -> String {
         match self {
             SignificanceReason::HighVolumePercent(pct) => format!("Vol: {:.1}%", pct),
+            SignificanceReason::LargeNotional => "Notional".to_string(),
+            SignificanceReason::SizeZScore(z) => format!("Z: {:.1}", z),
+            SignificanceReason::LevelSweep(levels) => format!("Sweep: {}", levels),
+            SignificanceReason::FarFromMid(bps) => format!("Mid: {:.1}bp", bps),
         }
     }
 }
@@ -189,18 +250,49 @@ impl SignificanceReason {
 pub struct SignificantTrade {
     pub trade: Trade,
     pub notional_value: Decimal,
-    pub significance_reason: SignificanceReason,
+    // every rule the trade matched, never empty
+    pub significance_reasons: Vec<SignificanceReason>,
+    // counts up per symbol, so readers can tell which trades they haven't seen
+    // even when several share a trade time. A regrown order keeps its number.
+    pub seq: u64,
 }
 
 impl SignificantTrade {
-    pub fn new(trade: Trade, notional_value: Decimal, reason: SignificanceReason) -> Self {
+    pub fn new(
+        trade: Trade,
+        notional_value: Decimal,
+        reasons: Vec<SignificanceReason>,
+        seq: u64,
+    ) -> Self {
         Self {
             trade,
             notional_value,
-            significance_reason: reason,
+            significance_reasons: reasons,
+            seq,
+        }
+    }
+
+    // first matched reason, with a count of the others
+    pub fn short_reason(&self) -> String {
+        let first = self
+            .significance_reasons
+            .first()
+            .map(|r| r.display())
+            .unwrap_or_default();
+        match self.significance_reasons.len() {
+            0 | 1 => first,
+            n => format!("{} +{}", first, n - 1),
         }
     }
 
+    pub fn reasons(&self) -> String {
+        self.significance_reasons
+            .iter()
+            .map(|r| r.display())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     pub fn side(&self) -> Side {
         self.trade.side()
     }
@@ -0,0 +1,392 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::ops::RangeBounds;
+
+use crate::binance::types::{DepthSnapshot, DepthUpdate};
+use crate::book::orderbook::{BookSide, OrderBook};
+use crate::book::scaler::Scaler;
+
+// applied deltas kept around to roll a REST snapshot forward to the local book's update id
+const HISTORY_LEN: usize = 512;
+
+// a price level where the local book and the reference disagree, None = level missing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelMismatch {
+    pub side: BookSide,
+    pub price: u64,
+    pub local_qty: Option<u64>,
+    pub reference_qty: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct AuditReport {
+    pub update_id: u64,
+    pub levels_compared: usize,
+    pub mismatches: Vec<LevelMismatch>,
+}
+
+#[derive(Debug)]
+pub enum AuditOutcome {
+    // no audit snapshot waiting
+    Idle,
+    // the snapshot is ahead of the local book, checked again after the next delta
+    Waiting,
+    // the delta history no longer reaches back to the snapshot, audit dropped
+    Unaligned { snapshot_update_id: u64 },
+    Completed(AuditReport),
+}
+
+// Compares the local book with an independently fetched REST snapshot. The
+// snapshot is aligned by replaying the deltas applied after its lastUpdateId,
+// then both books are compared over the price range they both cover.
+#[derive(Default)]
+pub struct BookAuditor {
+    history: VecDeque<DepthUpdate>,
+    pending: Option<DepthSnapshot>,
+}
+
+impl BookAuditor {
+    // remember a delta that was just applied to the local book
+    pub fn record(&mut self, update: &DepthUpdate) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(update.clone());
+    }
+
+    // the delta sequence was broken (gap or new snapshot), history can't be trusted anymore
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.pending = None;
+    }
+
+    pub fn submit(&mut self, snapshot: DepthSnapshot) {
+        self.pending = Some(snapshot);
+    }
+
+    pub fn check(
+        &mut self,
+        book: &OrderBook,
+        local_update_id: u64,
+        scaler: &Scaler,
+    ) -> Result<AuditOutcome> {
+        let Some(snapshot) = self.pending.take() else {
+            return Ok(AuditOutcome::Idle);
+        };
+
+        let snapshot_update_id = snapshot.last_update_id;
+        if snapshot_update_id > local_update_id {
+            self.pending = Some(snapshot);
+            return Ok(AuditOutcome::Waiting);
+        }
+
+        let to_replay: Vec<&DepthUpdate> = self
+            .history
+            .iter()
+            .filter(|u| u.final_update_id > snapshot_update_id)
+            .collect();
+
+        // the first replayed delta has to pick up right where the snapshot ends
+        if snapshot_update_id < local_update_id {
+            let aligned = to_replay.first().is_some_and(|first| {
+                first.first_update_id <= snapshot_update_id + 1
+                    || first
+                        .prev_final_update_id
+                        .is_some_and(|pu| pu <= snapshot_update_id)
+            });
+            if !aligned {
+                return Ok(AuditOutcome::Unaligned { snapshot_update_id });
+            }
+        }
+
+        let mut reference = OrderBook::from_snapshot(snapshot, scaler)?;
+        // a depth limited snapshot only covers the levels nearest the touch, take
+        // its bounds before replaying since deltas can land deeper than that
+        let ref_low = reference.bids().keys().next().copied();
+        let ref_high = reference.asks().keys().next_back().copied();
+        for update in to_replay {
+            reference.apply_update(update, scaler)?;
+        }
+
+        let mut mismatches = Vec::new();
+        let mut levels_compared = 0;
+
+        if let (Some(ref_low), Some(&local_low)) = (ref_low, book.bids().keys().next()) {
+            let low = ref_low.max(local_low);
+            levels_compared += compare_side(
+                BookSide::Bid,
+                book.bids(),
+                reference.bids(),
+                low..,
+                &mut mismatches,
+            );
+        }
+
+        if let (Some(ref_high), Some(&local_high)) = (ref_high, book.asks().keys().next_back()) {
+            let high = ref_high.min(local_high);
+            levels_compared += compare_side(
+                BookSide::Ask,
+                book.asks(),
+                reference.asks(),
+                ..=high,
+                &mut mismatches,
+            );
+        }
+
+        Ok(AuditOutcome::Completed(AuditReport {
+            update_id: local_update_id,
+            levels_compared,
+            mismatches,
+        }))
+    }
+}
+
+fn compare_side<R: RangeBounds<u64> + Clone>(
+    side: BookSide,
+    local: &BTreeMap<u64, u64>,
+    reference: &BTreeMap<u64, u64>,
+    range: R,
+    mismatches: &mut Vec<LevelMismatch>,
+) -> usize {
+    let prices: BTreeSet<u64> = local
+        .range(range.clone())
+        .chain(reference.range(range))
+        .map(|(price, _)| *price)
+        .collect();
+
+    for &price in &prices {
+        let local_qty = local.get(&price).copied();
+        let reference_qty = reference.get(&price).copied();
+        if local_qty != reference_qty {
+            mismatches.push(LevelMismatch {
+                side,
+                price,
+                local_qty,
+                reference_qty,
+            });
+        }
+    }
+
+    prices.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3))
+    }
+
+    fn levels(levels: &[(&str, &str)]) -> Vec<[String; 2]> {
+        levels
+            .iter()
+            .map(|(p, q)| [p.to_string(), q.to_string()])
+            .collect()
+    }
+
+    fn mk_snapshot(
+        last_update_id: u64,
+        bids: &[(&str, &str)],
+        asks: &[(&str, &str)],
+    ) -> DepthSnapshot {
+        DepthSnapshot {
+            last_update_id,
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    fn mk_update(
+        first: u64,
+        final_id: u64,
+        bids: &[(&str, &str)],
+        asks: &[(&str, &str)],
+    ) -> DepthUpdate {
+        DepthUpdate {
+            event_time: 0,
+            first_update_id: first,
+            final_update_id: final_id,
+            prev_final_update_id: None,
+            b: levels(bids),
+            a: levels(asks),
+        }
+    }
+
+    // local book built from `base` plus `updates`, all recorded in the auditor
+    fn local_book(
+        auditor: &mut BookAuditor,
+        base: DepthSnapshot,
+        updates: &[DepthUpdate],
+    ) -> OrderBook {
+        let mut book = OrderBook::from_snapshot(base, &scaler()).unwrap();
+        for update in updates {
+            book.apply_update(update, &scaler()).unwrap();
+            auditor.record(update);
+        }
+        book
+    }
+
+    #[test]
+    fn rolls_an_older_snapshot_forward_before_comparing() {
+        let mut auditor = BookAuditor::default();
+        let updates = [
+            mk_update(11, 12, &[("99.00", "2")], &[]),
+            mk_update(13, 14, &[], &[("101.00", "0"), ("101.50", "1")]),
+        ];
+        let book = local_book(
+            &mut auditor,
+            mk_snapshot(10, &[("99.00", "1")], &[("101.00", "1"), ("102.00", "1")]),
+            &updates,
+        );
+
+        // fetched between the two deltas
+        auditor.submit(mk_snapshot(
+            12,
+            &[("99.00", "2")],
+            &[("101.00", "1"), ("102.00", "1")],
+        ));
+
+        match auditor.check(&book, 14, &scaler()).unwrap() {
+            AuditOutcome::Completed(report) => {
+                assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+                assert_eq!(report.update_id, 14);
+                assert_eq!(report.levels_compared, 3);
+            }
+            other => panic!("expected completed audit, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_diverged_levels() {
+        let mut auditor = BookAuditor::default();
+        let book = local_book(
+            &mut auditor,
+            mk_snapshot(
+                10,
+                &[("99.00", "1"), ("98.00", "1")],
+                &[("101.00", "1"), ("102.00", "1")],
+            ),
+            &[],
+        );
+
+        auditor.submit(mk_snapshot(
+            10,
+            &[("99.00", "3"), ("98.00", "1")],
+            &[("101.00", "1"), ("101.50", "1"), ("102.00", "1")],
+        ));
+
+        let report = match auditor.check(&book, 10, &scaler()).unwrap() {
+            AuditOutcome::Completed(report) => report,
+            other => panic!("expected completed audit, got {other:?}"),
+        };
+
+        assert_eq!(
+            report.mismatches,
+            vec![
+                LevelMismatch {
+                    side: BookSide::Bid,
+                    price: 9900,
+                    local_qty: Some(1000),
+                    reference_qty: Some(3000),
+                },
+                LevelMismatch {
+                    side: BookSide::Ask,
+                    price: 10150,
+                    local_qty: None,
+                    reference_qty: Some(1000),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_levels_outside_the_snapshot_depth() {
+        let mut auditor = BookAuditor::default();
+        // local book is deeper than the audit snapshot
+        let book = local_book(
+            &mut auditor,
+            mk_snapshot(
+                10,
+                &[("99.00", "1"), ("90.00", "5")],
+                &[("101.00", "1"), ("110.00", "5")],
+            ),
+            &[],
+        );
+
+        auditor.submit(mk_snapshot(10, &[("99.00", "1")], &[("101.00", "1")]));
+
+        match auditor.check(&book, 10, &scaler()).unwrap() {
+            AuditOutcome::Completed(report) => assert!(report.mismatches.is_empty()),
+            other => panic!("expected completed audit, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn replayed_deltas_below_snapshot_depth_do_not_widen_the_range() {
+        let mut auditor = BookAuditor::default();
+        // local book holds levels beyond the audit snapshot's depth
+        let book = local_book(
+            &mut auditor,
+            mk_snapshot(
+                10,
+                &[("99.00", "1"), ("95.00", "1"), ("90.00", "5")],
+                &[("101.00", "1"), ("105.00", "1"), ("110.00", "5")],
+            ),
+            &[mk_update(11, 12, &[("92.00", "2")], &[("108.00", "2")])],
+        );
+
+        auditor.submit(mk_snapshot(10, &[("99.00", "1")], &[("101.00", "1")]));
+
+        match auditor.check(&book, 12, &scaler()).unwrap() {
+            AuditOutcome::Completed(report) => {
+                assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+                assert_eq!(report.levels_compared, 2);
+            }
+            other => panic!("expected completed audit, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn waits_for_local_book_to_catch_up() {
+        let mut auditor = BookAuditor::default();
+        let book = local_book(&mut auditor, mk_snapshot(10, &[("99.00", "1")], &[]), &[]);
+
+        auditor.submit(mk_snapshot(20, &[("99.00", "1")], &[]));
+
+        assert!(matches!(
+            auditor.check(&book, 10, &scaler()).unwrap(),
+            AuditOutcome::Waiting
+        ));
+        assert!(matches!(
+            auditor.check(&book, 20, &scaler()).unwrap(),
+            AuditOutcome::Completed(_)
+        ));
+        assert!(matches!(
+            auditor.check(&book, 20, &scaler()).unwrap(),
+            AuditOutcome::Idle
+        ));
+    }
+
+    #[test]
+    fn drops_snapshot_older_than_history() {
+        let mut auditor = BookAuditor::default();
+        let book = local_book(
+            &mut auditor,
+            mk_snapshot(10, &[("99.00", "1")], &[]),
+            &[mk_update(11, 12, &[], &[]), mk_update(13, 14, &[], &[])],
+        );
+        auditor.reset();
+        auditor.record(&mk_update(15, 16, &[], &[]));
+
+        auditor.submit(mk_snapshot(12, &[("99.00", "1")], &[]));
+
+        assert!(matches!(
+            auditor.check(&book, 16, &scaler()).unwrap(),
+            AuditOutcome::Unaligned {
+                snapshot_update_id: 12
+            }
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+use serde::Serialize;
+
+use crate::book::orderbook::OrderBook;
+
+// whether the top of book makes sense. a healthy exchange book is never
+// crossed, so seeing one means our copy has diverged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookHealth {
+    #[default]
+    Normal,
+    // best bid == best ask
+    Locked,
+    // best bid > best ask
+    Crossed,
+}
+
+impl BookHealth {
+    pub fn of(book: &OrderBook) -> Self {
+        match (book.best_bid(), book.best_ask()) {
+            (Some((bid, _)), Some((ask, _))) if bid > ask => BookHealth::Crossed,
+            (Some((bid, _)), Some((ask, _))) if bid == ask => BookHealth::Locked,
+            _ => BookHealth::Normal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+    use crate::book::scaler::Scaler;
+    use rust_decimal::Decimal;
+
+    fn book(bid: Option<&str>, ask: Option<&str>) -> OrderBook {
+        let level = |price: Option<&str>| {
+            price
+                .map(|p| vec![[p.to_string(), "1".to_string()]])
+                .unwrap_or_default()
+        };
+        let snapshot = DepthSnapshot {
+            last_update_id: 1,
+            bids: level(bid),
+            asks: level(ask),
+        };
+        OrderBook::from_snapshot(snapshot, &Scaler::new(Decimal::new(1, 2), Decimal::ONE)).unwrap()
+    }
+
+    #[test]
+    fn classifies_top_of_book() {
+        assert_eq!(
+            BookHealth::of(&book(Some("99.99"), Some("100.00"))),
+            BookHealth::Normal
+        );
+        assert_eq!(
+            BookHealth::of(&book(Some("100.00"), Some("100.00"))),
+            BookHealth::Locked
+        );
+        assert_eq!(
+            BookHealth::of(&book(Some("100.01"), Some("100.00"))),
+            BookHealth::Crossed
+        );
+    }
+
+    #[test]
+    fn one_sided_book_is_normal() {
+        assert_eq!(
+            BookHealth::of(&book(Some("100.00"), None)),
+            BookHealth::Normal
+        );
+        assert_eq!(BookHealth::of(&book(None, None)), BookHealth::Normal);
+    }
+
+    #[test]
+    fn crossed_book_has_no_spread() {
+        assert_eq!(book(Some("100.01"), Some("100.00")).spread(), None);
+        assert_eq!(book(Some("100.00"), Some("100.00")).spread(), Some(0));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod audit;
+pub mod health;
 pub mod orderbook;
 pub mod scaler;
 pub mod sync;
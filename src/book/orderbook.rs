@@ -8,6 +8,21 @@ use crate::book::scaler;
 
 pub type RawDepthLevel = (u64, u64);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+impl std::fmt::Display for BookSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookSide::Bid => write!(f, "bid"),
+            BookSide::Ask => write!(f, "ask"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OrderBook {
     bids: BTreeMap<u64, u64>,
@@ -73,6 +88,14 @@ impl OrderBook {
         Ok(())
     }
 
+    pub fn bids(&self) -> &BTreeMap<u64, u64> {
+        &self.bids
+    }
+
+    pub fn asks(&self) -> &BTreeMap<u64, u64> {
+        &self.asks
+    }
+
     pub fn best_bid(&self) -> Option<(&u64, &u64)> {
         self.bids.iter().next_back() // highest price
     }
@@ -83,7 +106,8 @@ impl OrderBook {
 
     pub fn spread(&self) -> Option<u64> {
         match (self.best_bid(), self.best_ask()) {
-            (Some((bid, _)), Some((ask, _))) => Some(ask - bid),
+            // None rather than underflowing when the book is crossed
+            (Some((bid, _)), Some((ask, _))) => ask.checked_sub(*bid),
             _ => None,
         }
     }
@@ -48,6 +48,26 @@ impl Scaler {
         ticks.to_u64()
     }
 
+    // unlike price_to_ticks, refuses a price off the tick grid instead of rounding it
+    pub fn exact_price_ticks(&self, price: Decimal) -> Option<u64> {
+        let ticks = price / self.tick_size;
+        if ticks.is_integer() {
+            ticks.to_u64()
+        } else {
+            None
+        }
+    }
+
+    // unlike qty_to_ticks, refuses a quantity off the step grid instead of rounding it
+    pub fn exact_qty_ticks(&self, qty: Decimal) -> Option<u64> {
+        let ticks = qty / self.step_size;
+        if ticks.is_integer() {
+            ticks.to_u64()
+        } else {
+            None
+        }
+    }
+
     pub fn ticks_to_price(&self, ticks: u64) -> Decimal {
         Decimal::from(ticks) * self.tick_size
     }
@@ -55,6 +75,14 @@ impl Scaler {
     pub fn ticks_to_qty(&self, ticks: u64) -> Decimal {
         Decimal::from(ticks) * self.step_size
     }
+
+    pub fn tick_size(&self) -> Decimal {
+        self.tick_size
+    }
+
+    pub fn step_size(&self) -> Decimal {
+        self.step_size
+    }
 }
 
 #[cfg(test)]
@@ -85,6 +113,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn exact_conversions_refuse_values_off_the_grid() {
+        let scaler = Scaler::new(
+            Decimal::from_str("0.01").unwrap(),
+            Decimal::from_str("0.1").unwrap(),
+        );
+
+        assert_eq!(
+            scaler.exact_price_ticks(Decimal::from_str("1.23").unwrap()),
+            Some(123)
+        );
+        assert_eq!(
+            scaler.exact_price_ticks(Decimal::from_str("0.015").unwrap()),
+            None
+        );
+        assert_eq!(
+            scaler.exact_qty_ticks(Decimal::from_str("0.3").unwrap()),
+            Some(3)
+        );
+        assert_eq!(
+            scaler.exact_qty_ticks(Decimal::from_str("0.25").unwrap()),
+            None
+        );
+        assert_eq!(
+            scaler.exact_qty_ticks(Decimal::from_str("-0.1").unwrap()),
+            None
+        );
+    }
+
     #[test]
     fn rejects_values_not_aligned_to_tick_or_step() {
         let scaler = Scaler::new(
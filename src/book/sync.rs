@@ -4,6 +4,8 @@ use crate::binance::types::DepthUpdate;
 pub struct SyncState {
     last_update_id: Option<u64>,
     buffer: Vec<DepthUpdate>,
+    // a delta has been applied since the last snapshot, futures deltas must chain via pu from here on
+    in_sequence: bool,
 }
 
 #[derive(Debug)]
@@ -16,6 +18,11 @@ pub enum SyncOutcome {
 impl SyncState {
     pub fn set_last_update_id(&mut self, last_update_id: u64) {
         self.last_update_id = Some(last_update_id);
+        self.in_sequence = false;
+    }
+
+    pub fn last_update_id(&self) -> Option<u64> {
+        self.last_update_id
     }
 
     // returns list of updates to apply
@@ -38,6 +45,7 @@ impl SyncState {
 
         let mut to_apply = Vec::new();
         let mut expected = last_id + 1;
+        let mut in_sequence = self.in_sequence;
 
         for u in candidates {
             // skip stale chunks
@@ -45,17 +53,26 @@ impl SyncState {
                 continue;
             }
             // require contiguity
-            if u.first_update_id > expected {
+            let continuous = match u.prev_final_update_id {
+                // futures ids are not contiguous, each delta points at the previous one instead
+                Some(pu) if in_sequence => pu == expected - 1,
+                // first futures delta after a snapshot either chains onto it or straddles it
+                Some(pu) => pu == expected - 1 || u.first_update_id <= expected,
+                None => u.first_update_id <= expected,
+            };
+            if !continuous {
                 return SyncOutcome::GapBetweenUpdates;
             }
 
             // we are ok to apply
             to_apply.push(u);
             expected = to_apply.last().unwrap().final_update_id + 1;
+            in_sequence = true;
         }
 
         if let Some(last) = to_apply.last() {
-            self.set_last_update_id(last.final_update_id);
+            self.last_update_id = Some(last.final_update_id);
+            self.in_sequence = true;
         }
 
         SyncOutcome::Updates(to_apply)
@@ -76,11 +93,19 @@ mod tests {
             event_time,
             first_update_id: first,
             final_update_id: final_id,
+            prev_final_update_id: None,
             b: vec![],
             a: vec![],
         }
     }
 
+    fn mk_futures_update(first: u64, final_id: u64, prev_final_id: u64) -> DepthUpdate {
+        DepthUpdate {
+            prev_final_update_id: Some(prev_final_id),
+            ..mk_update(first, final_id, 0)
+        }
+    }
+
     #[test]
     fn buffers_when_last_id_unknown() {
         let mut state = SyncState::default();
@@ -155,4 +180,46 @@ mod tests {
         assert_eq!(state.last_update_id, Some(10));
         assert!(state.buffer.is_empty());
     }
+
+    #[test]
+    fn chains_futures_updates_through_pu() {
+        let mut state = SyncState::default();
+        state.set_last_update_id(100);
+
+        // first delta straddles the snapshot, later ones skip ids but chain via pu
+        let outcomes = [
+            state.process_delta(mk_futures_update(95, 105, 90)),
+            state.process_delta(mk_futures_update(120, 130, 105)),
+            state.process_delta(mk_futures_update(140, 141, 130)),
+        ];
+
+        for outcome in outcomes {
+            assert!(matches!(outcome, SyncOutcome::Updates(ref u) if u.len() == 1));
+        }
+        assert_eq!(state.last_update_id, Some(141));
+    }
+
+    #[test]
+    fn errors_when_futures_pu_does_not_chain() {
+        let mut state = SyncState::default();
+        state.set_last_update_id(100);
+        state.process_delta(mk_futures_update(95, 105, 90));
+
+        // ids look contiguous but pu says an event was missed
+        let outcome = state.process_delta(mk_futures_update(106, 110, 104));
+
+        assert!(matches!(outcome, SyncOutcome::GapBetweenUpdates));
+        assert_eq!(state.last_update_id, Some(105));
+    }
+
+    #[test]
+    fn first_futures_update_may_chain_onto_snapshot() {
+        let mut state = SyncState::default();
+        state.set_last_update_id(100);
+
+        let outcome = state.process_delta(mk_futures_update(110, 115, 100));
+
+        assert!(matches!(outcome, SyncOutcome::Updates(ref u) if u.len() == 1));
+        assert_eq!(state.last_update_id, Some(115));
+    }
 }
@@ -0,0 +1,325 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+
+use crate::config::CONFIG_KEYS;
+use crate::recording::replay::ReplaySpeed;
+
+pub enum Mode {
+    Live(Vec<String>),
+    Replay { path: String, speed: ReplaySpeed },
+}
+
+pub enum CliCommand {
+    // live or replayed market data in the TUI, or as JSON lines when headless
+    Tui {
+        mode: Mode,
+        headless: bool,
+    },
+    // print a REST depth snapshot as JSON
+    Snapshot {
+        symbol: String,
+        limit: u16,
+    },
+    // print the exchange filters of each symbol
+    Info {
+        symbols: Vec<String>,
+    },
+    // print what a running engine has published to shared memory
+    Shm {
+        path: Option<String>,
+        symbols: Vec<String>,
+    },
+}
+
+pub struct Cli {
+    pub config_path: Option<PathBuf>,
+    // (config key, raw value) for every override flag given
+    pub overrides: Vec<(String, String)>,
+    // warn about invalid config keys and use their defaults instead of failing
+    pub lenient: bool,
+    pub command: CliCommand,
+}
+
+// exits with usage on --help, --version or bad arguments
+pub fn parse() -> Result<Cli> {
+    from_matches(&command().get_matches())
+}
+
+fn config_flag(key: &str) -> String {
+    key.replace('_', "-")
+}
+
+fn tui_args() -> [Arg; 4] {
+    [
+        Arg::new("symbols")
+            .value_name("SYMBOL")
+            .num_args(1..)
+            .required_unless_present("replay")
+            .conflicts_with("replay")
+            .help("Trading pairs to watch, e.g. BTCUSDT ETHUSDT"),
+        Arg::new("replay")
+            .long("replay")
+            .value_name("FILE")
+            .help("Replay a recording instead of connecting to Binance"),
+        Arg::new("speed")
+            .long("speed")
+            .value_name("SPEED")
+            .help("Replay speed: 1, 10x or max [default: 1]"),
+        Arg::new("headless")
+            .long("headless")
+            .action(ArgAction::SetTrue)
+            .help("Skip the TUI and write JSON lines to stdout"),
+    ]
+}
+
+fn command() -> Command {
+    let overrides = CONFIG_KEYS.iter().map(|(key, help)| {
+        Arg::new(*key)
+            .long(config_flag(key))
+            .value_name("VALUE")
+            .help(*help)
+            .help_heading("Config overrides")
+            .global(true)
+    });
+
+    Command::new("binance-market-terminal")
+        .about("Live Binance order books, trades and metrics in the terminal")
+        .version(env!("CARGO_PKG_VERSION"))
+        .after_help(
+            "Without a subcommand the arguments are those of `tui`.\n\n\
+             The config file is the --config path, otherwise the first of ./config.toml,\n\
+             $XDG_CONFIG_HOME/binance-market-terminal/config.toml (~/.config by default)\n\
+             and $XDG_CONFIG_DIRS/binance-market-terminal/config.toml (/etc/xdg by default).\n\
+             Override flags take precedence over the file. Unknown keys and invalid values\n\
+             are reported with their line number and stop the program unless --lenient is given.",
+        )
+        .subcommand_negates_reqs(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .short('c')
+                .value_name("PATH")
+                .value_parser(clap::value_parser!(PathBuf))
+                .global(true)
+                .help("Config file to use instead of searching for one"),
+        )
+        .arg(
+            Arg::new("lenient")
+                .long("lenient")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Use the defaults for invalid config keys instead of refusing to start"),
+        )
+        .args(tui_args())
+        .args(overrides)
+        .subcommand(
+            Command::new("tui")
+                .about("Watch live or replayed market data (the default)")
+                .args(tui_args()),
+        )
+        .subcommand(
+            Command::new("snapshot")
+                .about("Print a REST depth snapshot as JSON")
+                .arg(Arg::new("symbol").value_name("SYMBOL").required(true))
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("LEVELS")
+                        .value_parser(clap::value_parser!(u16))
+                        .default_value("100")
+                        .help("Levels per side, capped at the market's maximum"),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("Print the exchange filters (tick size, lot size, ...) of each symbol")
+                .arg(
+                    Arg::new("symbols")
+                        .value_name("SYMBOL")
+                        .num_args(1..)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("shm")
+                .about("Print the top of book a running instance publishes to shared memory")
+                .arg(
+                    Arg::new("symbols")
+                        .value_name("SYMBOL")
+                        .num_args(0..)
+                        .help("Symbols to print [default: all]"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .value_name("FILE")
+                        .help("Shared memory file [default: shm_path from the config]"),
+                ),
+        )
+}
+
+fn symbols(matches: &ArgMatches) -> Vec<String> {
+    matches
+        .get_many::<String>("symbols")
+        .into_iter()
+        .flatten()
+        .map(|s| s.to_uppercase())
+        .collect()
+}
+
+fn tui_command(matches: &ArgMatches) -> Result<CliCommand> {
+    let mode = match matches.get_one::<String>("replay") {
+        Some(path) => Mode::Replay {
+            path: path.clone(),
+            speed: match matches.get_one::<String>("speed") {
+                Some(speed) => speed.parse()?,
+                None => ReplaySpeed::Multiplier(1.0),
+            },
+        },
+        None if matches.get_one::<String>("speed").is_some() => {
+            anyhow::bail!("--speed only applies to --replay")
+        }
+        None => Mode::Live(symbols(matches)),
+    };
+    Ok(CliCommand::Tui {
+        mode,
+        headless: matches.get_flag("headless"),
+    })
+}
+
+fn from_matches(matches: &ArgMatches) -> Result<Cli> {
+    let (command, sub_matches) = match matches.subcommand() {
+        Some(("tui", sub)) => (tui_command(sub)?, sub),
+        Some(("snapshot", sub)) => (
+            CliCommand::Snapshot {
+                symbol: sub
+                    .get_one::<String>("symbol")
+                    .map(|s| s.to_uppercase())
+                    .unwrap_or_default(),
+                limit: sub.get_one::<u16>("limit").copied().unwrap_or(100),
+            },
+            sub,
+        ),
+        Some(("info", sub)) => (
+            CliCommand::Info {
+                symbols: symbols(sub),
+            },
+            sub,
+        ),
+        Some(("shm", sub)) => (
+            CliCommand::Shm {
+                path: sub.get_one::<String>("path").cloned(),
+                symbols: symbols(sub),
+            },
+            sub,
+        ),
+        _ => (tui_command(matches)?, matches),
+    };
+
+    // global args are visible from the subcommand's matches wherever they were given
+    let overrides = CONFIG_KEYS
+        .iter()
+        .filter_map(|(key, _)| {
+            sub_matches
+                .get_one::<String>(key)
+                .map(|value| (key.to_string(), value.clone()))
+        })
+        .collect();
+
+    Ok(Cli {
+        config_path: sub_matches.get_one::<PathBuf>("config").cloned(),
+        overrides,
+        lenient: sub_matches.get_flag("lenient"),
+        command,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn try_parse_from(args: &[&str]) -> Result<Cli> {
+        from_matches(&command().try_get_matches_from(args)?)
+    }
+
+    #[test]
+    fn bare_symbols_run_the_tui() {
+        let cli = try_parse_from(&["bmt", "btcusdt", "ethusdt", "--headless"]).unwrap();
+        match cli.command {
+            CliCommand::Tui {
+                mode: Mode::Live(symbols),
+                headless,
+            } => {
+                assert_eq!(symbols, vec!["BTCUSDT", "ETHUSDT"]);
+                assert!(headless);
+            }
+            _ => panic!("expected live tui"),
+        }
+    }
+
+    #[test]
+    fn replay_takes_a_speed() {
+        let cli = try_parse_from(&["bmt", "tui", "--replay", "a.rec", "--speed", "max"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            CliCommand::Tui {
+                mode: Mode::Replay {
+                    speed: ReplaySpeed::Max,
+                    ..
+                },
+                headless: false,
+            }
+        ));
+        assert!(try_parse_from(&["bmt", "--speed", "max", "BTCUSDT"]).is_err());
+        assert!(try_parse_from(&["bmt"]).is_err());
+    }
+
+    #[test]
+    fn config_path_and_overrides_work_before_and_after_the_subcommand() {
+        let cli = try_parse_from(&[
+            "bmt",
+            "--config",
+            "/tmp/bmt.toml",
+            "--market",
+            "usdm",
+            "snapshot",
+            "btcusdt",
+            "--limit",
+            "5",
+            "--max-backoff-ms",
+            "500",
+            "--lenient",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.config_path, Some(PathBuf::from("/tmp/bmt.toml")));
+        assert!(cli.lenient);
+        assert_eq!(
+            cli.overrides,
+            vec![
+                ("max_backoff_ms".to_string(), "500".to_string()),
+                ("market".to_string(), "usdm".to_string()),
+            ]
+        );
+        assert!(matches!(
+            cli.command,
+            CliCommand::Snapshot { ref symbol, limit: 5 } if symbol == "BTCUSDT"
+        ));
+    }
+
+    #[test]
+    fn info_needs_a_symbol() {
+        assert!(try_parse_from(&["bmt", "info"]).is_err());
+        let cli = try_parse_from(&["bmt", "info", "btcusdt"]).unwrap();
+        assert!(matches!(cli.command, CliCommand::Info { ref symbols } if symbols == &["BTCUSDT"]));
+    }
+
+    #[test]
+    fn help_documents_every_config_key() {
+        let help = command().render_long_help().to_string();
+        for (key, _) in CONFIG_KEYS {
+            assert!(help.contains(&format!("--{}", config_flag(key))), "{key}");
+        }
+    }
+}
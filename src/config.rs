@@ -1,7 +1,16 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::alerts::rules::AlertRule;
+use crate::binance::market::{Market, TradeStream};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(default)]
 pub struct Config {
     pub orderbook_initial_snapshot_depth: u16,
@@ -11,6 +20,22 @@ pub struct Config {
     pub significant_trades_retention_secs: u64,
     pub significant_trade_volume_pct: f64,
     pub min_trades_for_significance: usize,
+    pub significant_notional: Option<f64>,
+    pub significant_size_zscore: Option<f64>,
+    pub significant_sweep_levels: Option<usize>,
+    pub significant_mid_distance_bps: Option<f64>,
+    pub significance_min_rules: usize,
+    pub candle_history_len: usize,
+
+    pub level_events_window_secs: u64,
+    pub spoof_touch_ticks: u64,
+    pub spoof_max_lifetime_ms: u64,
+    pub spoof_min_notional: f64,
+
+    // exact, so it maps onto the symbol's step size without float noise
+    #[serde(with = "rust_decimal::serde::float")]
+    pub virtual_order_qty: Decimal,
+    pub virtual_order_horizon_secs: u64,
 
     pub max_reconnect_attempts: u32,
     pub initial_backoff_ms: u64,
@@ -20,6 +45,36 @@ pub struct Config {
     pub orderbook_depth_display_count: usize,
     pub recent_trades_display_count: usize,
     pub significant_trades_display_count: usize,
+    pub headless_interval_ms: u64,
+    pub depth_chart_range_pct: f64,
+    pub heatmap_history_len: usize,
+    pub heatmap_sample_ms: u64,
+    pub heatmap_range_pct: f64,
+
+    pub record_path: Option<String>,
+
+    pub crossed_book_grace_ms: u64,
+
+    pub audit_interval_secs: u64,
+    pub audit_snapshot_depth: u16,
+    pub audit_force_resync: bool,
+
+    pub market: Market,
+    pub trade_stream: TradeStream,
+    pub rest_base_url: Option<String>,
+    pub ws_base_url: Option<String>,
+
+    pub metrics_addr: Option<String>,
+
+    pub api_addr: Option<String>,
+
+    pub shm_path: Option<String>,
+    pub shm_depth_levels: usize,
+
+    pub alert_bell: bool,
+    pub alert_command: Option<String>,
+    pub alert_webhook_url: Option<String>,
+    pub alerts: Vec<AlertRule>,
 }
 
 impl Default for Config {
@@ -33,6 +88,20 @@ impl Default for Config {
 
             significant_trade_volume_pct: 0.05,
             min_trades_for_significance: 50,
+            significant_notional: None,
+            significant_size_zscore: None,
+            significant_sweep_levels: None,
+            significant_mid_distance_bps: None,
+            significance_min_rules: 1,
+            candle_history_len: 120,
+
+            level_events_window_secs: 10,
+            spoof_touch_ticks: 5,
+            spoof_max_lifetime_ms: 2000,
+            spoof_min_notional: 10000.0,
+
+            virtual_order_qty: Decimal::new(5, 1),
+            virtual_order_horizon_secs: 60,
 
             max_reconnect_attempts: 10,
             initial_backoff_ms: 100,
@@ -42,19 +111,798 @@ impl Default for Config {
             orderbook_depth_display_count: 5,
             recent_trades_display_count: 10,
             significant_trades_display_count: 20,
+            headless_interval_ms: 1000,
+            depth_chart_range_pct: 1.0,
+            heatmap_history_len: 300,
+            heatmap_sample_ms: 250,
+            heatmap_range_pct: 0.2,
+
+            record_path: None,
+
+            crossed_book_grace_ms: 1000,
+
+            audit_interval_secs: 0,
+            audit_snapshot_depth: 100,
+            audit_force_resync: false,
+
+            market: Market::Spot,
+            trade_stream: TradeStream::Trade,
+            rest_base_url: None,
+            ws_base_url: None,
+
+            metrics_addr: None,
+
+            api_addr: None,
+
+            shm_path: None,
+            shm_depth_levels: 10,
+
+            alert_bell: false,
+            alert_command: None,
+            alert_webhook_url: None,
+            alerts: Vec::new(),
         }
     }
 }
 
-pub fn load_config() -> Config {
-    match fs::read_to_string("config.toml") {
-        Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
-            tracing::warn!("Failed to parse config.toml: {e}, using defaults");
-            Config::default()
-        }),
-        Err(_) => {
-            tracing::info!("No config.toml found, using defaults");
-            Config::default()
+// every key `Config` accepts with a one line description, used for the CLI
+// override flags and their help text
+pub const CONFIG_KEYS: &[(&str, &str)] = &[
+    (
+        "orderbook_initial_snapshot_depth",
+        "Levels requested in the startup depth snapshot",
+    ),
+    (
+        "orderbook_imbalance_depth_levels",
+        "Levels per side used for the imbalance ratio",
+    ),
+    (
+        "recent_trades_starting_capacity",
+        "Initial capacity of the recent trades buffer",
+    ),
+    (
+        "significant_trades_retention_secs",
+        "How long significant trades are kept",
+    ),
+    (
+        "significant_trade_volume_pct",
+        "Share of 1m volume that makes a trade significant",
+    ),
+    (
+        "min_trades_for_significance",
+        "Trades needed in the window before flagging any",
+    ),
+    (
+        "significant_notional",
+        "Notional at or above which a trade is significant",
+    ),
+    (
+        "significant_size_zscore",
+        "Trade size z-score against the 1m window that is significant",
+    ),
+    (
+        "significant_sweep_levels",
+        "Book levels a trade must go through to be significant",
+    ),
+    (
+        "significant_mid_distance_bps",
+        "Distance from the mid, in bps, that makes a trade significant",
+    ),
+    (
+        "significance_min_rules",
+        "Rules a trade must match to be flagged",
+    ),
+    ("candle_history_len", "Bars kept per candle interval"),
+    (
+        "level_events_window_secs",
+        "Window for per-level add, cancel and fill rates",
+    ),
+    (
+        "spoof_touch_ticks",
+        "Ticks from the touch within which adds are watched for pulls",
+    ),
+    (
+        "spoof_max_lifetime_ms",
+        "Adds cancelled untraded within this long count as pulls",
+    ),
+    (
+        "spoof_min_notional",
+        "Smallest add, in notional, watched for pulls",
+    ),
+    (
+        "virtual_order_qty",
+        "Size of virtual orders placed from the TUI",
+    ),
+    (
+        "virtual_order_horizon_secs",
+        "Horizon for virtual order fill probabilities",
+    ),
+    (
+        "max_reconnect_attempts",
+        "Reconnect attempts before giving up",
+    ),
+    ("initial_backoff_ms", "First reconnect backoff"),
+    ("max_backoff_ms", "Reconnect backoff cap"),
+    ("message_timeout_ms", "Stream silence before reconnecting"),
+    (
+        "orderbook_depth_display_count",
+        "Levels per side shown in the book panel",
+    ),
+    (
+        "recent_trades_display_count",
+        "Rows in the recent trades table",
+    ),
+    (
+        "significant_trades_display_count",
+        "Rows in the significant trades table",
+    ),
+    (
+        "headless_interval_ms",
+        "How often --headless writes a line per symbol",
+    ),
+    (
+        "depth_chart_range_pct",
+        "Depth chart range either side of the mid, in percent",
+    ),
+    ("heatmap_history_len", "Book samples kept for the heatmap"),
+    ("heatmap_sample_ms", "Time between heatmap samples"),
+    (
+        "heatmap_range_pct",
+        "Heatmap range either side of the mid, in percent",
+    ),
+    (
+        "record_path",
+        "Append raw frames and snapshots to this file",
+    ),
+    (
+        "crossed_book_grace_ms",
+        "How long the book may stay crossed before a resync",
+    ),
+    (
+        "audit_interval_secs",
+        "Seconds between book audits, 0 disables",
+    ),
+    (
+        "audit_snapshot_depth",
+        "Levels requested in audit snapshots",
+    ),
+    (
+        "audit_force_resync",
+        "Resync when an audit finds diverged levels",
+    ),
+    ("market", "spot, usdm or coinm"),
+    (
+        "trade_stream",
+        "Spot trade stream, trade or aggtrade (futures always use aggtrade)",
+    ),
+    ("rest_base_url", "Override the market's REST base URL"),
+    ("ws_base_url", "Override the market's websocket base URL"),
+    (
+        "metrics_addr",
+        "Serve Prometheus metrics on this address, e.g. 127.0.0.1:9184",
+    ),
+    (
+        "api_addr",
+        "Serve the book API on this address, e.g. 127.0.0.1:9185",
+    ),
+    (
+        "shm_path",
+        "Publish top of book to this memory-mapped file, e.g. /dev/shm/bmt",
+    ),
+    (
+        "shm_depth_levels",
+        "Levels per side in the shared memory file",
+    ),
+    ("alert_bell", "Ring the terminal bell on alerts"),
+    (
+        "alert_command",
+        "Run this shell command on alerts, with ALERT_* set",
+    ),
+    ("alert_webhook_url", "POST each alert as JSON to this URL"),
+    (
+        "alerts",
+        "Alert rules, [[alerts]] tables in the config file",
+    ),
+];
+
+const APP_DIR: &str = "binance-market-terminal";
+const CONFIG_FILE: &str = "config.toml";
+
+// ./config.toml first so running from a checkout keeps working, then the XDG locations
+pub fn config_search_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(CONFIG_FILE)];
+    paths.extend(xdg_config_paths(
+        env::var_os("XDG_CONFIG_HOME"),
+        env::var_os("HOME"),
+        env::var_os("XDG_CONFIG_DIRS"),
+    ));
+    paths
+}
+
+fn xdg_config_paths(
+    config_home: Option<OsString>,
+    home: Option<OsString>,
+    config_dirs: Option<OsString>,
+) -> Vec<PathBuf> {
+    // relative values are invalid per the spec and are ignored
+    let absolute = |dir: PathBuf| dir.is_absolute().then_some(dir);
+
+    let config_home = config_home
+        .map(PathBuf::from)
+        .and_then(absolute)
+        .or_else(|| home.map(|home| PathBuf::from(home).join(".config")));
+
+    let config_dirs: Vec<PathBuf> = match config_dirs.filter(|dirs| !dirs.is_empty()) {
+        Some(dirs) => env::split_paths(&dirs).filter_map(absolute).collect(),
+        None => vec![PathBuf::from("/etc/xdg")],
+    };
+
+    config_home
+        .into_iter()
+        .chain(config_dirs)
+        .map(|dir| dir.join(APP_DIR).join(CONFIG_FILE))
+        .collect()
+}
+
+// the file `load_config` reads: `explicit` whether or not it exists, otherwise
+// the first file on the search path
+pub fn find_config(explicit: Option<&Path>) -> Option<PathBuf> {
+    match explicit {
+        Some(path) => Some(path.to_path_buf()),
+        None => config_search_paths()
+            .into_iter()
+            .find(|path| path.is_file()),
+    }
+}
+
+// where a rejected key was set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeyOrigin {
+    Line(usize),
+    Flag,
+    // not set anywhere, the default clashes with another key
+    Default,
+}
+
+// a key that is unknown, badly typed or out of range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub key: String,
+    pub origin: KeyOrigin,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.origin {
+            KeyOrigin::Line(line) => write!(f, "line {}: {}: {}", line, self.key, self.message),
+            KeyOrigin::Flag => write!(f, "--{}: {}", self.key.replace('_', "-"), self.message),
+            KeyOrigin::Default => write!(f, "{} (default): {}", self.key, self.message),
         }
     }
 }
+
+// `explicit` has to exist, otherwise the first file found on the search path is used.
+// `overrides` are (key, value) pairs from the command line and win over the file.
+// Any invalid key is an error, unless `lenient` is set in which case it is logged
+// and left at its default
+pub fn load_config(
+    explicit: Option<&Path>,
+    overrides: &[(String, String)],
+    lenient: bool,
+) -> Result<Config> {
+    let path = find_config(explicit);
+
+    let mut table = toml::Table::new();
+    let mut origins = HashMap::new();
+    match &path {
+        Some(path) => {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            match parse_file(&content) {
+                Ok((file_table, lines)) => {
+                    tracing::info!("Loaded config from {}", path.display());
+                    table = file_table;
+                    origins = lines;
+                }
+                Err(e) if lenient => {
+                    tracing::warn!("Failed to parse {}: {e}, using defaults", path.display());
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to parse {}", path.display()));
+                }
+            }
+        }
+        None => tracing::info!("No config.toml found, using defaults"),
+    }
+
+    for (key, value) in overrides {
+        table.insert(key.clone(), override_value(value));
+        origins.insert(key.clone(), KeyOrigin::Flag);
+    }
+
+    let (conf, issues) = resolve(table, &origins, lenient);
+    if issues.is_empty() {
+        return Ok(conf);
+    }
+
+    let source = path.as_ref().map_or("command line".to_string(), |path| {
+        path.display().to_string()
+    });
+    if lenient {
+        for issue in &issues {
+            tracing::warn!("Ignoring config {issue} ({source}), using the default");
+        }
+        return Ok(conf);
+    }
+
+    let listed: Vec<String> = issues.iter().map(|issue| format!("  {issue}")).collect();
+    anyhow::bail!(
+        "Invalid config ({source}):\n{}\nFix these or pass --lenient to use the defaults instead",
+        listed.join("\n")
+    )
+}
+
+// the table and the line each top level key is on
+fn parse_file(content: &str) -> Result<(toml::Table, HashMap<String, KeyOrigin>)> {
+    let spanned = toml::de::DeTable::parse(content)?;
+    let origins = spanned
+        .get_ref()
+        .keys()
+        .map(|key| {
+            let line = content[..key.span().start].matches('\n').count() + 1;
+            (key.get_ref().to_string(), KeyOrigin::Line(line))
+        })
+        .collect();
+    Ok((toml::from_str(content)?, origins))
+}
+
+// Builds the config from every key of `table` that is valid. When strict the
+// first pass's issues are all returned. When lenient the rejected keys are
+// dropped and the rest checked again, since a cross-key check like the backoff
+// bounds can only be settled once its bad half is back to the default
+fn resolve(
+    mut table: toml::Table,
+    origins: &HashMap<String, KeyOrigin>,
+    lenient: bool,
+) -> (Config, Vec<ConfigIssue>) {
+    let mut issues = Vec::new();
+    loop {
+        let (conf, found) = check(&table, origins);
+        let before = table.len();
+        if lenient {
+            for issue in &found {
+                table.remove(&issue.key);
+            }
+        }
+        // nothing left to drop once only defaults are involved
+        let done = table.len() == before;
+        issues.extend(found);
+        if done {
+            // in file order, then the flags
+            issues.sort_by_key(|issue| issue.origin);
+            return (conf, issues);
+        }
+    }
+}
+
+fn check(table: &toml::Table, origins: &HashMap<String, KeyOrigin>) -> (Config, Vec<ConfigIssue>) {
+    let origin = |key: &str| origins.get(key).copied().unwrap_or(KeyOrigin::Default);
+    let mut issues = Vec::new();
+    let mut valid = toml::Table::new();
+
+    for (key, value) in table {
+        if !CONFIG_KEYS.iter().any(|(known, _)| known == key) {
+            issues.push(ConfigIssue {
+                key: key.clone(),
+                origin: origin(key),
+                message: "unknown key".to_string(),
+            });
+            continue;
+        }
+        // one key at a time so every badly typed key is reported, not just the first
+        let single = toml::Table::from_iter([(key.clone(), value.clone())]);
+        match Config::deserialize(single) {
+            Ok(_) => {
+                valid.insert(key.clone(), value.clone());
+            }
+            Err(e) => issues.push(ConfigIssue {
+                key: key.clone(),
+                origin: origin(key),
+                message: e.message().trim().to_string(),
+            }),
+        }
+    }
+
+    let conf = Config::deserialize(valid).unwrap_or_default();
+    for (keys, message) in conf.range_errors() {
+        // blame a key that was actually set, the first one if both were
+        let key = keys
+            .iter()
+            .find(|key| table.contains_key(**key))
+            .unwrap_or(&keys[0]);
+        issues.push(ConfigIssue {
+            key: key.to_string(),
+            origin: origin(key),
+            message,
+        });
+    }
+
+    (conf, issues)
+}
+
+impl Config {
+    // values that deserialize but can't work, with the keys involved
+    fn range_errors(&self) -> Vec<(&'static [&'static str], String)> {
+        let mut errors: Vec<(&'static [&'static str], String)> = Vec::new();
+
+        let limits = self.market.snapshot_depth_limits();
+        let allowed = limits
+            .iter()
+            .map(u16::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !limits.contains(&self.orderbook_initial_snapshot_depth) {
+            errors.push((
+                &["orderbook_initial_snapshot_depth", "market"],
+                format!("must be one of {} for the {} market", allowed, self.market),
+            ));
+        }
+        if !limits.contains(&self.audit_snapshot_depth) {
+            errors.push((
+                &["audit_snapshot_depth", "market"],
+                format!("must be one of {} for the {} market", allowed, self.market),
+            ));
+        }
+
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            errors.push((
+                &["initial_backoff_ms", "max_backoff_ms"],
+                format!(
+                    "initial_backoff_ms ({}) must not exceed max_backoff_ms ({})",
+                    self.initial_backoff_ms, self.max_backoff_ms
+                ),
+            ));
+        }
+
+        // written this way round so NaN fails too
+        if !(self.significant_trade_volume_pct > 0.0 && self.significant_trade_volume_pct <= 1.0) {
+            errors.push((
+                &["significant_trade_volume_pct"],
+                "must be a fraction in (0, 1], e.g. 0.05 for 5%".to_string(),
+            ));
+        }
+        let thresholds: [(&'static [&'static str], Option<f64>); 4] = [
+            (&["significant_notional"], self.significant_notional),
+            (&["significant_size_zscore"], self.significant_size_zscore),
+            (
+                &["significant_sweep_levels"],
+                self.significant_sweep_levels.map(|levels| levels as f64),
+            ),
+            (
+                &["significant_mid_distance_bps"],
+                self.significant_mid_distance_bps,
+            ),
+        ];
+        for (keys, value) in thresholds {
+            if let Some(value) = value
+                && (value.is_nan() || value <= 0.0)
+            {
+                errors.push((keys, "must be greater than 0 when set".to_string()));
+            }
+        }
+        let rules = crate::engine::significance::enabled_rules(self);
+        if !(1..=rules).contains(&self.significance_min_rules) {
+            errors.push((
+                &["significance_min_rules"],
+                format!("must be between 1 and the {rules} enabled significance rules"),
+            ));
+        }
+        if self.spoof_min_notional.is_nan() || self.spoof_min_notional < 0.0 {
+            errors.push((&["spoof_min_notional"], "must not be negative".to_string()));
+        }
+        if self.virtual_order_qty <= Decimal::ZERO {
+            errors.push((&["virtual_order_qty"], "must be greater than 0".to_string()));
+        }
+        let range_pcts: [(&'static [&'static str], f64); 2] = [
+            (&["depth_chart_range_pct"], self.depth_chart_range_pct),
+            (&["heatmap_range_pct"], self.heatmap_range_pct),
+        ];
+        for (keys, value) in range_pcts {
+            if !(value > 0.0 && value <= 100.0) {
+                errors.push((keys, "must be a percentage in (0, 100]".to_string()));
+            }
+        }
+
+        // zero would spin a timer or keep nothing
+        let positive: [(&'static [&'static str], u64); 9] = [
+            (&["message_timeout_ms"], self.message_timeout_ms),
+            (&["headless_interval_ms"], self.headless_interval_ms),
+            (&["heatmap_sample_ms"], self.heatmap_sample_ms),
+            (&["heatmap_history_len"], self.heatmap_history_len as u64),
+            (&["candle_history_len"], self.candle_history_len as u64),
+            (&["level_events_window_secs"], self.level_events_window_secs),
+            (&["spoof_max_lifetime_ms"], self.spoof_max_lifetime_ms),
+            (
+                &["virtual_order_horizon_secs"],
+                self.virtual_order_horizon_secs,
+            ),
+            (&["shm_depth_levels"], self.shm_depth_levels as u64),
+        ];
+        for (keys, value) in positive {
+            if value == 0 {
+                errors.push((keys, "must be greater than 0".to_string()));
+            }
+        }
+
+        let urls: [(&'static [&'static str], &Option<String>, [&str; 2]); 3] = [
+            (
+                &["rest_base_url"],
+                &self.rest_base_url,
+                ["http://", "https://"],
+            ),
+            (&["ws_base_url"], &self.ws_base_url, ["ws://", "wss://"]),
+            (
+                &["alert_webhook_url"],
+                &self.alert_webhook_url,
+                ["http://", "https://"],
+            ),
+        ];
+        for (keys, url, schemes) in urls {
+            if let Some(url) = url
+                && !schemes.iter().any(|scheme| url.starts_with(scheme))
+            {
+                errors.push((keys, format!("must start with {}", schemes.join(" or "))));
+            }
+        }
+
+        let addrs: [(&'static [&'static str], &Option<String>, &str); 2] = [
+            (&["metrics_addr"], &self.metrics_addr, "127.0.0.1:9184"),
+            (&["api_addr"], &self.api_addr, "127.0.0.1:9185"),
+        ];
+        for (keys, addr, example) in addrs {
+            if let Some(addr) = addr
+                && addr.parse::<std::net::SocketAddr>().is_err()
+            {
+                errors.push((keys, format!("must be an ip:port address, e.g. {example}")));
+            }
+        }
+
+        for (idx, rule) in self.alerts.iter().enumerate() {
+            if let Some(problem) = rule.problem() {
+                errors.push((
+                    &["alerts"],
+                    format!("alert {} ({}): {}", idx + 1, rule.title(), problem),
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+// anything that isn't a TOML literal (number, bool, quoted string, ...) is taken as a bare string
+fn override_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_keys_cover_every_field() {
+        let conf = Config {
+            record_path: Some(String::new()),
+            rest_base_url: Some(String::new()),
+            ws_base_url: Some(String::new()),
+            metrics_addr: Some(String::new()),
+            api_addr: Some(String::new()),
+            shm_path: Some(String::new()),
+            alert_command: Some(String::new()),
+            alert_webhook_url: Some(String::new()),
+            significant_notional: Some(1.0),
+            significant_size_zscore: Some(1.0),
+            significant_sweep_levels: Some(1),
+            significant_mid_distance_bps: Some(1.0),
+            ..Config::default()
+        };
+        let table = toml::Table::try_from(&conf).unwrap();
+
+        let mut fields: Vec<&str> = table.keys().map(String::as_str).collect();
+        let mut keys: Vec<&str> = CONFIG_KEYS.iter().map(|(key, _)| *key).collect();
+        fields.sort();
+        keys.sort();
+        assert_eq!(fields, keys);
+    }
+
+    fn write_config(name: &str, content: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("bmt-config-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn overrides_win_over_the_file() {
+        let path = write_config(
+            "overrides",
+            "audit_interval_secs = 5\nmax_backoff_ms = 100\n",
+        );
+
+        let overrides = [
+            ("max_backoff_ms".to_string(), "250".to_string()),
+            ("market".to_string(), "usdm".to_string()),
+            ("record_path".to_string(), "\"out.rec\"".to_string()),
+        ];
+        let conf = load_config(Some(&path), &overrides, false).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(conf.audit_interval_secs, 5);
+        assert_eq!(conf.max_backoff_ms, 250);
+        assert_eq!(conf.market, Market::UsdM);
+        assert_eq!(conf.record_path.as_deref(), Some("out.rec"));
+    }
+
+    #[test]
+    fn virtual_order_qty_is_read_without_float_noise() {
+        let path = write_config("virtual-qty", "virtual_order_qty = 0.1\n");
+        let conf = load_config(Some(&path), &[], false).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(conf.virtual_order_qty.to_string(), "0.1");
+
+        let overrides = [("virtual_order_qty".to_string(), "2".to_string())];
+        let conf = load_config(None, &overrides, false).unwrap();
+        assert_eq!(conf.virtual_order_qty, Decimal::TWO);
+    }
+
+    #[test]
+    fn rejects_badly_typed_overrides() {
+        let overrides = [("max_backoff_ms".to_string(), "soon".to_string())];
+        assert!(load_config(Some(Path::new("/nonexistent/config.toml")), &[], false).is_err());
+        assert!(
+            load_config(None, &overrides, false)
+                .unwrap_err()
+                .to_string()
+                .contains("--max-backoff-ms")
+        );
+    }
+
+    #[test]
+    fn reports_every_bad_key_with_its_line() {
+        let content = "\
+# comment
+orderbook_initial_snapshot_depth = 300
+max_backoff_ms = \"soon\"
+initial_backoff_ms = 5000
+max_backof_ms = 10
+significant_trade_volume_pct = 5.0
+
+[display]
+rows = 3
+";
+        let (table, origins) = parse_file(content).unwrap();
+        let (_, issues) = resolve(table, &origins, false);
+
+        let lines: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        assert_eq!(lines.len(), 5, "{lines:#?}");
+        assert!(
+            lines[0].starts_with("line 2: orderbook_initial_snapshot_depth: must be one of 5, 10")
+        );
+        assert!(lines[1].starts_with("line 3: max_backoff_ms: invalid type"));
+        assert_eq!(lines[2], "line 5: max_backof_ms: unknown key");
+        assert!(lines[3].starts_with("line 6: significant_trade_volume_pct: must be a fraction"));
+        assert_eq!(lines[4], "line 8: display: unknown key");
+    }
+
+    #[test]
+    fn backoff_bounds_blame_the_key_that_was_set() {
+        let (table, origins) = parse_file("max_backoff_ms = 50\n").unwrap();
+        let (_, issues) = resolve(table, &origins, false);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "max_backoff_ms");
+        assert_eq!(issues[0].origin, KeyOrigin::Line(1));
+    }
+
+    #[test]
+    fn significance_rules_must_be_enabled_to_be_required() {
+        let (table, origins) =
+            parse_file("significant_notional = 50000.0\nsignificance_min_rules = 3\n").unwrap();
+        let (_, issues) = resolve(table, &origins, false);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "significance_min_rules");
+        assert!(issues[0].message.contains("the 2 enabled"));
+    }
+
+    #[test]
+    fn reads_alert_rules_from_tables() {
+        let content = "\
+alert_bell = true
+
+[[alerts]]
+kind = \"spread_ticks\"
+above = 5.0
+clear = 2.0
+
+[[alerts]]
+kind = \"resync\"
+above = 1.0
+";
+        let (table, origins) = parse_file(content).unwrap();
+        let (conf, issues) = resolve(table, &origins, false);
+
+        assert_eq!(conf.alerts.len(), 2);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "line 3: alerts: alert 2 (resync): resync takes no `above`"
+        );
+    }
+
+    #[test]
+    fn lenient_keeps_valid_keys_and_defaults_the_rest() {
+        let path = write_config(
+            "lenient",
+            "max_backoff_ms = 50\nheatmap_sample_ms = 0\naudit_interval_secs = 5\nbogus = 1\n",
+        );
+
+        assert!(load_config(Some(&path), &[], false).is_err());
+        let conf = load_config(Some(&path), &[], true).unwrap();
+
+        let broken = write_config("lenient-syntax", "max_backoff_ms = = 1\n");
+        assert!(load_config(Some(&broken), &[], false).is_err());
+        assert_eq!(
+            load_config(Some(&broken), &[], true)
+                .unwrap()
+                .max_backoff_ms,
+            30000
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        fs::remove_dir_all(broken.parent().unwrap()).unwrap();
+
+        let defaults = Config::default();
+        assert_eq!(conf.audit_interval_secs, 5);
+        assert_eq!(conf.max_backoff_ms, defaults.max_backoff_ms);
+        assert_eq!(conf.heatmap_sample_ms, defaults.heatmap_sample_ms);
+    }
+
+    #[test]
+    fn defaults_and_the_shipped_config_are_valid() {
+        assert!(Config::default().range_errors().is_empty());
+
+        let content =
+            fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/config.toml")).unwrap();
+        let (table, origins) = parse_file(&content).unwrap();
+        assert_eq!(resolve(table, &origins, false).1, vec![]);
+    }
+
+    #[test]
+    fn xdg_paths_follow_the_spec() {
+        let paths = xdg_config_paths(
+            Some("/home/me/.cfg".into()),
+            Some("/home/me".into()),
+            Some("/opt/xdg:relative".into()),
+        );
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/home/me/.cfg/binance-market-terminal/config.toml"),
+                PathBuf::from("/opt/xdg/binance-market-terminal/config.toml"),
+            ]
+        );
+
+        let defaults = xdg_config_paths(None, Some("/home/me".into()), None);
+        assert_eq!(
+            defaults,
+            vec![
+                PathBuf::from("/home/me/.config/binance-market-terminal/config.toml"),
+                PathBuf::from("/etc/xdg/binance-market-terminal/config.toml"),
+            ]
+        );
+    }
+}
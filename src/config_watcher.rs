@@ -0,0 +1,234 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, watch};
+
+use crate::config::{self, CONFIG_KEYS, Config};
+use crate::engine::runtime::EngineCommand;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// keys only read while starting up. a reload keeps their current values and
+// logs that the new ones need a restart
+pub const RESTART_KEYS: &[&str] = &[
+    "recent_trades_starting_capacity",
+    "headless_interval_ms",
+    "record_path",
+    "market",
+    "trade_stream",
+    "rest_base_url",
+    "ws_base_url",
+    "metrics_addr",
+    "api_addr",
+    "shm_path",
+    "shm_depth_levels",
+];
+
+// Polls the config file and pushes valid changes to the engine (as an
+// `EngineCommand::UpdateConfig`) and to whoever holds the returned receiver.
+// Command line overrides keep winning over the file, as they did at startup.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    overrides: Vec<(String, String)>,
+    lenient: bool,
+    current: Arc<Config>,
+    // modification time and length of the file as last loaded
+    last_seen: Option<(SystemTime, u64)>,
+}
+
+impl ConfigWatcher {
+    // `path` doesn't have to exist yet, the config is picked up once it's created
+    pub fn new(
+        path: PathBuf,
+        overrides: Vec<(String, String)>,
+        lenient: bool,
+        current: Arc<Config>,
+    ) -> Self {
+        let last_seen = file_stamp(&path);
+        Self {
+            path,
+            overrides,
+            lenient,
+            current,
+            last_seen,
+        }
+    }
+
+    // runs until the engine goes away
+    pub fn spawn(
+        mut self,
+        command_tx: mpsc::Sender<EngineCommand>,
+    ) -> watch::Receiver<Arc<Config>> {
+        let (config_tx, config_rx) = watch::channel(self.current.clone());
+        tracing::info!("Watching {} for config changes", self.path.display());
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = command_tx.closed() => return,
+                }
+
+                let stamp = file_stamp(&self.path);
+                if stamp.is_none() || stamp == self.last_seen {
+                    continue;
+                }
+                self.last_seen = stamp;
+
+                let Some(conf) = self.reload() else {
+                    continue;
+                };
+                if command_tx
+                    .send(EngineCommand::UpdateConfig(conf.clone()))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+                // nothing may be listening, e.g. when headless
+                let _ = config_tx.send(conf);
+            }
+        });
+
+        config_rx
+    }
+
+    // the config to apply, None when the file is invalid or nothing live changed
+    fn reload(&mut self) -> Option<Arc<Config>> {
+        // even when lenient, a half saved file shouldn't reset everything to the defaults
+        let parsed = std::fs::read_to_string(&self.path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(content.parse::<toml::Table>()?))
+            .and_then(|_| config::load_config(Some(&self.path), &self.overrides, self.lenient));
+        let loaded = match parsed {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                tracing::error!(
+                    "Config reload of {} failed, keeping the current config: {e:#}",
+                    self.path.display()
+                );
+                return None;
+            }
+        };
+
+        let applied = match keep_restart_keys(&self.current, &loaded) {
+            Ok(applied) => applied,
+            Err(e) => {
+                tracing::error!("Config reload failed, keeping the current config: {e:#}");
+                return None;
+            }
+        };
+
+        for key in changed_keys(&applied, &loaded) {
+            tracing::warn!("Config {key} changed, restart to apply it");
+        }
+        let changed = changed_keys(&self.current, &applied);
+        if changed.is_empty() {
+            return None;
+        }
+        tracing::info!("Config reloaded, applying {}", changed.join(", "));
+
+        self.current = Arc::new(applied);
+        Some(self.current.clone())
+    }
+}
+
+fn file_stamp(path: &std::path::Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+// `new` with every restart-only key put back to its value in `current`
+fn keep_restart_keys(current: &Config, new: &Config) -> Result<Config> {
+    let current = toml::Table::try_from(current)?;
+    let mut merged = toml::Table::try_from(new)?;
+    for key in RESTART_KEYS {
+        // unset options are missing from the table
+        match current.get(*key) {
+            Some(value) => merged.insert(key.to_string(), value.clone()),
+            None => merged.remove(*key),
+        };
+    }
+    Ok(merged.try_into()?)
+}
+
+pub fn changed_keys(old: &Config, new: &Config) -> Vec<&'static str> {
+    let (Ok(old), Ok(new)) = (toml::Table::try_from(old), toml::Table::try_from(new)) else {
+        return Vec::new();
+    };
+    CONFIG_KEYS
+        .iter()
+        .map(|(key, _)| *key)
+        .filter(|key| old.get(*key) != new.get(*key))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::market::Market;
+
+    #[test]
+    fn restart_keys_keep_their_running_values() {
+        let current = Config::default();
+        let new = Config {
+            significant_trade_volume_pct: 0.1,
+            market: Market::UsdM,
+            record_path: Some("out.rec".to_string()),
+            ..Config::default()
+        };
+
+        let applied = keep_restart_keys(&current, &new).unwrap();
+
+        assert_eq!(applied.significant_trade_volume_pct, 0.1);
+        assert_eq!(applied.market, Market::Spot);
+        assert_eq!(applied.record_path, None);
+        assert_eq!(
+            changed_keys(&current, &applied),
+            vec!["significant_trade_volume_pct"]
+        );
+        assert_eq!(changed_keys(&applied, &new), vec!["record_path", "market"]);
+    }
+
+    #[test]
+    fn restart_keys_are_config_keys() {
+        for key in RESTART_KEYS {
+            assert!(CONFIG_KEYS.iter().any(|(known, _)| known == key), "{key}");
+        }
+    }
+
+    #[test]
+    fn picks_up_edits_and_skips_invalid_ones() {
+        let dir = std::env::temp_dir().join(format!("bmt-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "min_trades_for_significance = 50\n").unwrap();
+
+        let overrides = vec![("max_backoff_ms".to_string(), "500".to_string())];
+        let current = Arc::new(config::load_config(Some(&path), &overrides, false).unwrap());
+        let mut watcher = ConfigWatcher::new(path.clone(), overrides, false, current);
+
+        std::fs::write(
+            &path,
+            "min_trades_for_significance = 10\nmax_backoff_ms = 1\n",
+        )
+        .unwrap();
+        let conf = watcher.reload().unwrap();
+        assert_eq!(conf.min_trades_for_significance, 10);
+        assert_eq!(conf.max_backoff_ms, 500);
+
+        std::fs::write(&path, "min_trades_for_significance = \"many\"\n").unwrap();
+        assert!(watcher.reload().is_none());
+        std::fs::write(
+            &path,
+            "market = \"usdm\"\nmin_trades_for_significance = 10\n",
+        )
+        .unwrap();
+        assert!(watcher.reload().is_none());
+        assert_eq!(watcher.current.market, Market::Spot);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::binance::types::Trade;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandleInterval {
+    S1,
+    S5,
+    M1,
+    M5,
+    H1,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 5] = [
+        CandleInterval::S1,
+        CandleInterval::S5,
+        CandleInterval::M1,
+        CandleInterval::M5,
+        CandleInterval::H1,
+    ];
+
+    pub fn millis(self) -> u64 {
+        match self {
+            CandleInterval::S1 => 1_000,
+            CandleInterval::S5 => 5_000,
+            CandleInterval::M1 => 60_000,
+            CandleInterval::M5 => 300_000,
+            CandleInterval::H1 => 3_600_000,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            CandleInterval::S1 => 0,
+            CandleInterval::S5 => 1,
+            CandleInterval::M1 => 2,
+            CandleInterval::M5 => 3,
+            CandleInterval::H1 => 4,
+        }
+    }
+}
+
+impl std::fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CandleInterval::S1 => write!(f, "1s"),
+            CandleInterval::S5 => write!(f, "5s"),
+            CandleInterval::M1 => write!(f, "1m"),
+            CandleInterval::M5 => write!(f, "5m"),
+            CandleInterval::H1 => write!(f, "1h"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candle {
+    // exchange time the bar opens at, aligned to the interval
+    pub open_time: u64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    // split by aggressor side
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    pub trade_count: u64,
+}
+
+impl Candle {
+    fn open(open_time: u64, trade: &Trade) -> Self {
+        let mut candle = Candle {
+            open_time,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: Decimal::ZERO,
+            buy_volume: Decimal::ZERO,
+            sell_volume: Decimal::ZERO,
+            trade_count: 0,
+        };
+        candle.add(trade);
+        candle
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.quantity;
+        if trade.is_buyer_maker {
+            self.sell_volume += trade.quantity;
+        } else {
+            self.buy_volume += trade.quantity;
+        }
+        self.trade_count += 1;
+    }
+}
+
+// One interval's bars. Every trade updates the forming bar, while the closed
+// ones only change when a bar rolls over, so they are shared with published
+// snapshots and copied only then.
+#[derive(Debug, Clone, Default)]
+struct Bars {
+    // oldest first
+    closed: Arc<VecDeque<Candle>>,
+    forming: Option<Candle>,
+}
+
+impl Bars {
+    fn len(&self) -> usize {
+        self.closed.len() + self.forming.is_some() as usize
+    }
+
+    // keeps at most `len` bars, the forming one included
+    fn truncate_front(&mut self, len: usize) {
+        let excess = self.len().saturating_sub(len).min(self.closed.len());
+        if excess > 0 {
+            Arc::make_mut(&mut self.closed).drain(..excess);
+        }
+    }
+}
+
+// OHLCV bars for every interval, built from the trade stream. Intervals with
+// no trades get no bar, so consecutive bars aren't always adjacent in time.
+#[derive(Debug, Clone, Default)]
+pub struct CandleSeries {
+    series: [Bars; 5],
+    history_len: usize,
+}
+
+impl CandleSeries {
+    pub fn new(history_len: usize) -> Self {
+        Self {
+            series: Default::default(),
+            history_len: history_len.max(1),
+        }
+    }
+
+    pub fn add_trade(&mut self, trade: &Trade) {
+        for interval in CandleInterval::ALL {
+            let open_time = trade.trade_time / interval.millis() * interval.millis();
+            let bars = &mut self.series[interval.index()];
+
+            match &mut bars.forming {
+                Some(bar) if bar.open_time == open_time => bar.add(trade),
+                Some(bar) if bar.open_time > open_time => {
+                    // a late trade still belongs to its own bar while that bar is kept
+                    if bars.closed.iter().any(|c| c.open_time == open_time) {
+                        let closed = Arc::make_mut(&mut bars.closed);
+                        if let Some(bar) =
+                            closed.iter_mut().rev().find(|c| c.open_time == open_time)
+                        {
+                            bar.add(trade);
+                        }
+                    }
+                }
+                _ => {
+                    let opened = Candle::open(open_time, trade);
+                    if let Some(previous) = bars.forming.replace(opened) {
+                        Arc::make_mut(&mut bars.closed).push_back(previous);
+                    }
+                    bars.truncate_front(self.history_len);
+                }
+            }
+        }
+    }
+
+    // drops the oldest bars if the history shrank
+    pub fn set_history_len(&mut self, history_len: usize) {
+        self.history_len = history_len.max(1);
+        for bars in &mut self.series {
+            bars.truncate_front(self.history_len);
+        }
+    }
+
+    // oldest first, the last bar is still forming
+    pub fn candles(&self, interval: CandleInterval) -> Vec<&Candle> {
+        let bars = &self.series[interval.index()];
+        bars.closed.iter().chain(&bars.forming).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(price: i64, qty: i64, trade_time: u64, is_buyer_maker: bool) -> Trade {
+        Trade {
+            price: Decimal::new(price, 0),
+            quantity: Decimal::new(qty, 0),
+            trade_time,
+            is_buyer_maker,
+        }
+    }
+
+    #[test]
+    fn builds_ohlcv_with_side_split() {
+        let mut series = CandleSeries::new(10);
+        series.add_trade(&trade(100, 1, 1_000, false));
+        series.add_trade(&trade(103, 2, 1_200, true));
+        series.add_trade(&trade(99, 3, 1_400, false));
+        series.add_trade(&trade(101, 1, 1_900, true));
+
+        let bars = series.candles(CandleInterval::S1);
+        assert_eq!(
+            bars,
+            vec![&Candle {
+                open_time: 1_000,
+                open: Decimal::new(100, 0),
+                high: Decimal::new(103, 0),
+                low: Decimal::new(99, 0),
+                close: Decimal::new(101, 0),
+                volume: Decimal::new(7, 0),
+                buy_volume: Decimal::new(4, 0),
+                sell_volume: Decimal::new(3, 0),
+                trade_count: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn each_interval_buckets_independently() {
+        let mut series = CandleSeries::new(10);
+        series.add_trade(&trade(100, 1, 59_000, false));
+        series.add_trade(&trade(101, 1, 61_000, false));
+        series.add_trade(&trade(102, 1, 64_000, false));
+
+        assert_eq!(series.candles(CandleInterval::S1).len(), 3);
+        assert_eq!(series.candles(CandleInterval::S5).len(), 2);
+        let minutes: Vec<(u64, u64)> = series
+            .candles(CandleInterval::M1)
+            .iter()
+            .map(|c| (c.open_time, c.trade_count))
+            .collect();
+        assert_eq!(minutes, vec![(0, 1), (60_000, 2)]);
+        assert_eq!(series.candles(CandleInterval::H1).len(), 1);
+    }
+
+    #[test]
+    fn closed_bars_are_shared_until_a_bar_rolls_over() {
+        let mut series = CandleSeries::new(10);
+        series.add_trade(&trade(100, 1, 1_000, false));
+        series.add_trade(&trade(101, 1, 2_000, false));
+        let published = series.clone();
+        let closed = |series: &CandleSeries| series.series[0].closed.clone();
+
+        // only the forming bar changes
+        series.add_trade(&trade(102, 1, 2_500, false));
+        assert!(Arc::ptr_eq(&closed(&published), &closed(&series)));
+        assert_eq!(published.candles(CandleInterval::S1)[1].trade_count, 1);
+        assert_eq!(series.candles(CandleInterval::S1)[1].trade_count, 2);
+
+        series.add_trade(&trade(103, 1, 3_000, false));
+        assert!(!Arc::ptr_eq(&closed(&published), &closed(&series)));
+        assert_eq!(published.candles(CandleInterval::S1).len(), 2);
+        assert_eq!(series.candles(CandleInterval::S1).len(), 3);
+    }
+
+    #[test]
+    fn keeps_history_bounded_and_folds_late_trades() {
+        let mut series = CandleSeries::new(2);
+        series.add_trade(&trade(100, 1, 1_000, false));
+        series.add_trade(&trade(101, 1, 2_000, false));
+        series.add_trade(&trade(102, 1, 3_000, false));
+        // late, lands in the kept 2s bar
+        series.add_trade(&trade(105, 1, 2_500, false));
+        // late, its bar was already dropped
+        series.add_trade(&trade(90, 1, 1_500, false));
+
+        let bars: Vec<(u64, Decimal, u64)> = series
+            .candles(CandleInterval::S1)
+            .iter()
+            .map(|c| (c.open_time, c.high, c.trade_count))
+            .collect();
+        assert_eq!(
+            bars,
+            vec![
+                (2_000, Decimal::new(105, 0), 2),
+                (3_000, Decimal::new(102, 0), 1)
+            ]
+        );
+    }
+}
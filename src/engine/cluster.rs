@@ -0,0 +1,115 @@
+use rust_decimal::Decimal;
+
+use crate::binance::types::Trade;
+
+// Same-side prints sharing a trade time, taken as one taker order walking the
+// book. `trade` carries the order's average price and total quantity.
+#[derive(Debug, Clone)]
+pub struct TakerOrder {
+    pub trade: Trade,
+    pub notional: Decimal,
+    pub prints: usize,
+    // distinct prices printed at, counted as the order walks further into the book
+    pub levels: usize,
+    // the print furthest into the book
+    pub worst_price: Decimal,
+    // scale of the most precise print, the average is rounded to it
+    price_scale: u32,
+}
+
+impl TakerOrder {
+    pub fn new(print: &Trade) -> Self {
+        Self {
+            trade: print.clone(),
+            notional: print.price * print.quantity,
+            prints: 1,
+            levels: 1,
+            worst_price: print.price,
+            price_scale: print.price.scale(),
+        }
+    }
+
+    pub fn continues(&self, print: &Trade) -> bool {
+        print.trade_time == self.trade.trade_time
+            && print.is_buyer_maker == self.trade.is_buyer_maker
+    }
+
+    pub fn merge(&mut self, print: &Trade) {
+        self.prints += 1;
+        // buys walk up the asks, sells down the bids
+        let further = if print.is_buyer_maker {
+            print.price < self.worst_price
+        } else {
+            print.price > self.worst_price
+        };
+        if further {
+            self.worst_price = print.price;
+            self.levels += 1;
+        }
+
+        self.notional += print.price * print.quantity;
+        self.trade.quantity += print.quantity;
+        self.price_scale = self.price_scale.max(print.price.scale());
+        if !self.trade.quantity.is_zero() {
+            self.trade.price = (self.notional / self.trade.quantity).round_dp(self.price_scale);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn print(price: &str, qty: &str, trade_time: u64, is_buyer_maker: bool) -> Trade {
+        Trade {
+            price: price.parse().unwrap(),
+            quantity: qty.parse().unwrap(),
+            trade_time,
+            is_buyer_maker,
+        }
+    }
+
+    #[test]
+    fn merges_a_sweep_into_one_order() {
+        let mut order = TakerOrder::new(&print("100.00", "1.0", 5, false));
+        for next in [
+            print("100.00", "0.5", 5, false),
+            print("100.01", "1.0", 5, false),
+            print("100.03", "0.5", 5, false),
+        ] {
+            assert!(order.continues(&next));
+            order.merge(&next);
+        }
+
+        assert_eq!(order.prints, 4);
+        assert_eq!(order.levels, 3);
+        assert_eq!(order.worst_price, "100.03".parse().unwrap());
+        assert_eq!(order.trade.quantity, "3.0".parse().unwrap());
+        assert_eq!(order.notional, "300.025".parse().unwrap());
+        // 300.025 / 3 rounded to the prints' precision
+        assert_eq!(order.trade.price, "100.01".parse().unwrap());
+    }
+
+    #[test]
+    fn prints_back_at_a_counted_price_add_no_level() {
+        let mut order = TakerOrder::new(&print("100.00", "1.0", 5, true));
+        for next in [
+            print("99.99", "1.0", 5, true),
+            print("100.00", "1.0", 5, true),
+            print("99.99", "1.0", 5, true),
+        ] {
+            order.merge(&next);
+        }
+
+        assert_eq!(order.prints, 4);
+        assert_eq!(order.levels, 2);
+        assert_eq!(order.worst_price, "99.99".parse().unwrap());
+    }
+
+    #[test]
+    fn side_or_time_changes_start_a_new_order() {
+        let order = TakerOrder::new(&print("100.00", "1.0", 5, false));
+        assert!(!order.continues(&print("100.00", "1.0", 5, true)));
+        assert!(!order.continues(&print("100.00", "1.0", 6, false)));
+    }
+}
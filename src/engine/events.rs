@@ -0,0 +1,15 @@
+use crate::binance::types::{DepthUpdate, Trade};
+
+// room for bursts before slow subscribers start lagging
+pub const EVENT_CHANNEL_CAPACITY: usize = 4096;
+
+// What the engine has validated and applied, in order, for anyone outside it
+// that wants to follow the book rather than poll snapshots
+#[derive(Debug, Clone)]
+pub enum BookEvent {
+    // a delta that passed the sequence checks and was applied to the book
+    Depth { symbol: String, update: DepthUpdate },
+    Trade { symbol: String, trade: Trade },
+    // the book was replaced by a snapshot, earlier deltas no longer apply
+    Reset { symbol: String },
+}
@@ -0,0 +1,357 @@
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use crate::binance::types::Trade;
+use crate::book::orderbook::OrderBook;
+use crate::book::scaler::Scaler;
+
+const WINDOW_1M_MS: u64 = 60_000;
+const WINDOW_5M_MS: u64 = 300_000;
+// seconds of history kept for the sparklines
+pub const FLOW_HISTORY_LEN: usize = 120;
+
+// buy minus sell taker volume
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VolumeDelta {
+    pub qty: Decimal,
+    pub notional: Decimal,
+}
+
+impl VolumeDelta {
+    fn add(&mut self, other: VolumeDelta) {
+        self.qty += other.qty;
+        self.notional += other.notional;
+    }
+
+    fn sub(&mut self, other: VolumeDelta) {
+        self.qty -= other.qty;
+        self.notional -= other.notional;
+    }
+}
+
+// price and size at the top of each side, in ticks
+#[derive(Debug, Clone, Copy)]
+struct BestLevels {
+    bid: (u64, u64),
+    ask: (u64, u64),
+}
+
+impl BestLevels {
+    fn of(book: &OrderBook) -> Option<Self> {
+        let (bid_price, bid_qty) = book.best_bid()?;
+        let (ask_price, ask_qty) = book.best_ask()?;
+        Some(Self {
+            bid: (*bid_price, *bid_qty),
+            ask: (*ask_price, *ask_qty),
+        })
+    }
+
+    // order flow imbalance of the move from `prev` to `self` (Cont, Kukanov and
+    // Stoikov): size added at or above the old best bid counts as buying
+    // pressure, size added at or below the old best ask as selling pressure,
+    // and size that disappears counts the other way
+    fn imbalance_since(&self, prev: &BestLevels) -> i128 {
+        let (bid_price, bid_qty) = (self.bid.0, self.bid.1 as i128);
+        let (prev_bid_price, prev_bid_qty) = (prev.bid.0, prev.bid.1 as i128);
+        let bid_flow = match bid_price.cmp(&prev_bid_price) {
+            Ordering::Greater => bid_qty,
+            Ordering::Equal => bid_qty - prev_bid_qty,
+            Ordering::Less => -prev_bid_qty,
+        };
+
+        let (ask_price, ask_qty) = (self.ask.0, self.ask.1 as i128);
+        let (prev_ask_price, prev_ask_qty) = (prev.ask.0, prev.ask.1 as i128);
+        let ask_flow = match ask_price.cmp(&prev_ask_price) {
+            Ordering::Less => ask_qty,
+            Ordering::Equal => ask_qty - prev_ask_qty,
+            Ordering::Greater => -prev_ask_qty,
+        };
+
+        bid_flow - ask_flow
+    }
+}
+
+// Cumulative volume delta from trades and order flow imbalance from changes at
+// the best levels, since the session started and over rolling windows. Times
+// are exchange times, so replays give the same numbers as the live run.
+#[derive(Debug, Clone, Default)]
+pub struct OrderFlow {
+    cvd: VolumeDelta,
+    cvd_1m: VolumeDelta,
+    cvd_5m: VolumeDelta,
+    // (trade time, signed volume) of the trades still inside the 5m window
+    trades: VecDeque<(u64, VolumeDelta)>,
+    // trades at the front of `trades` that have already left the 1m window
+    trades_past_1m: usize,
+
+    // in qty ticks
+    ofi: i128,
+    ofi_1m: i128,
+    // (event time, imbalance) of the book changes inside the 1m window
+    book_changes: VecDeque<(u64, i128)>,
+    best: Option<BestLevels>,
+
+    // one point per second of exchange time, the current second last
+    cvd_history: VecDeque<f64>,
+    ofi_history: VecDeque<f64>,
+    history_second: Option<u64>,
+}
+
+impl OrderFlow {
+    pub fn new(book: &OrderBook) -> Self {
+        Self {
+            best: BestLevels::of(book),
+            ..Default::default()
+        }
+    }
+
+    pub fn add_trade(&mut self, trade: &Trade) {
+        let sign = if trade.is_buyer_maker {
+            Decimal::NEGATIVE_ONE
+        } else {
+            Decimal::ONE
+        };
+        let delta = VolumeDelta {
+            qty: trade.quantity * sign,
+            notional: trade.quantity * trade.price * sign,
+        };
+
+        self.cvd.add(delta);
+        self.cvd_1m.add(delta);
+        self.cvd_5m.add(delta);
+        self.trades.push_back((trade.trade_time, delta));
+        self.expire_trades(trade.trade_time);
+
+        self.advance_history(trade.trade_time);
+        if let (Some(point), Some(cvd)) = (self.cvd_history.back_mut(), self.cvd.qty.to_f64()) {
+            *point = cvd;
+        }
+    }
+
+    // call after every batch of deltas applied to the book
+    pub fn observe_book(&mut self, book: &OrderBook, event_time: u64) {
+        let best = BestLevels::of(book);
+        let imbalance = match (&best, &self.best) {
+            (Some(best), Some(prev)) => best.imbalance_since(prev),
+            _ => 0,
+        };
+        self.best = best;
+
+        self.advance_history(event_time);
+        if imbalance != 0 {
+            self.ofi += imbalance;
+            self.ofi_1m += imbalance;
+            self.book_changes.push_back((event_time, imbalance));
+            if let Some(point) = self.ofi_history.back_mut() {
+                *point += imbalance as f64;
+            }
+        }
+        let cutoff = event_time.saturating_sub(WINDOW_1M_MS);
+        while let Some((time, imbalance)) = self.book_changes.front() {
+            if *time >= cutoff {
+                break;
+            }
+            self.ofi_1m -= imbalance;
+            self.book_changes.pop_front();
+        }
+    }
+
+    // the book was replaced by a snapshot, measure changes from it rather than
+    // from the book it replaced
+    pub fn rebase(&mut self, book: &OrderBook) {
+        self.best = BestLevels::of(book);
+    }
+
+    pub fn cvd(&self) -> VolumeDelta {
+        self.cvd
+    }
+
+    pub fn cvd_1m(&self) -> VolumeDelta {
+        self.cvd_1m
+    }
+
+    pub fn cvd_5m(&self) -> VolumeDelta {
+        self.cvd_5m
+    }
+
+    pub fn ofi(&self, scaler: &Scaler) -> Decimal {
+        ticks_to_qty(self.ofi, scaler)
+    }
+
+    pub fn ofi_1m(&self, scaler: &Scaler) -> Decimal {
+        ticks_to_qty(self.ofi_1m, scaler)
+    }
+
+    // session cvd (qty) at the end of each second
+    pub fn cvd_history(&self) -> &VecDeque<f64> {
+        &self.cvd_history
+    }
+
+    // imbalance (qty ticks) within each second
+    pub fn ofi_history(&self) -> &VecDeque<f64> {
+        &self.ofi_history
+    }
+
+    fn expire_trades(&mut self, now: u64) {
+        let cutoff_1m = now.saturating_sub(WINDOW_1M_MS);
+        let cutoff_5m = now.saturating_sub(WINDOW_5M_MS);
+
+        while let Some((time, delta)) = self.trades.front().copied() {
+            if time >= cutoff_5m {
+                break;
+            }
+            self.cvd_5m.sub(delta);
+            if self.trades_past_1m == 0 {
+                // old enough for both windows without passing through the 1m check
+                self.cvd_1m.sub(delta);
+            } else {
+                self.trades_past_1m -= 1;
+            }
+            self.trades.pop_front();
+        }
+
+        while let Some((time, delta)) = self.trades.get(self.trades_past_1m).copied() {
+            if time >= cutoff_1m {
+                break;
+            }
+            self.cvd_1m.sub(delta);
+            self.trades_past_1m += 1;
+        }
+    }
+
+    // start a point for every second up to `time`, carrying the cvd forward
+    fn advance_history(&mut self, time: u64) {
+        let second = time / 1000;
+        let missing = match self.history_second {
+            Some(current) if second <= current => return,
+            Some(current) => (second - current).min(FLOW_HISTORY_LEN as u64) as usize,
+            None => 1,
+        };
+        self.history_second = Some(second);
+
+        let cvd = self.cvd_history.back().copied().unwrap_or_default();
+        for _ in 0..missing {
+            self.cvd_history.push_back(cvd);
+            self.ofi_history.push_back(0.0);
+        }
+        while self.cvd_history.len() > FLOW_HISTORY_LEN {
+            self.cvd_history.pop_front();
+            self.ofi_history.pop_front();
+        }
+    }
+}
+
+fn ticks_to_qty(ticks: i128, scaler: &Scaler) -> Decimal {
+    let qty = scaler.ticks_to_qty(ticks.unsigned_abs() as u64);
+    if ticks < 0 { -qty } else { qty }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::{DepthSnapshot, DepthUpdate};
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::ONE, Decimal::ONE)
+    }
+
+    fn book(bid: (&str, &str), ask: (&str, &str)) -> OrderBook {
+        OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![[bid.0.to_string(), bid.1.to_string()]],
+                asks: vec![[ask.0.to_string(), ask.1.to_string()]],
+            },
+            &scaler(),
+        )
+        .unwrap()
+    }
+
+    fn trade(price: i64, qty: i64, trade_time: u64, is_buyer_maker: bool) -> Trade {
+        Trade {
+            price: Decimal::from(price),
+            quantity: Decimal::from(qty),
+            trade_time,
+            is_buyer_maker,
+        }
+    }
+
+    #[test]
+    fn cvd_counts_buys_against_sells_per_window() {
+        let mut flow = OrderFlow::default();
+        flow.add_trade(&trade(100, 5, 0, false));
+        flow.add_trade(&trade(100, 2, 200_000, true));
+        flow.add_trade(&trade(110, 1, 290_000, false));
+
+        assert_eq!(flow.cvd().qty, Decimal::from(4));
+        assert_eq!(flow.cvd().notional, Decimal::from(410));
+        assert_eq!(flow.cvd_5m().qty, Decimal::from(4));
+        // only the last buy is still inside the 1m window
+        assert_eq!(flow.cvd_1m().qty, Decimal::from(1));
+
+        flow.add_trade(&trade(110, 1, 600_000, true));
+        assert_eq!(flow.cvd_5m().qty, Decimal::from(-1));
+        assert_eq!(flow.cvd_1m().qty, Decimal::from(-1));
+        assert_eq!(flow.cvd().qty, Decimal::from(3));
+    }
+
+    #[test]
+    fn ofi_follows_best_level_changes() {
+        let mut book = book(("100", "5"), ("102", "5"));
+        let mut flow = OrderFlow::new(&book);
+
+        let mut apply = |bids: &[(&str, &str)], asks: &[(&str, &str)], time| {
+            let levels = |levels: &[(&str, &str)]| {
+                levels
+                    .iter()
+                    .map(|(price, qty)| [price.to_string(), qty.to_string()])
+                    .collect()
+            };
+            let update = DepthUpdate {
+                event_time: time,
+                first_update_id: 0,
+                final_update_id: 0,
+                prev_final_update_id: None,
+                b: levels(bids),
+                a: levels(asks),
+            };
+            book.apply_update(&update, &scaler()).unwrap();
+            flow.observe_book(&book, time);
+            flow.clone()
+        };
+
+        // more size on the bid
+        let flow_now = apply(&[("100", "8")], &[], 1_000);
+        assert_eq!(flow_now.ofi(&scaler()), Decimal::from(3));
+        // a better bid counts in full
+        apply(&[("101", "4")], &[], 1_500);
+        // the best ask was taken out, the selling pressure it held is gone
+        let flow_now = apply(&[], &[("102", "0"), ("103", "2")], 2_000);
+        assert_eq!(flow_now.ofi(&scaler()), Decimal::from(12));
+
+        // size joining the ask pushes the other way, and the rest has left the 1m window
+        let flow_now = apply(&[], &[("103", "6")], 70_000);
+        assert_eq!(flow_now.ofi(&scaler()), Decimal::from(8));
+        assert_eq!(flow_now.ofi_1m(&scaler()), Decimal::from(-4));
+        assert_eq!(flow_now.ofi_history().back(), Some(&-4.0));
+    }
+
+    #[test]
+    fn history_keeps_one_point_per_second() {
+        let mut flow = OrderFlow::default();
+        flow.add_trade(&trade(100, 2, 1_000, false));
+        flow.add_trade(&trade(100, 1, 1_900, true));
+        flow.add_trade(&trade(100, 3, 4_100, false));
+
+        assert_eq!(
+            flow.cvd_history().iter().copied().collect::<Vec<_>>(),
+            vec![1.0, 1.0, 1.0, 4.0]
+        );
+
+        flow.add_trade(&trade(100, 1, 1_000_000, false));
+        assert_eq!(flow.cvd_history().len(), FLOW_HISTORY_LEN);
+        assert_eq!(flow.cvd_history().back(), Some(&5.0));
+    }
+}
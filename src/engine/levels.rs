@@ -0,0 +1,452 @@
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+
+use crate::binance::types::{DepthUpdate, Trade};
+use crate::book::orderbook::{BookSide, OrderBook};
+use crate::book::scaler::Scaler;
+use crate::config::Config;
+
+// how far apart in exchange time a trade and the level decrease it explains can be
+const FILL_MATCH_WINDOW_MS: u64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelChange {
+    Add,
+    Cancel,
+    // a decrease matched against trades at that price
+    Fill,
+}
+
+// qty in ticks added, cancelled and filled over the window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelFlow {
+    pub added: u64,
+    pub cancelled: u64,
+    pub filled: u64,
+}
+
+impl LevelFlow {
+    fn slot(&mut self, change: LevelChange) -> &mut u64 {
+        match change {
+            LevelChange::Add => &mut self.added,
+            LevelChange::Cancel => &mut self.cancelled,
+            LevelChange::Fill => &mut self.filled,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added == 0 && self.cancelled == 0 && self.filled == 0
+    }
+}
+
+// per level and per side flow over the last `window_ms`
+#[derive(Debug, Clone, Default)]
+pub struct LevelActivity {
+    pub bids: BTreeMap<u64, LevelFlow>,
+    pub asks: BTreeMap<u64, LevelFlow>,
+    pub bid_total: LevelFlow,
+    pub ask_total: LevelFlow,
+    pub window_ms: u64,
+}
+
+impl LevelActivity {
+    pub fn level(&self, side: BookSide, price: u64) -> LevelFlow {
+        let levels = match side {
+            BookSide::Bid => &self.bids,
+            BookSide::Ask => &self.asks,
+        };
+        levels.get(&price).copied().unwrap_or_default()
+    }
+
+    // ticks over the window to qty per second
+    pub fn rate(&self, qty_ticks: u64, scaler: &Scaler) -> f64 {
+        let secs = self.window_ms.max(1) as f64 / 1000.0;
+        scaler.ticks_to_qty(qty_ticks).to_f64().unwrap_or(0.0) / secs
+    }
+
+    fn record(&mut self, event: &LevelEvent, removing: bool) {
+        let (levels, total) = match event.side {
+            BookSide::Bid => (&mut self.bids, &mut self.bid_total),
+            BookSide::Ask => (&mut self.asks, &mut self.ask_total),
+        };
+        let level = levels.entry(event.price).or_default();
+        for flow in [level, total] {
+            let slot = flow.slot(event.change);
+            *slot = if removing {
+                slot.saturating_sub(event.qty)
+            } else {
+                *slot + event.qty
+            };
+        }
+        if removing && levels.get(&event.price).is_some_and(LevelFlow::is_empty) {
+            levels.remove(&event.price);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LevelEvent {
+    pub time: u64,
+    pub side: BookSide,
+    pub price: u64,
+    pub change: LevelChange,
+    pub qty: u64,
+}
+
+// taker volume not yet matched to a level decrease
+#[derive(Debug)]
+struct PendingFill {
+    time: u64,
+    side: BookSide,
+    price: u64,
+    qty: u64,
+}
+
+// sizeable add near the touch, watched for being pulled before it trades
+#[derive(Debug)]
+struct Placement {
+    time: u64,
+    qty: u64,
+}
+
+// Classifies every level change of the depth stream as an add, a cancel, or a
+// fill when trades at that price explain the decrease, and keeps rolling flow
+// per level and side. Adds near the touch that are cancelled again before
+// trading, within `spoof_max_lifetime_ms`, are counted as pulls.
+#[derive(Debug, Default)]
+pub struct LevelTracker {
+    events: VecDeque<LevelEvent>,
+    pending_fills: VecDeque<PendingFill>,
+    placements: HashMap<(BookSide, u64), Placement>,
+    // shared with the published snapshots, only copied when it changes
+    activity: Arc<LevelActivity>,
+    // index into `events` of the first one from the latest update
+    update_start: usize,
+    bid_pulls: u64,
+    ask_pulls: u64,
+}
+
+impl LevelTracker {
+    pub fn add_trade(&mut self, trade: &Trade, scaler: &Scaler) {
+        let (Some(price), Some(qty)) = (
+            scaler.price_to_ticks(&trade.price.to_string()),
+            scaler.qty_to_ticks(&trade.quantity.to_string()),
+        ) else {
+            return;
+        };
+        // a taker sell fills resting bids
+        let side = if trade.is_buyer_maker {
+            BookSide::Bid
+        } else {
+            BookSide::Ask
+        };
+        self.pending_fills.push_back(PendingFill {
+            time: trade.trade_time,
+            side,
+            price,
+            qty,
+        });
+    }
+
+    // call before `update` is applied to `book`
+    pub fn observe_update(
+        &mut self,
+        book: &OrderBook,
+        update: &DepthUpdate,
+        scaler: &Scaler,
+        conf: &Config,
+    ) {
+        let time = update.event_time;
+        self.prune(time, conf);
+        self.update_start = self.events.len();
+
+        let sides = [
+            (BookSide::Bid, &update.b, book.bids(), book.best_bid()),
+            (BookSide::Ask, &update.a, book.asks(), book.best_ask()),
+        ];
+        for (side, levels, resting, best) in sides {
+            let best = best.map(|(price, _)| *price);
+            for [price, qty] in levels {
+                let (Some(price), Some(qty)) =
+                    (scaler.price_to_ticks(price), scaler.qty_to_ticks(qty))
+                else {
+                    continue;
+                };
+                let old = resting.get(&price).copied().unwrap_or(0);
+                if qty > old {
+                    self.added(side, price, qty - old, best, time, scaler, conf);
+                } else if qty < old {
+                    self.removed(side, price, old - qty, time);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn added(
+        &mut self,
+        side: BookSide,
+        price: u64,
+        qty: u64,
+        best: Option<u64>,
+        time: u64,
+        scaler: &Scaler,
+        conf: &Config,
+    ) {
+        self.push(side, price, LevelChange::Add, qty, time);
+
+        // ticks behind the touch, 0 when improving on it
+        let distance = best.map(|best| match side {
+            BookSide::Bid => best.saturating_sub(price),
+            BookSide::Ask => price.saturating_sub(best),
+        });
+        let notional = scaler.ticks_to_price(price) * scaler.ticks_to_qty(qty);
+        let near_touch = distance.is_some_and(|d| d <= conf.spoof_touch_ticks);
+        if near_touch && notional.to_f64().unwrap_or(0.0) >= conf.spoof_min_notional {
+            self.placements
+                .entry((side, price))
+                .and_modify(|placement| placement.qty += qty)
+                .or_insert(Placement { time, qty });
+        }
+    }
+
+    fn removed(&mut self, side: BookSide, price: u64, qty: u64, time: u64) {
+        let mut unexplained = qty;
+        for fill in self
+            .pending_fills
+            .iter_mut()
+            .filter(|fill| fill.side == side && fill.price == price)
+        {
+            let matched = fill.qty.min(unexplained);
+            fill.qty -= matched;
+            unexplained -= matched;
+            if unexplained == 0 {
+                break;
+            }
+        }
+        self.pending_fills.retain(|fill| fill.qty > 0);
+
+        let filled = qty - unexplained;
+        if filled > 0 {
+            self.push(side, price, LevelChange::Fill, filled, time);
+            // it traded, so it wasn't only there to be seen
+            self.placements.remove(&(side, price));
+        }
+        if unexplained > 0 {
+            self.push(side, price, LevelChange::Cancel, unexplained, time);
+            if let Some(placement) = self.placements.get_mut(&(side, price)) {
+                placement.qty = placement.qty.saturating_sub(unexplained);
+                if placement.qty == 0 {
+                    self.placements.remove(&(side, price));
+                    match side {
+                        BookSide::Bid => self.bid_pulls += 1,
+                        BookSide::Ask => self.ask_pulls += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, side: BookSide, price: u64, change: LevelChange, qty: u64, time: u64) {
+        let event = LevelEvent {
+            time,
+            side,
+            price,
+            change,
+            qty,
+        };
+        Arc::make_mut(&mut self.activity).record(&event, false);
+        self.events.push_back(event);
+    }
+
+    fn prune(&mut self, now: u64, conf: &Config) {
+        let window_ms = conf.level_events_window_secs * 1000;
+        if self.activity.window_ms != window_ms {
+            Arc::make_mut(&mut self.activity).window_ms = window_ms;
+        }
+        let cutoff = now.saturating_sub(window_ms);
+        while let Some(event) = self.events.front().copied() {
+            if event.time >= cutoff {
+                break;
+            }
+            Arc::make_mut(&mut self.activity).record(&event, true);
+            self.events.pop_front();
+        }
+
+        let fill_cutoff = now.saturating_sub(FILL_MATCH_WINDOW_MS);
+        self.pending_fills.retain(|fill| fill.time >= fill_cutoff);
+        let placement_cutoff = now.saturating_sub(conf.spoof_max_lifetime_ms);
+        self.placements
+            .retain(|_, placement| placement.time >= placement_cutoff);
+    }
+
+    // the book was replaced, so nothing in flight can be matched against it
+    pub fn rebase(&mut self) {
+        self.pending_fills.clear();
+        self.placements.clear();
+    }
+
+    // what the latest `observe_update` classified
+    pub fn update_events(&self) -> impl Iterator<Item = &LevelEvent> {
+        self.events.range(self.update_start..)
+    }
+
+    pub fn activity(&self) -> &Arc<LevelActivity> {
+        &self.activity
+    }
+
+    pub fn pulls(&self) -> (u64, u64) {
+        (self.bid_pulls, self.ask_pulls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+    use rust_decimal::Decimal;
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3))
+    }
+
+    fn book() -> OrderBook {
+        OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![["100.00".to_string(), "2.000".to_string()]],
+                asks: vec![["100.01".to_string(), "2.000".to_string()]],
+            },
+            &scaler(),
+        )
+        .unwrap()
+    }
+
+    fn update(time: u64, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> DepthUpdate {
+        let levels = |levels: &[(&str, &str)]| {
+            levels
+                .iter()
+                .map(|(p, q)| [p.to_string(), q.to_string()])
+                .collect()
+        };
+        DepthUpdate {
+            event_time: time,
+            first_update_id: 2,
+            final_update_id: 2,
+            prev_final_update_id: None,
+            b: levels(bids),
+            a: levels(asks),
+        }
+    }
+
+    fn conf() -> Config {
+        Config {
+            level_events_window_secs: 10,
+            spoof_touch_ticks: 2,
+            spoof_max_lifetime_ms: 1_000,
+            spoof_min_notional: 100.0,
+            ..Config::default()
+        }
+    }
+
+    // applies `update` the way the engine does
+    fn apply(tracker: &mut LevelTracker, book: &mut OrderBook, update: DepthUpdate) {
+        tracker.observe_update(book, &update, &scaler(), &conf());
+        book.apply_update(&update, &scaler()).unwrap();
+    }
+
+    #[test]
+    fn splits_decreases_into_fills_and_cancels() {
+        let (mut tracker, mut book) = (LevelTracker::default(), book());
+        // a taker buy of 0.5 at the ask, then the ask drops by 1.5
+        tracker.add_trade(
+            &Trade {
+                price: "100.01".parse().unwrap(),
+                quantity: "0.5".parse().unwrap(),
+                trade_time: 100,
+                is_buyer_maker: false,
+            },
+            &scaler(),
+        );
+        apply(
+            &mut tracker,
+            &mut book,
+            update(150, &[("100.00", "3.000")], &[("100.01", "0.500")]),
+        );
+
+        let activity = tracker.activity();
+        assert_eq!(
+            activity.level(BookSide::Ask, 10001),
+            LevelFlow {
+                added: 0,
+                cancelled: 1000,
+                filled: 500,
+            }
+        );
+        assert_eq!(activity.bid_total.added, 1000);
+        assert_eq!(activity.rate(activity.ask_total.cancelled, &scaler()), 0.1);
+
+        // both fall out of the window
+        apply(&mut tracker, &mut book, update(10_151, &[], &[]));
+        assert!(tracker.activity().asks.is_empty());
+        assert_eq!(tracker.activity().ask_total, LevelFlow::default());
+    }
+
+    #[test]
+    fn published_activity_is_only_copied_when_it_changes() {
+        let (mut tracker, mut book) = (LevelTracker::default(), book());
+        apply(
+            &mut tracker,
+            &mut book,
+            update(100, &[("100.00", "3.000")], &[]),
+        );
+        let published = tracker.activity().clone();
+
+        // nothing at any level changed
+        apply(&mut tracker, &mut book, update(200, &[], &[]));
+        assert!(Arc::ptr_eq(&published, tracker.activity()));
+
+        apply(
+            &mut tracker,
+            &mut book,
+            update(300, &[("100.00", "4.000")], &[]),
+        );
+        assert!(!Arc::ptr_eq(&published, tracker.activity()));
+        assert_eq!(published.bid_total.added, 1000);
+        assert_eq!(tracker.activity().bid_total.added, 2000);
+    }
+
+    #[test]
+    fn counts_size_pulled_from_near_the_touch() {
+        let (mut tracker, mut book) = (LevelTracker::default(), book());
+        // 5 lots join one tick behind the best bid, and leave again untraded
+        apply(
+            &mut tracker,
+            &mut book,
+            update(100, &[("99.99", "5.000")], &[]),
+        );
+        apply(&mut tracker, &mut book, update(600, &[("99.99", "0")], &[]));
+        assert_eq!(tracker.pulls(), (1, 0));
+
+        // too far from the touch
+        apply(
+            &mut tracker,
+            &mut book,
+            update(700, &[("99.90", "5.000")], &[]),
+        );
+        apply(&mut tracker, &mut book, update(800, &[("99.90", "0")], &[]));
+        // cancelled only after the lifetime ran out
+        apply(
+            &mut tracker,
+            &mut book,
+            update(900, &[("99.99", "5.000")], &[]),
+        );
+        apply(
+            &mut tracker,
+            &mut book,
+            update(2_000, &[("99.99", "0")], &[]),
+        );
+        assert_eq!(tracker.pulls(), (1, 0));
+    }
+}
@@ -1,10 +1,13 @@
 use rust_decimal::Decimal;
+use serde::Serialize;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time;
 
 use crate::{
-    binance::types::Trade,
+    binance::types::{MarkPriceUpdate, Trade},
     book::{orderbook::OrderBook, scaler::Scaler},
+    engine::{flow::OrderFlow, levels::LevelTracker},
 };
 
 fn compute_latencies(event_time: u64, received_at: time::Instant) -> (u64, u64) {
@@ -20,7 +23,81 @@ fn compute_latencies(event_time: u64, received_at: time::Instant) -> (u64, u64)
     (total_lag_ms, network_lag_ms)
 }
 
-#[derive(Clone)]
+// upper bounds (inclusive) of the lag histogram buckets, in ms
+pub const LAG_BUCKETS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+// Distribution of every lag observed since startup, for the /metrics exporter
+#[derive(Clone, Default, Debug)]
+pub struct LagHistogram {
+    // per bucket, not cumulative. the last slot holds anything over the largest bound
+    counts: [u64; LAG_BUCKETS_MS.len() + 1],
+    sum_ms: u64,
+}
+
+impl LagHistogram {
+    pub fn observe(&mut self, lag_ms: u64) {
+        let bucket = LAG_BUCKETS_MS.partition_point(|&bound| bound < lag_ms);
+        self.counts[bucket] += 1;
+        self.sum_ms += lag_ms;
+    }
+
+    // (upper bound, observations at or below it) for each bucket, then the +Inf total
+    pub fn cumulative(&self) -> impl Iterator<Item = (Option<u64>, u64)> + '_ {
+        let bounds = LAG_BUCKETS_MS.iter().map(|&b| Some(b)).chain([None]);
+        bounds
+            .zip(self.counts.iter())
+            .scan(0, |total, (bound, count)| {
+                *total += count;
+                Some((bound, *total))
+            })
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn sum_ms(&self) -> u64 {
+        self.sum_ms
+    }
+}
+
+// Counters for the combined stream, which is shared by every symbol
+#[derive(Default, Debug)]
+pub struct FeedStats {
+    connections: AtomicU64,
+    // every attempt after the first successful connection, plus retries of that one
+    reconnect_attempts: AtomicU64,
+    // frames that couldn't be parsed into an event
+    parse_errors: AtomicU64,
+}
+
+impl FeedStats {
+    pub fn record_connection(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_reconnect_attempt(&self) {
+        self.reconnect_attempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_parse_error(&self) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connections(&self) -> u64 {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn reconnect_attempts(&self) -> u64 {
+        self.reconnect_attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn parse_errors(&self) -> u64 {
+        self.parse_errors.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone, Serialize)]
 pub struct MarketMetrics {
     // Orderbook metrics
     pub spread: Option<Decimal>,
@@ -36,15 +113,66 @@ pub struct MarketMetrics {
     pub vwap_1m: Option<Decimal>,
     pub total_trades: u64,
 
+    // Order flow metrics, cumulative volume delta is buy minus sell taker volume
+    pub cvd_qty: Decimal,
+    pub cvd_notional: Decimal,
+    pub cvd_qty_1m: Decimal,
+    pub cvd_notional_1m: Decimal,
+    pub cvd_qty_5m: Decimal,
+    pub cvd_notional_5m: Decimal,
+    // order flow imbalance at the best levels, in base asset qty
+    pub ofi: Decimal,
+    pub ofi_1m: Decimal,
+    #[serde(skip)]
+    pub cvd_history: VecDeque<f64>,
+    #[serde(skip)]
+    pub ofi_history: VecDeque<f64>,
+
+    // Level event metrics, base asset qty per second over the level events window
+    pub bid_add_rate: f64,
+    pub bid_cancel_rate: f64,
+    pub bid_fill_rate: f64,
+    pub ask_add_rate: f64,
+    pub ask_cancel_rate: f64,
+    pub ask_fill_rate: f64,
+    // sized adds near the touch cancelled again before trading
+    pub bid_pulls: u64,
+    pub ask_pulls: u64,
+
+    // Futures metrics, None on spot
+    pub mark_price: Option<Decimal>,
+    pub index_price: Option<Decimal>,
+    pub funding_rate: Option<Decimal>,
+    pub next_funding_time: Option<u64>,
+
+    // Book health metrics, counted each time the book enters the state
+    pub crossed_book_events: u64,
+    pub locked_book_events: u64,
+
+    // Book audit metrics
+    pub audits_run: u64,
+    pub audit_divergences: u64,
+    pub last_audit_mismatches: usize,
+
     // System metrics
     pub updates_per_second: f64,
+    // depth updates, trades and mark prices handled
+    pub messages_processed: u64,
+    pub gaps_detected: u64,
+    // times the book was thrown away to wait for a fresh snapshot
+    pub resyncs: u64,
 
     // latency tracking
     pub orderbook_lag_ms: Option<u64>,
     pub orderbook_network_lag_ms: Option<u64>,
     pub trade_lag_ms: Option<u64>,
     pub trade_network_lag_ms: Option<u64>,
+    #[serde(skip)]
+    pub orderbook_lag_histogram: LagHistogram,
+    #[serde(skip)]
+    pub trade_lag_histogram: LagHistogram,
 
+    #[serde(skip)]
     imbalance_depth_levels: usize,
 }
 
@@ -55,6 +183,11 @@ impl MarketMetrics {
             ..Default::default()
         }
     }
+
+    pub fn set_imbalance_depth_levels(&mut self, levels: usize) {
+        self.imbalance_depth_levels = levels;
+    }
+
     // Compute only orderbook-related metrics
     pub fn compute_book_metrics(
         &mut self,
@@ -74,6 +207,7 @@ impl MarketMetrics {
             .and_then(Decimal::from_f64_retain);
 
         let (total_lag, network_lag) = compute_latencies(event_time, received_at);
+        self.orderbook_lag_histogram.observe(total_lag);
         self.orderbook_lag_ms = Some(total_lag);
         self.orderbook_network_lag_ms = Some(network_lag);
     }
@@ -112,10 +246,55 @@ impl MarketMetrics {
         self.total_trades = total_trades;
 
         let (total_lag, network_lag) = compute_latencies(event_time, received_at);
+        self.trade_lag_histogram.observe(total_lag);
         self.trade_lag_ms = Some(total_lag);
         self.trade_network_lag_ms = Some(network_lag);
     }
 
+    pub fn update_order_flow(&mut self, flow: &OrderFlow, scaler: &Scaler) {
+        let (cvd, cvd_1m, cvd_5m) = (flow.cvd(), flow.cvd_1m(), flow.cvd_5m());
+        self.cvd_qty = cvd.qty;
+        self.cvd_notional = cvd.notional;
+        self.cvd_qty_1m = cvd_1m.qty;
+        self.cvd_notional_1m = cvd_1m.notional;
+        self.cvd_qty_5m = cvd_5m.qty;
+        self.cvd_notional_5m = cvd_5m.notional;
+        self.ofi = flow.ofi(scaler);
+        self.ofi_1m = flow.ofi_1m(scaler);
+        self.cvd_history.clone_from(flow.cvd_history());
+        self.ofi_history.clone_from(flow.ofi_history());
+    }
+
+    pub fn update_level_events(&mut self, levels: &LevelTracker, scaler: &Scaler) {
+        let activity = levels.activity();
+        let rate = |ticks| activity.rate(ticks, scaler);
+        self.bid_add_rate = rate(activity.bid_total.added);
+        self.bid_cancel_rate = rate(activity.bid_total.cancelled);
+        self.bid_fill_rate = rate(activity.bid_total.filled);
+        self.ask_add_rate = rate(activity.ask_total.added);
+        self.ask_cancel_rate = rate(activity.ask_total.cancelled);
+        self.ask_fill_rate = rate(activity.ask_total.filled);
+        (self.bid_pulls, self.ask_pulls) = levels.pulls();
+    }
+
+    pub fn update_mark_price(&mut self, update: &MarkPriceUpdate) {
+        self.mark_price = Some(update.mark_price);
+        self.index_price = Some(update.index_price);
+        self.funding_rate = update.funding_rate;
+        self.next_funding_time = update
+            .funding_rate
+            .is_some()
+            .then_some(update.next_funding_time);
+    }
+
+    pub fn record_audit(&mut self, mismatched_levels: usize) {
+        self.audits_run += 1;
+        if mismatched_levels > 0 {
+            self.audit_divergences += 1;
+        }
+        self.last_audit_mismatches = mismatched_levels;
+    }
+
     pub fn update_performance_metrics(&mut self, updates_per_second: f64) {
         self.updates_per_second = updates_per_second;
     }
@@ -134,12 +313,66 @@ impl Default for MarketMetrics {
             total_trades: 0,
             buy_ratio_1m: None,
             vwap_1m: None,
+            cvd_qty: Decimal::ZERO,
+            cvd_notional: Decimal::ZERO,
+            cvd_qty_1m: Decimal::ZERO,
+            cvd_notional_1m: Decimal::ZERO,
+            cvd_qty_5m: Decimal::ZERO,
+            cvd_notional_5m: Decimal::ZERO,
+            ofi: Decimal::ZERO,
+            ofi_1m: Decimal::ZERO,
+            cvd_history: VecDeque::new(),
+            ofi_history: VecDeque::new(),
+            bid_add_rate: 0.0,
+            bid_cancel_rate: 0.0,
+            bid_fill_rate: 0.0,
+            ask_add_rate: 0.0,
+            ask_cancel_rate: 0.0,
+            ask_fill_rate: 0.0,
+            bid_pulls: 0,
+            ask_pulls: 0,
+            mark_price: None,
+            index_price: None,
+            funding_rate: None,
+            next_funding_time: None,
+            crossed_book_events: 0,
+            locked_book_events: 0,
+            audits_run: 0,
+            audit_divergences: 0,
+            last_audit_mismatches: 0,
             updates_per_second: 0.0,
+            messages_processed: 0,
+            gaps_detected: 0,
+            resyncs: 0,
             orderbook_lag_ms: None,
             orderbook_network_lag_ms: None,
             trade_lag_ms: None,
             trade_network_lag_ms: None,
+            orderbook_lag_histogram: LagHistogram::default(),
+            trade_lag_histogram: LagHistogram::default(),
             imbalance_depth_levels: 10,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lag_histogram_buckets_are_cumulative() {
+        let mut histogram = LagHistogram::default();
+        for lag in [0, 1, 2, 30, 20_000] {
+            histogram.observe(lag);
+        }
+
+        let buckets: Vec<(Option<u64>, u64)> = histogram.cumulative().collect();
+        assert_eq!(buckets[0], (Some(1), 2));
+        assert_eq!(buckets[1], (Some(5), 3));
+        assert_eq!(buckets[4], (Some(50), 4));
+        assert_eq!(buckets[11], (Some(10000), 4));
+        assert_eq!(buckets[12], (None, 5));
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.sum_ms(), 20_033);
+    }
+}
@@ -1,3 +1,11 @@
+pub mod candles;
+pub mod cluster;
+pub mod events;
+pub mod flow;
+pub mod levels;
 pub mod metrics;
+pub mod queue;
 pub mod runtime;
+pub mod significance;
 pub mod state;
+pub mod symbol;
@@ -0,0 +1,377 @@
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::binance::types::Trade;
+use crate::book::orderbook::{BookSide, OrderBook};
+use crate::book::scaler::Scaler;
+use crate::engine::levels::{LevelActivity, LevelChange, LevelEvent};
+
+// A hypothetical resting order. It never reaches the exchange, it only joins
+// the back of the visible queue at its price and is moved up as that queue
+// trades or is cancelled.
+#[derive(Debug, Clone)]
+struct VirtualOrder {
+    id: u64,
+    side: BookSide,
+    price: u64,
+    qty: u64,
+    placed_at: u64,
+    // resting qty in front of it
+    ahead: u64,
+    filled: u64,
+    filled_at: Option<u64>,
+}
+
+impl VirtualOrder {
+    fn remaining(&self) -> u64 {
+        self.qty - self.filled
+    }
+
+    // whether a taker on the other side reaching `price` trades through this order
+    fn is_through(&self, price: u64) -> bool {
+        match self.side {
+            BookSide::Bid => price < self.price,
+            BookSide::Ask => price > self.price,
+        }
+    }
+
+    fn fill(&mut self, qty: u64, time: u64) {
+        self.filled += qty.min(self.remaining());
+        if self.remaining() == 0 {
+            self.ahead = 0;
+            self.filled_at.get_or_insert(time);
+        }
+    }
+}
+
+// what the TUI shows for one virtual order
+#[derive(Debug, Clone)]
+pub struct VirtualOrderEstimate {
+    pub id: u64,
+    pub side: BookSide,
+    pub price: Decimal,
+    pub qty: Decimal,
+    pub ahead: Decimal,
+    pub filled: Decimal,
+    // exchange time
+    pub placed_at: u64,
+    pub filled_at: Option<u64>,
+    // None while nothing at its level trades
+    pub eta_ms: Option<u64>,
+    // of filling within the horizon, taking the eta as the mean of an exponential wait
+    pub fill_probability: Option<f64>,
+}
+
+// Virtual orders for one symbol. Prints at an order's price take from the
+// queue ahead of it first, prints through its price fill it outright, and
+// cancels are assumed to come evenly from the whole queue.
+#[derive(Debug, Default)]
+pub struct QueueTracker {
+    orders: Vec<VirtualOrder>,
+    next_id: u64,
+}
+
+impl QueueTracker {
+    // joins the back of the queue at `price`, or at the touch when unset.
+    // returns the order id, None when the side is empty or the size rounds to zero
+    pub fn place(
+        &mut self,
+        side: BookSide,
+        price: Option<u64>,
+        qty: u64,
+        book: &OrderBook,
+        time: u64,
+    ) -> Option<u64> {
+        let (levels, touch) = match side {
+            BookSide::Bid => (book.bids(), book.best_bid()),
+            BookSide::Ask => (book.asks(), book.best_ask()),
+        };
+        let price = price.or(touch.map(|(price, _)| *price))?;
+        if qty == 0 {
+            return None;
+        }
+
+        self.next_id += 1;
+        let mut order = VirtualOrder {
+            id: self.next_id,
+            side,
+            price,
+            qty,
+            placed_at: time,
+            ahead: levels.get(&price).copied().unwrap_or(0),
+            filled: 0,
+            filled_at: None,
+        };
+        // a marketable price would have taken liquidity straight away
+        let opposite = match side {
+            BookSide::Bid => book.best_ask(),
+            BookSide::Ask => book.best_bid(),
+        };
+        if opposite.is_some_and(|(best, _)| order.is_through(*best) || *best == price) {
+            order.fill(qty, time);
+        }
+        self.orders.push(order);
+        Some(self.next_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.orders.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn add_trade(&mut self, trade: &Trade, scaler: &Scaler) {
+        let (Some(price), Some(mut qty)) = (
+            scaler.price_to_ticks(&trade.price.to_string()),
+            scaler.qty_to_ticks(&trade.quantity.to_string()),
+        ) else {
+            return;
+        };
+        // a taker sell trades against resting bids
+        let maker_side = if trade.is_buyer_maker {
+            BookSide::Bid
+        } else {
+            BookSide::Ask
+        };
+
+        // oldest first, so earlier virtual orders keep their priority
+        for order in self
+            .orders
+            .iter_mut()
+            .filter(|order| order.side == maker_side && order.filled_at.is_none())
+        {
+            if order.is_through(price) {
+                order.fill(order.remaining(), trade.trade_time);
+            } else if order.price == price {
+                let from_ahead = order.ahead.min(qty);
+                order.ahead -= from_ahead;
+                qty -= from_ahead;
+                let filled = order.remaining().min(qty);
+                order.fill(filled, trade.trade_time);
+            }
+        }
+    }
+
+    // takes cancels classified from an update out of the queues they hit. call
+    // before the update is applied to `book`
+    pub fn observe_level_events<'a>(
+        &mut self,
+        events: impl Iterator<Item = &'a LevelEvent>,
+        book: &OrderBook,
+    ) {
+        if self.orders.is_empty() {
+            return;
+        }
+        // a level's fill comes right before its cancel, and has already left the queues
+        let mut last_fill: Option<&LevelEvent> = None;
+        for event in events {
+            if event.change != LevelChange::Cancel {
+                last_fill = Some(event).filter(|event| event.change == LevelChange::Fill);
+                continue;
+            }
+            let levels = match event.side {
+                BookSide::Bid => book.bids(),
+                BookSide::Ask => book.asks(),
+            };
+            let filled = last_fill
+                .filter(|fill| fill.side == event.side && fill.price == event.price)
+                .map_or(0, |fill| fill.qty);
+            let resting = levels
+                .get(&event.price)
+                .copied()
+                .unwrap_or(0)
+                .saturating_sub(filled);
+            for order in self.orders.iter_mut().filter(|order| {
+                order.side == event.side && order.price == event.price && order.ahead > 0
+            }) {
+                // the share of the cancelled size that was in front of us
+                let cancelled_ahead = (event.qty as u128 * order.ahead as u128)
+                    .checked_div(resting as u128)
+                    .unwrap_or(0) as u64;
+                order.ahead -= cancelled_ahead.min(order.ahead);
+            }
+        }
+    }
+
+    // call after an update or snapshot has been applied
+    pub fn observe_book(&mut self, book: &OrderBook, time: u64) {
+        for order in self.orders.iter_mut().filter(|o| o.filled_at.is_none()) {
+            let (levels, opposite) = match order.side {
+                BookSide::Bid => (book.bids(), book.best_ask()),
+                BookSide::Ask => (book.asks(), book.best_bid()),
+            };
+            // no more can be ahead than is resting there
+            let resting = levels.get(&order.price).copied().unwrap_or(0);
+            order.ahead = order.ahead.min(resting);
+            // the other side moved through it, so it would have traded
+            if opposite.is_some_and(|(best, _)| order.is_through(*best) || *best == order.price) {
+                order.fill(order.remaining(), time);
+            }
+        }
+    }
+
+    // `activity` gives the recent fill and cancel flow the queue is projected from
+    pub fn estimates(
+        &self,
+        book: &OrderBook,
+        activity: &LevelActivity,
+        scaler: &Scaler,
+        horizon_secs: u64,
+    ) -> Vec<VirtualOrderEstimate> {
+        self.orders
+            .iter()
+            .map(|order| {
+                let eta_ms = match order.filled_at {
+                    Some(_) => Some(0),
+                    None => eta_ms(order, book, activity),
+                };
+                let fill_probability = eta_ms.map(|eta| match eta {
+                    0 => 1.0,
+                    eta => 1.0 - (-(horizon_secs as f64 * 1000.0) / eta as f64).exp(),
+                });
+                VirtualOrderEstimate {
+                    id: order.id,
+                    side: order.side,
+                    price: scaler.ticks_to_price(order.price),
+                    qty: scaler.ticks_to_qty(order.qty),
+                    ahead: scaler.ticks_to_qty(order.ahead),
+                    filled: scaler.ticks_to_qty(order.filled),
+                    placed_at: order.placed_at,
+                    filled_at: order.filled_at,
+                    eta_ms,
+                    fill_probability,
+                }
+            })
+            .collect()
+    }
+}
+
+// Time for the queue ahead to drain, through fills and its share of the
+// cancels, and then for the order itself to be filled, at the rates seen over
+// the level events window. Orders at the touch fall back to the side's fill
+// rate while nothing has printed at their own level.
+fn eta_ms(order: &VirtualOrder, book: &OrderBook, activity: &LevelActivity) -> Option<u64> {
+    let secs = activity.window_ms.max(1) as f64 / 1000.0;
+    let flow = activity.level(order.side, order.price);
+    let (levels, touch, side_total) = match order.side {
+        BookSide::Bid => (book.bids(), book.best_bid(), activity.bid_total),
+        BookSide::Ask => (book.asks(), book.best_ask(), activity.ask_total),
+    };
+    let at_touch = touch.is_some_and(|(best, _)| *best == order.price);
+    let filled = match flow.filled {
+        0 if at_touch => side_total.filled,
+        filled => filled,
+    };
+    if filled == 0 {
+        return None;
+    }
+    let fill_rate = filled as f64 / secs;
+
+    let resting = levels.get(&order.price).copied().unwrap_or(0);
+    let cancel_share = match resting {
+        0 => 0.0,
+        resting => order.ahead as f64 / resting as f64,
+    };
+    let drain_rate = fill_rate + flow.cancelled as f64 / secs * cancel_share;
+
+    let secs_to_fill = order.ahead as f64 / drain_rate + order.remaining() as f64 / fill_rate;
+    (secs_to_fill * 1000.0).round().to_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::{DepthSnapshot, DepthUpdate};
+    use crate::config::Config;
+    use crate::engine::levels::LevelTracker;
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3))
+    }
+
+    fn book() -> OrderBook {
+        OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![
+                    ["100.00".to_string(), "2.000".to_string()],
+                    ["99.99".to_string(), "1.000".to_string()],
+                ],
+                asks: vec![["100.01".to_string(), "2.000".to_string()]],
+            },
+            &scaler(),
+        )
+        .unwrap()
+    }
+
+    fn sell(price: &str, qty: &str, trade_time: u64) -> Trade {
+        Trade {
+            price: price.parse().unwrap(),
+            quantity: qty.parse().unwrap(),
+            trade_time,
+            is_buyer_maker: true,
+        }
+    }
+
+    fn bid_update(time: u64, price: &str, qty: &str) -> DepthUpdate {
+        DepthUpdate {
+            event_time: time,
+            first_update_id: 2,
+            final_update_id: 2,
+            prev_final_update_id: None,
+            b: vec![[price.to_string(), qty.to_string()]],
+            a: vec![],
+        }
+    }
+
+    #[test]
+    fn trades_and_cancels_move_the_order_up_the_queue() {
+        let (scaler, conf) = (scaler(), Config::default());
+        let mut book = book();
+        let mut levels = LevelTracker::default();
+        let mut queue = QueueTracker::default();
+        queue.place(BookSide::Bid, None, 500, &book, 0).unwrap();
+
+        // 0.5 trades at the best bid, then half of what's left is cancelled
+        let trade = sell("100.00", "0.5", 10);
+        levels.add_trade(&trade, &scaler);
+        queue.add_trade(&trade, &scaler);
+        let update = bid_update(20, "100.00", "0.750");
+        levels.observe_update(&book, &update, &scaler, &conf);
+        queue.observe_level_events(levels.update_events(), &book);
+        book.apply_update(&update, &scaler).unwrap();
+        queue.observe_book(&book, 20);
+
+        let estimate = &queue.estimates(&book, levels.activity(), &scaler, 60)[0];
+        // 1.5 was ahead after the trade, and 0.75 of it was cancelled in proportion
+        assert_eq!(estimate.ahead, Decimal::new(750, 3));
+        // 0.5 filled over the 10s window is 0.05/s, with 0.075/s of cancels in front
+        // of the order: 0.75 / 0.125 + 0.5 / 0.05 = 16s
+        assert_eq!(estimate.eta_ms, Some(16_000));
+
+        // a larger sell takes the rest of the queue and part of the order
+        queue.add_trade(&sell("100.00", "1.0", 30), &scaler);
+        let estimate = &queue.estimates(&book, levels.activity(), &scaler, 60)[0];
+        assert_eq!(estimate.ahead, Decimal::ZERO);
+        assert_eq!(estimate.filled, Decimal::new(250, 3));
+        assert_eq!(estimate.filled_at, None);
+    }
+
+    #[test]
+    fn prices_traded_through_fill_the_order() {
+        let (scaler, book) = (scaler(), book());
+        let mut queue = QueueTracker::default();
+        queue.place(BookSide::Bid, Some(9999), 500, &book, 0);
+        // an ask order can't fill from taker sells
+        queue.place(BookSide::Ask, None, 500, &book, 0);
+
+        queue.add_trade(&sell("99.98", "0.1", 40), &scaler);
+        let estimates = queue.estimates(&book, &LevelActivity::default(), &scaler, 60);
+        assert_eq!(estimates[0].filled_at, Some(40));
+        assert_eq!(estimates[0].fill_probability, Some(1.0));
+        assert_eq!(estimates[1].filled, Decimal::ZERO);
+        assert_eq!(estimates[1].eta_ms, None);
+    }
+}
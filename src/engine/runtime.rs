@@ -1,263 +1,255 @@
 use anyhow::Result;
 use futures_util::StreamExt;
-use num_traits::ToPrimitive;
-use std::collections::VecDeque;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{self, Duration};
-use tokio::sync::mpsc;
-
-use crate::binance::types::{
-    DepthSnapshot, MarketEvent, ReceivedDepthUpdate, ReceivedTrade, SignificanceReason,
-    SignificantTrade, Trade,
-};
-use crate::binance::{snapshot, stream};
-use crate::book::orderbook::OrderBook;
-use crate::book::scaler::Scaler;
-use crate::book::sync::{SyncOutcome, SyncState};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::binance::types::{DepthSnapshot, MarketEvent};
+use crate::book::orderbook::BookSide;
 use crate::config;
-use crate::engine::metrics::MarketMetrics;
-use crate::engine::state::{MarketSnapshot, MarketState};
+use crate::engine::events::{BookEvent, EVENT_CHANNEL_CAPACITY};
+use crate::engine::metrics::FeedStats;
+use crate::engine::state::MarketState;
+use crate::engine::symbol::{SymbolEngine, SymbolSetup};
+use crate::shm::ShmWriter;
+use crate::source::MarketDataSource;
 
 pub enum EngineCommand {
-    NewSnapshot(DepthSnapshot),
-    RequestSnapshot,
+    NewSnapshot(String, DepthSnapshot),
+    RequestSnapshot(String),
+    AuditSnapshot(String, DepthSnapshot),
+    // a reloaded config, already stripped of changes that need a restart
+    UpdateConfig(Arc<config::Config>),
+    // a hypothetical order of `qty` joining the queue at `price`, or at the touch when None
+    PlaceVirtualOrder {
+        symbol: String,
+        side: BookSide,
+        price: Option<Decimal>,
+        qty: Decimal,
+    },
+    ClearVirtualOrders(String),
     Shutdown,
 }
 
-pub struct MarketDataEngine {
-    state: Arc<MarketState>,
-    metrics: MarketMetrics,
-    recent_trades: VecDeque<Trade>,
-    significant_trades: VecDeque<SignificantTrade>,
-
-    conf: Arc<config::Config>,
+pub struct MarketDataEngine<S: MarketDataSource> {
+    source: Arc<S>,
 
-    sync_state: SyncState,
-    book: OrderBook,
-    scaler: Scaler,
-    symbol: String,
+    markets: Vec<SymbolEngine>,
+    // lowercase symbol (as it appears in stream names) -> index into markets
+    market_index: HashMap<String, usize>,
 
-    is_syncing: bool,
+    conf: Arc<config::Config>,
+    feed: Arc<FeedStats>,
+    events: broadcast::Sender<BookEvent>,
 
     command_tx: mpsc::Sender<EngineCommand>,
     command_rx: mpsc::Receiver<EngineCommand>,
-
-    update_counter: u64,
-    last_rate_calc_time: std::time::Instant,
-    updates_per_second: f64,
-    total_trades: u64,
 }
 
-impl MarketDataEngine {
+impl<S: MarketDataSource> MarketDataEngine<S> {
     pub fn new(
-        symbol: String,
-        initial_snapshot: DepthSnapshot,
-        scaler: Scaler,
+        source: Arc<S>,
+        setups: Vec<SymbolSetup>,
         conf: Arc<config::Config>,
-    ) -> Result<(Self, mpsc::Sender<EngineCommand>, Arc<MarketState>)> {
+    ) -> Result<(Self, mpsc::Sender<EngineCommand>, Vec<Arc<MarketState>>)> {
+        anyhow::ensure!(!setups.is_empty(), "Engine needs at least one symbol");
+
         let (command_tx, command_rx) = mpsc::channel(32);
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+
+        let mut markets = Vec::with_capacity(setups.len());
+        let mut market_index = HashMap::with_capacity(setups.len());
+
+        for setup in setups {
+            let key = setup.symbol.to_lowercase();
+            anyhow::ensure!(
+                !market_index.contains_key(&key),
+                "Symbol \"{}\" given more than once",
+                setup.symbol
+            );
+            market_index.insert(key, markets.len());
+            markets.push(SymbolEngine::new(
+                setup,
+                conf.clone(),
+                command_tx.clone(),
+                events.clone(),
+            )?);
+        }
 
-        let mut sync_state = SyncState::default();
-        sync_state.set_last_update_id(initial_snapshot.last_update_id);
-        let book = OrderBook::from_snapshot(initial_snapshot.clone(), &scaler)?;
-        let state = Arc::new(MarketState::new(
-            book.clone(),
-            symbol.clone(),
-            scaler.clone(),
-        ));
+        let states = markets.iter().map(SymbolEngine::state).collect();
 
         let engine = MarketDataEngine {
-            state: state.clone(),
-            metrics: MarketMetrics::new(conf.orderbook_imbalance_depth_levels),
-            recent_trades: VecDeque::with_capacity(conf.recent_trades_starting_capacity),
-            significant_trades: VecDeque::with_capacity(conf.significant_trades_display_count),
-
-            conf,
+            source,
 
-            sync_state,
-            book,
-            scaler,
-            symbol,
+            markets,
+            market_index,
 
-            is_syncing: true,
+            conf,
+            feed: Arc::new(FeedStats::default()),
+            events,
 
             command_tx: command_tx.clone(),
             command_rx,
-
-            update_counter: 0,
-            last_rate_calc_time: std::time::Instant::now(),
-            updates_per_second: 0.0,
-            total_trades: 0,
         };
 
-        Ok((engine, command_tx, state))
+        Ok((engine, command_tx, states))
     }
 
-    fn publish_snapshot(&self) {
-        let snapshot = MarketSnapshot {
-            book: self.book.clone(),
-            metrics: self.metrics.clone(),
-            recent_trades: self.recent_trades.clone(),
-            significant_trades: self.significant_trades.clone(),
-            is_syncing: self.is_syncing,
-        };
+    // connection and parse error counters of the combined stream, for the exporter
+    pub fn feed_stats(&self) -> Arc<FeedStats> {
+        self.feed.clone()
+    }
+
+    // publish every symbol's top of book into `writer`, one slot each in subscription order
+    pub fn with_shared_memory(mut self, writer: &ShmWriter) -> Self {
+        for (index, market) in self.markets.iter_mut().enumerate() {
+            market.set_shm_slot(writer, index);
+        }
+        self
+    }
+
+    // subscribe to get every applied delta, trade and book reset
+    pub fn book_events(&self) -> broadcast::Sender<BookEvent> {
+        self.events.clone()
+    }
+
+    fn symbols(&self) -> Vec<String> {
+        self.markets
+            .iter()
+            .map(|m| m.symbol().to_string())
+            .collect()
+    }
 
-        self.state.snapshot.store(Arc::new(snapshot));
+    fn market_mut(&mut self, symbol: &str) -> Option<&mut SymbolEngine> {
+        let idx = *self.market_index.get(&symbol.to_lowercase())?;
+        self.markets.get_mut(idx)
     }
 
-    fn spawn_snapshot_fetch(&self) {
-        let symbol = self.symbol.clone();
+    fn spawn_snapshot_fetch(&self, symbol: String) {
+        self.spawn_fetch(symbol, self.conf.orderbook_initial_snapshot_depth, false);
+    }
+
+    fn spawn_audit_fetch(&self, symbol: String) {
+        self.spawn_fetch(symbol, self.conf.audit_snapshot_depth, true);
+    }
+
+    fn spawn_fetch(&self, symbol: String, depth: u16, audit: bool) {
+        let source = self.source.clone();
         let tx = self.command_tx.clone();
-        let conf = self.conf.clone();
+        let into_command: fn(String, DepthSnapshot) -> EngineCommand = if audit {
+            EngineCommand::AuditSnapshot
+        } else {
+            EngineCommand::NewSnapshot
+        };
 
         tokio::spawn(async move {
-            match snapshot::fetch_snapshot(&symbol, conf.orderbook_initial_snapshot_depth).await {
+            let fetched = if audit {
+                source.fetch_audit_snapshot(&symbol, depth).await
+            } else {
+                source.fetch_snapshot(&symbol, depth).await
+            };
+            match fetched {
                 Ok(snapshot) => {
-                    if tx.send(EngineCommand::NewSnapshot(snapshot)).await.is_err() {
+                    if tx.send(into_command(symbol, snapshot)).await.is_err() {
                         tracing::error!("Failed to send snapshot to engine - channel closed")
                     };
                 }
                 Err(e) => {
-                    tracing::error!("Fatal error, failed to fetch snapshot: {}", e);
+                    tracing::error!(
+                        "Fatal error, failed to fetch snapshot for {}: {}",
+                        symbol,
+                        e
+                    );
                 }
             }
         });
     }
 
-    fn update_rate_counter(&mut self) {
-        self.update_counter += 1;
-        let now = time::Instant::now();
-        let elapsed_secs = now.duration_since(self.last_rate_calc_time).as_secs_f64();
-
-        if elapsed_secs >= 1.0 {
-            self.updates_per_second = self.update_counter as f64 / elapsed_secs;
-            self.last_rate_calc_time = now;
-            self.update_counter = 0;
+    // every book is stale after a reconnect, so resync all of them
+    fn resync_all(&mut self) {
+        for market in &mut self.markets {
+            market.reset_sync();
         }
-    }
-
-    fn detect_significant_trade(&mut self, trade: &Trade, event_time: u64) {
-        let trade_qty = trade.quantity.to_f64().unwrap_or(0.0);
-        let notional_value = trade.price * trade.quantity;
-
-        let reason = self
-            .recent_trades
-            .len()
-            .ge(&self.conf.min_trades_for_significance)
-            .then(|| {
-                let one_min_volume: f64 = self
-                    .recent_trades
-                    .iter()
-                    .map(|t| t.quantity.to_f64().unwrap_or(0.0))
-                    .sum();
-
-                (one_min_volume > 0.0)
-                    .then(|| trade_qty / one_min_volume)
-                    .filter(|&ratio| ratio >= self.conf.significant_trade_volume_pct)
-                    .map(|ratio| SignificanceReason::HighVolumePercent(ratio * 100.0))
-            })
-            .flatten();
-
-        if let Some(significance_reason) = reason {
-            self.significant_trades.push_back(SignificantTrade::new(
-                trade.clone(),
-                notional_value,
-                significance_reason,
-            ));
-
-            // Prune old significant trades
-            let cutoff =
-                event_time.saturating_sub(self.conf.significant_trades_retention_secs * 1000);
-            while let Some(oldest) = self.significant_trades.front() {
-                if oldest.trade.trade_time < cutoff {
-                    self.significant_trades.pop_front();
-                } else {
-                    break;
-                }
-            }
+        for symbol in self.symbols() {
+            self.spawn_snapshot_fetch(symbol);
         }
     }
 
-    fn handle_ws_trade(&mut self, received: ReceivedTrade) {
-        self.total_trades += 1;
-        self.update_rate_counter();
-
-        let event_time = received.trade.trade_time;
-        let received_at = received.received_at;
-        let cutoff_time = event_time.saturating_sub(60_000);
-
-        self.recent_trades.push_back(received.trade.clone());
-
-        while let Some(oldest) = self.recent_trades.front() {
-            if oldest.trade_time < cutoff_time {
-                self.recent_trades.pop_front();
-            } else {
-                break;
-            }
+    async fn handle_event(&mut self, event: MarketEvent) -> Result<()> {
+        match event {
+            MarketEvent::Trade(trade) => match self.market_mut(&trade.symbol) {
+                Some(market) => market.handle_trade(trade),
+                None => tracing::warn!("Trade for unknown symbol: {}", trade.symbol),
+            },
+            MarketEvent::Depth(update) => match self.market_mut(&update.symbol) {
+                Some(market) => market.handle_depth_update(update).await?,
+                None => tracing::warn!("Depth update for unknown symbol: {}", update.symbol),
+            },
+            MarketEvent::MarkPrice(mark) => match self.market_mut(&mark.symbol) {
+                Some(market) => market.handle_mark_price(mark),
+                None => tracing::warn!("Mark price for unknown symbol: {}", mark.symbol),
+            },
+            MarketEvent::Snapshot(received) => match self.market_mut(&received.symbol) {
+                Some(market) => market.apply_snapshot(received.snapshot)?,
+                None => tracing::warn!("Snapshot for unknown symbol: {}", received.symbol),
+            },
         }
-
-        self.detect_significant_trade(&received.trade, event_time);
-
-        //update metrics in place
-        self.metrics.compute_trade_metrics(
-            &self.recent_trades,
-            self.total_trades,
-            event_time,
-            received_at,
-        );
-
-        self.metrics
-            .update_performance_metrics(self.updates_per_second);
-
-        self.publish_snapshot();
-    }
-
-    async fn handle_ws_depth_update(&mut self, received: ReceivedDepthUpdate) -> Result<()> {
-        self.update_rate_counter();
-        let event_time = received.update.event_time;
-        let received_at = received.received_at;
-
-        match self.sync_state.process_delta(received.update) {
-            SyncOutcome::Updates(updates) => {
-                for update in updates {
-                    self.book.apply_update(&update, &self.scaler)?;
-                }
-                self.is_syncing = false;
-            }
-            SyncOutcome::GapBetweenUpdates => {
-                self.command_tx.send(EngineCommand::RequestSnapshot).await?;
-                self.sync_state = SyncState::default();
-                self.is_syncing = true;
-            }
-            SyncOutcome::NoUpdates => {}
-        }
-
-        self.metrics
-            .compute_book_metrics(&self.book, &self.scaler, event_time, received_at);
-
-        self.publish_snapshot();
-
         Ok(())
     }
 
     async fn handle_command(&mut self, cmd: EngineCommand) -> Result<bool> {
         match cmd {
-            EngineCommand::NewSnapshot(snapshot) => {
-                tracing::info!(
-                    "Received new snapshot, lastUpdateId: {}",
-                    snapshot.last_update_id
+            EngineCommand::NewSnapshot(symbol, snapshot) => {
+                match self.market_mut(&symbol) {
+                    Some(market) => market.apply_snapshot(snapshot)?,
+                    None => tracing::warn!("Snapshot for unknown symbol: {}", symbol),
+                }
+                Ok(false)
+            }
+            EngineCommand::RequestSnapshot(symbol) if !self.source.is_live() => {
+                tracing::warn!(
+                    "[{}] Gap detected, waiting for recorded snapshot...",
+                    symbol
                 );
-
-                self.sync_state.set_last_update_id(snapshot.last_update_id);
-                self.book = OrderBook::from_snapshot(snapshot, &self.scaler)?;
-                self.publish_snapshot();
-
-                self.is_syncing = false;
                 Ok(false)
             }
-            EngineCommand::RequestSnapshot => {
-                tracing::warn!("Gap detected, requesting new snapshot...");
-                self.spawn_snapshot_fetch();
+            EngineCommand::RequestSnapshot(symbol) => {
+                tracing::warn!("[{}] Gap detected, requesting new snapshot...", symbol);
+                self.spawn_snapshot_fetch(symbol);
+                Ok(false)
+            }
+            EngineCommand::AuditSnapshot(symbol, snapshot) => {
+                match self.market_mut(&symbol) {
+                    Some(market) => market.handle_audit_snapshot(snapshot).await?,
+                    None => tracing::warn!("Audit snapshot for unknown symbol: {}", symbol),
+                }
+                Ok(false)
+            }
+            EngineCommand::UpdateConfig(conf) => {
+                for market in &mut self.markets {
+                    market.update_config(conf.clone());
+                }
+                self.conf = conf;
+                Ok(false)
+            }
+            EngineCommand::PlaceVirtualOrder {
+                symbol,
+                side,
+                price,
+                qty,
+            } => {
+                match self.market_mut(&symbol) {
+                    Some(market) => market.place_virtual_order(side, price, qty),
+                    None => tracing::warn!("Virtual order for unknown symbol: {}", symbol),
+                }
+                Ok(false)
+            }
+            EngineCommand::ClearVirtualOrders(symbol) => {
+                if let Some(market) = self.market_mut(&symbol) {
+                    market.clear_virtual_orders();
+                }
                 Ok(false)
             }
             EngineCommand::Shutdown => {
@@ -280,8 +272,12 @@ impl MarketDataEngine {
         let mut attempt = 0;
 
         loop {
+            if attempt > 0 || self.feed.connections() > 0 {
+                self.feed.record_reconnect_attempt();
+            }
             match connect_fn().await {
                 Ok(stream) => {
+                    self.feed.record_connection();
                     if attempt > 0 {
                         tracing::info!("{} reconnected after {} attempts", stream_name, attempt);
                     }
@@ -315,70 +311,93 @@ impl MarketDataEngine {
     }
 
     pub async fn run(mut self) -> Result<()> {
-        let symbol = self.symbol.clone();
+        let symbols = self.symbols();
+        let live = self.source.is_live();
 
-        tracing::info!("Engine running for symbol: {}", self.symbol);
+        tracing::info!("Engine running for symbols: {}", symbols.join(", "));
 
-        let mut market_stream = Box::pin(
-            self.connect_with_retry(|| stream::connect_market_stream(&symbol), "Market stream")
-                .await?,
-        );
+        let mut market_stream = self
+            .connect_with_retry(|| self.source.connect(&symbols), "Market stream")
+            .await?;
 
-        let stream_timeout = Duration::from_millis(self.conf.message_timeout_ms);
         let mut last_message_time = tokio::time::Instant::now();
+        // only recorded sources ever finish, live ones are reconnected
+        let mut finished = false;
+
+        let mut audit_timer = new_audit_timer(self.conf.audit_interval_secs);
 
         loop {
+            // both can change when the config is reloaded
+            let stream_timeout = Duration::from_millis(self.conf.message_timeout_ms);
+            // recorded sources have no REST endpoint to audit against
+            let auditing = live && self.conf.audit_interval_secs > 0;
+
             tokio::select! {
                 Some(cmd) = self.command_rx.recv() => {
                     let should_shutdown = self.handle_command(cmd).await?;
                     if should_shutdown {
                         break;
                     }
+                    let audit_period = Duration::from_secs(self.conf.audit_interval_secs.max(1));
+                    if audit_timer.period() != audit_period {
+                        audit_timer = new_audit_timer(self.conf.audit_interval_secs);
+                    }
                 }
 
-                Some(result) = market_stream.next() => {
+                result = market_stream.next(), if !finished => {
                     last_message_time = tokio::time::Instant::now();
 
                     match result {
-                        Ok(event) => {
-                            match event {
-                                MarketEvent::Trade(trade) => self.handle_ws_trade(trade),
-                                MarketEvent::Depth(update) => self.handle_ws_depth_update(update).await?,
-                            }
-                        }
-                        Err(e) => {
+                        Some(Ok(event)) => self.handle_event(event).await?,
+                        Some(Err(e)) if live => {
+                            self.feed.record_parse_error();
                             tracing::error!("Market websocket stream error: {}", e);
-                            self.is_syncing = true;
-                            self.publish_snapshot();
 
-                            // reset sync state - we need a fresh snapshot after reconnect
-                            self.sync_state = SyncState::default();
-                            self.spawn_snapshot_fetch();
+                            // reset sync state - we need fresh snapshots after reconnect
+                            self.resync_all();
 
-                            market_stream = Box::pin(self.connect_with_retry(
-                                || stream::connect_market_stream(&symbol),
-                                "Market stream",
-                            ).await?);
+                            market_stream = self
+                                .connect_with_retry(|| self.source.connect(&symbols), "Market stream")
+                                .await?;
+                        }
+                        Some(Err(e)) => {
+                            self.feed.record_parse_error();
+                            tracing::warn!("Skipping unreadable market event: {}", e);
+                        }
+                        None if live => {
+                            tracing::warn!("Market stream closed, attempting reconnect...");
+
+                            self.resync_all();
+
+                            market_stream = self
+                                .connect_with_retry(|| self.source.connect(&symbols), "Market stream")
+                                .await?;
+                        }
+                        None => {
+                            tracing::info!("Market stream finished");
+                            finished = true;
                         }
                     }
                 }
 
-                _ = tokio::time::sleep_until(last_message_time + stream_timeout) => {
+                _ = tokio::time::sleep_until(last_message_time + stream_timeout), if live => {
                     tracing::warn!("No message received for {:?}, attempting reconnect...", stream_timeout);
-                    self.is_syncing = true;
-                    self.publish_snapshot();
 
-                    self.sync_state = SyncState::default();
-                    self.spawn_snapshot_fetch();
+                    self.resync_all();
 
-                    market_stream = Box::pin(self.connect_with_retry(
-                        || stream::connect_market_stream(&symbol),
-                        "Market stream",
-                    ).await?);
+                    market_stream = self
+                        .connect_with_retry(|| self.source.connect(&symbols), "Market stream")
+                        .await?;
 
                     last_message_time = tokio::time::Instant::now();
                 }
 
+                _ = audit_timer.tick(), if auditing => {
+                    for symbol in self.symbols() {
+                        self.spawn_audit_fetch(symbol);
+                    }
+                }
+
                 else => break
             }
         }
@@ -386,3 +405,9 @@ impl MarketDataEngine {
         Ok(())
     }
 }
+
+// first tick one period from now rather than immediately
+fn new_audit_timer(interval_secs: u64) -> tokio::time::Interval {
+    let period = Duration::from_secs(interval_secs.max(1));
+    tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+}
@@ -0,0 +1,310 @@
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::binance::types::{Side, SignificanceReason, Trade};
+use crate::book::orderbook::OrderBook;
+use crate::book::scaler::Scaler;
+use crate::config::Config;
+use crate::engine::cluster::TakerOrder;
+
+// Running totals over the trades in the 1 minute window, updated as trades
+// enter and leave it instead of re-summing the window on every trade
+#[derive(Debug, Clone, Default)]
+pub struct TradeSizeStats {
+    count: usize,
+    volume: Decimal,
+    // f64 sums for the mean and variance of trade size
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl TradeSizeStats {
+    pub fn add(&mut self, trade: &Trade) {
+        let qty = trade.quantity.to_f64().unwrap_or(0.0);
+        self.count += 1;
+        self.volume += trade.quantity;
+        self.sum += qty;
+        self.sum_sq += qty * qty;
+    }
+
+    pub fn remove(&mut self, trade: &Trade) {
+        let qty = trade.quantity.to_f64().unwrap_or(0.0);
+        self.count -= 1;
+        self.volume -= trade.quantity;
+        if self.count == 0 {
+            // drop the rounding error the running sums picked up
+            self.sum = 0.0;
+            self.sum_sq = 0.0;
+        } else {
+            self.sum -= qty;
+            self.sum_sq -= qty * qty;
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn volume(&self) -> Decimal {
+        self.volume
+    }
+
+    fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    fn std_dev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let variance = (self.sum_sq / self.count as f64 - mean * mean).max(0.0);
+        Some(variance.sqrt())
+    }
+}
+
+// rules enabled by `conf`, the 1m volume share rule is always on
+pub fn enabled_rules(conf: &Config) -> usize {
+    1 + conf.significant_notional.is_some() as usize
+        + conf.significant_size_zscore.is_some() as usize
+        + conf.significant_sweep_levels.is_some() as usize
+        + conf.significant_mid_distance_bps.is_some() as usize
+}
+
+// Every enabled rule `order` matches, empty when fewer than
+// `significance_min_rules` match. `stats` covers the window before `order`,
+// and `book` is the book as its last print arrived.
+pub fn evaluate(
+    conf: &Config,
+    order: &TakerOrder,
+    stats: &TradeSizeStats,
+    book: &OrderBook,
+    scaler: &Scaler,
+) -> Vec<SignificanceReason> {
+    let trade = &order.trade;
+    let qty = trade.quantity.to_f64().unwrap_or(0.0);
+    // the window rules only mean something once the window has some trades in it
+    let enough_trades = stats.count() + 1 >= conf.min_trades_for_significance;
+    let mut reasons = Vec::new();
+
+    let volume = (stats.volume() + trade.quantity).to_f64().unwrap_or(0.0);
+    if enough_trades && volume > 0.0 && qty / volume >= conf.significant_trade_volume_pct {
+        reasons.push(SignificanceReason::HighVolumePercent(qty / volume * 100.0));
+    }
+
+    if let Some(threshold) = conf.significant_notional
+        && order.notional.to_f64().unwrap_or(0.0) >= threshold
+    {
+        reasons.push(SignificanceReason::LargeNotional);
+    }
+
+    if let Some(threshold) = conf.significant_size_zscore
+        && enough_trades
+        && let (Some(mean), Some(std_dev)) = (stats.mean(), stats.std_dev())
+        && std_dev > 0.0
+    {
+        let zscore = (qty - mean) / std_dev;
+        if zscore >= threshold {
+            reasons.push(SignificanceReason::SizeZScore(zscore));
+        }
+    }
+
+    if let Some(threshold) = conf.significant_sweep_levels {
+        // prints land on distinct levels, and the book may not have caught up yet
+        let levels = order
+            .levels
+            .max(levels_swept(order, book, scaler, threshold + 1));
+        if levels > threshold {
+            reasons.push(SignificanceReason::LevelSweep(levels));
+        }
+    }
+
+    if let Some(threshold) = conf.significant_mid_distance_bps
+        && let Some(mid) = book.mid_price().map(|mid| scaler.ticks_to_price(mid))
+        && !mid.is_zero()
+    {
+        let bps = ((order.worst_price - mid).abs() / mid * Decimal::from(10_000))
+            .to_f64()
+            .unwrap_or(0.0);
+        if bps >= threshold {
+            reasons.push(SignificanceReason::FarFromMid(bps));
+        }
+    }
+
+    if reasons.len() < conf.significance_min_rules {
+        reasons.clear();
+    }
+    reasons
+}
+
+// resting levels on the side the taker hit, from the touch to its worst print,
+// counted up to `limit`
+fn levels_swept(order: &TakerOrder, book: &OrderBook, scaler: &Scaler, limit: usize) -> usize {
+    let Some(price) = scaler.price_to_ticks(&order.worst_price.to_string()) else {
+        return 0;
+    };
+    match order.trade.side() {
+        Side::Buy => book.asks().range(..=price).take(limit).count(),
+        Side::Sell => book.bids().range(price..).rev().take(limit).count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3))
+    }
+
+    fn book() -> OrderBook {
+        let level = |price: &str| [price.to_string(), "1.000".to_string()];
+        OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![level("99.99"), level("99.98"), level("99.97")],
+                asks: vec![level("100.01"), level("100.02"), level("100.03")],
+            },
+            &scaler(),
+        )
+        .unwrap()
+    }
+
+    fn trade(price: &str, qty: &str, is_buyer_maker: bool) -> Trade {
+        Trade {
+            price: price.parse().unwrap(),
+            quantity: qty.parse().unwrap(),
+            trade_time: 0,
+            is_buyer_maker,
+        }
+    }
+
+    fn order(price: &str, qty: &str, is_buyer_maker: bool) -> TakerOrder {
+        TakerOrder::new(&trade(price, qty, is_buyer_maker))
+    }
+
+    fn stats(qtys: &[&str]) -> TradeSizeStats {
+        let mut stats = TradeSizeStats::default();
+        for qty in qtys {
+            stats.add(&trade("100.00", qty, false));
+        }
+        stats
+    }
+
+    fn conf() -> Config {
+        Config {
+            min_trades_for_significance: 3,
+            significant_trade_volume_pct: 0.5,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn stats_follow_trades_in_and_out_of_the_window() {
+        let mut stats = stats(&["1", "3"]);
+        assert_eq!(stats.volume(), Decimal::from(4));
+        assert_eq!(stats.mean(), Some(2.0));
+        assert_eq!(stats.std_dev(), Some(1.0));
+
+        stats.remove(&trade("100.00", "1", false));
+        assert_eq!(stats.count(), 1);
+        assert_eq!(stats.std_dev(), Some(0.0));
+    }
+
+    #[test]
+    fn each_rule_adds_its_own_reason() {
+        let conf = Config {
+            significant_notional: Some(1_000.0),
+            significant_size_zscore: Some(3.0),
+            significant_sweep_levels: Some(2),
+            significant_mid_distance_bps: Some(2.0),
+            ..conf()
+        };
+        let window = stats(&["1", "1", "1", "1.5", "0.5"]);
+
+        // 20 lots bought through all three ask levels, 3bp above the mid
+        let reasons = evaluate(
+            &conf,
+            &order("100.03", "20", false),
+            &window,
+            &book(),
+            &scaler(),
+        );
+        let labels: Vec<String> = reasons.iter().map(|r| r.display()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Vol: 80.0%",
+                "Notional",
+                "Z: 60.1",
+                "Sweep: 3",
+                "Mid: 3.0bp"
+            ]
+        );
+
+        // a small sell at the touch matches nothing
+        let quiet = evaluate(
+            &conf,
+            &order("99.99", "1", true),
+            &window,
+            &book(),
+            &scaler(),
+        );
+        assert!(quiet.is_empty());
+    }
+
+    #[test]
+    fn needs_the_configured_number_of_matching_rules() {
+        let conf = Config {
+            significant_notional: Some(100.0),
+            significant_sweep_levels: Some(1),
+            significance_min_rules: 2,
+            ..conf()
+        };
+        // too few trades for the volume rule, and the notional alone isn't enough
+        let lone = evaluate(
+            &conf,
+            &order("100.01", "5", false),
+            &stats(&[]),
+            &book(),
+            &scaler(),
+        );
+        assert!(lone.is_empty());
+
+        let swept = evaluate(
+            &conf,
+            &order("100.02", "5", false),
+            &stats(&[]),
+            &book(),
+            &scaler(),
+        );
+        assert_eq!(swept.len(), 2);
+        assert_eq!(enabled_rules(&conf), 3);
+    }
+
+    #[test]
+    fn sweeps_count_the_levels_an_order_printed_at() {
+        let conf = Config {
+            significant_sweep_levels: Some(2),
+            ..conf()
+        };
+        // the book already moved past the first two levels the order took out
+        let mut swept = order("100.01", "1", false);
+        for price in ["100.02", "100.03"] {
+            let mut print = trade(price, "1", false);
+            print.trade_time = swept.trade.trade_time;
+            swept.merge(&print);
+        }
+        let moved = OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 2,
+                bids: vec![["99.99".to_string(), "1.000".to_string()]],
+                asks: vec![["100.03".to_string(), "0.500".to_string()]],
+            },
+            &scaler(),
+        )
+        .unwrap();
+
+        let reasons = evaluate(&conf, &swept, &stats(&[]), &moved, &scaler());
+        assert_eq!(reasons.len(), 1);
+        assert_eq!(reasons[0].display(), "Sweep: 3");
+    }
+}
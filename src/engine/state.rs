@@ -1,8 +1,11 @@
 use crate::binance::types::SignificantTrade;
 use crate::{
     binance::types::Trade,
-    book::{orderbook::OrderBook, scaler::Scaler},
-    engine::metrics::MarketMetrics,
+    book::{health::BookHealth, orderbook::OrderBook, scaler::Scaler},
+    engine::{
+        candles::CandleSeries, levels::LevelActivity, metrics::MarketMetrics,
+        queue::VirtualOrderEstimate,
+    },
 };
 use arc_swap::ArcSwap;
 use rust_decimal::Decimal;
@@ -15,7 +18,15 @@ pub struct MarketSnapshot {
     pub metrics: MarketMetrics,
     pub recent_trades: VecDeque<Trade>,
     pub significant_trades: VecDeque<SignificantTrade>,
+    pub candles: CandleSeries,
+    // add, cancel and fill flow per level over the level events window
+    pub level_activity: Arc<LevelActivity>,
+    // hypothetical resting orders placed from the TUI, oldest first
+    pub virtual_orders: Vec<VirtualOrderEstimate>,
     pub is_syncing: bool,
+    pub health: BookHealth,
+    // the book includes every delta up to this id, None while waiting for a snapshot
+    pub last_update_id: Option<u64>,
 }
 
 pub type DisplayDepthLevel = (Decimal, Decimal);
@@ -57,7 +68,12 @@ impl MarketState {
             metrics: MarketMetrics::default(),
             recent_trades: VecDeque::new(),
             significant_trades: VecDeque::new(),
+            candles: CandleSeries::default(),
+            level_activity: Arc::default(),
+            virtual_orders: Vec::new(),
             is_syncing: true,
+            health: BookHealth::default(),
+            last_update_id: None,
         };
 
         MarketState {
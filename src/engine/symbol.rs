@@ -0,0 +1,630 @@
+use anyhow::Result;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::binance::types::{
+    DepthSnapshot, ReceivedDepthUpdate, ReceivedMarkPrice, ReceivedTrade, SignificantTrade, Trade,
+};
+use crate::book::audit::{AuditOutcome, AuditReport, BookAuditor};
+use crate::book::health::BookHealth;
+use crate::book::orderbook::{BookSide, OrderBook};
+use crate::book::scaler::Scaler;
+use crate::book::sync::{SyncOutcome, SyncState};
+use crate::config;
+use crate::engine::candles::CandleSeries;
+use crate::engine::cluster::TakerOrder;
+use crate::engine::events::BookEvent;
+use crate::engine::flow::OrderFlow;
+use crate::engine::levels::LevelTracker;
+use crate::engine::metrics::MarketMetrics;
+use crate::engine::queue::QueueTracker;
+use crate::engine::runtime::EngineCommand;
+use crate::engine::significance::{self, TradeSizeStats};
+use crate::engine::state::{MarketSnapshot, MarketState};
+use crate::shm::{ShmSlot, ShmWriter};
+use crate::source::MarketDataSource;
+
+// cap on mismatched levels written to the log per failed audit
+const AUDIT_LOGGED_MISMATCHES: usize = 10;
+
+// Everything needed to bootstrap one symbol inside the engine
+pub struct SymbolSetup {
+    pub symbol: String,
+    pub snapshot: DepthSnapshot,
+    pub scaler: Scaler,
+}
+
+impl SymbolSetup {
+    pub async fn fetch<S: MarketDataSource>(
+        source: &S,
+        symbol: &str,
+        snapshot_depth: u16,
+    ) -> Result<Self> {
+        let snapshot = source.fetch_snapshot(symbol, snapshot_depth).await?;
+        tracing::info!(
+            "[DEPTH SNAPSHOT_INFO] {} lastUpdateId: {}",
+            symbol,
+            snapshot.last_update_id
+        );
+
+        let instrument = source.fetch_instrument(symbol).await?;
+
+        Ok(Self {
+            symbol: symbol.to_string(),
+            snapshot,
+            scaler: Scaler::new(instrument.tick_size, instrument.step_size),
+        })
+    }
+}
+
+// Per-symbol book, sync and metrics state. The engine owns one of these per
+// subscribed symbol and routes stream events to it.
+pub struct SymbolEngine {
+    state: Arc<MarketState>,
+    metrics: MarketMetrics,
+    // logical taker orders, the last one still open to further prints
+    recent_trades: VecDeque<Trade>,
+    // raw prints of the same window, what the 1 minute trade metrics count
+    recent_prints: VecDeque<Trade>,
+    recent_trade_stats: TradeSizeStats,
+    open_order: Option<TakerOrder>,
+    significant_trades: VecDeque<SignificantTrade>,
+    // seq of the newest significant trade
+    significant_seq: u64,
+    candles: CandleSeries,
+    flow: OrderFlow,
+    levels: LevelTracker,
+    queue: QueueTracker,
+
+    conf: Arc<config::Config>,
+
+    sync_state: SyncState,
+    book: OrderBook,
+    scaler: Scaler,
+    symbol: String,
+    auditor: BookAuditor,
+
+    is_syncing: bool,
+    health: BookHealth,
+    // exchange event time the book was first seen crossed
+    crossed_since_ms: Option<u64>,
+    // exchange time of the latest event, virtual orders are stamped with it
+    last_event_time: u64,
+
+    command_tx: mpsc::Sender<EngineCommand>,
+    events: broadcast::Sender<BookEvent>,
+    shm: Option<ShmSlot>,
+
+    update_counter: u64,
+    last_rate_calc_time: std::time::Instant,
+    updates_per_second: f64,
+    total_trades: u64,
+}
+
+impl SymbolEngine {
+    pub fn new(
+        setup: SymbolSetup,
+        conf: Arc<config::Config>,
+        command_tx: mpsc::Sender<EngineCommand>,
+        events: broadcast::Sender<BookEvent>,
+    ) -> Result<Self> {
+        let SymbolSetup {
+            symbol,
+            snapshot,
+            scaler,
+        } = setup;
+
+        let mut sync_state = SyncState::default();
+        sync_state.set_last_update_id(snapshot.last_update_id);
+        let book = OrderBook::from_snapshot(snapshot, &scaler)?;
+        let state = Arc::new(MarketState::new(
+            book.clone(),
+            symbol.clone(),
+            scaler.clone(),
+        ));
+
+        Ok(SymbolEngine {
+            state,
+            metrics: MarketMetrics::new(conf.orderbook_imbalance_depth_levels),
+            recent_trades: VecDeque::with_capacity(conf.recent_trades_starting_capacity),
+            recent_prints: VecDeque::with_capacity(conf.recent_trades_starting_capacity),
+            recent_trade_stats: TradeSizeStats::default(),
+            open_order: None,
+            significant_trades: VecDeque::with_capacity(conf.significant_trades_display_count),
+            significant_seq: 0,
+            candles: CandleSeries::new(conf.candle_history_len),
+            flow: OrderFlow::new(&book),
+            levels: LevelTracker::default(),
+            queue: QueueTracker::default(),
+
+            conf,
+
+            sync_state,
+            book,
+            scaler,
+            symbol,
+            auditor: BookAuditor::default(),
+
+            is_syncing: true,
+            health: BookHealth::default(),
+            crossed_since_ms: None,
+            last_event_time: 0,
+
+            command_tx,
+            events,
+            shm: None,
+
+            update_counter: 0,
+            last_rate_calc_time: std::time::Instant::now(),
+            updates_per_second: 0.0,
+            total_trades: 0,
+        })
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn state(&self) -> Arc<MarketState> {
+        self.state.clone()
+    }
+
+    pub fn set_shm_slot(&mut self, writer: &ShmWriter, index: usize) {
+        let slot = writer.slot(index, &self.symbol, &self.scaler);
+        slot.publish(
+            &self.book,
+            self.sync_state.last_update_id(),
+            self.is_syncing,
+            self.health,
+        );
+        self.shm = Some(slot);
+    }
+
+    // everything else reads `conf` as it goes, so only sizes held elsewhere need passing on
+    pub fn update_config(&mut self, conf: Arc<config::Config>) {
+        self.metrics
+            .set_imbalance_depth_levels(conf.orderbook_imbalance_depth_levels);
+        self.candles.set_history_len(conf.candle_history_len);
+        self.conf = conf;
+    }
+
+    pub fn publish_snapshot(&self) {
+        let snapshot = MarketSnapshot {
+            book: self.book.clone(),
+            metrics: self.metrics.clone(),
+            recent_trades: self.recent_trades.clone(),
+            significant_trades: self.significant_trades.clone(),
+            candles: self.candles.clone(),
+            level_activity: self.levels.activity().clone(),
+            // one entry per open virtual order, nothing to compute without any
+            virtual_orders: if self.queue.is_empty() {
+                Vec::new()
+            } else {
+                self.queue.estimates(
+                    &self.book,
+                    self.levels.activity(),
+                    &self.scaler,
+                    self.conf.virtual_order_horizon_secs,
+                )
+            },
+            is_syncing: self.is_syncing,
+            health: self.health,
+            last_update_id: self.sync_state.last_update_id(),
+        };
+
+        self.state.snapshot.store(Arc::new(snapshot));
+
+        if let Some(slot) = &self.shm {
+            slot.publish(
+                &self.book,
+                self.sync_state.last_update_id(),
+                self.is_syncing,
+                self.health,
+            );
+        }
+    }
+
+    // skips building the event when nobody is subscribed
+    fn emit(&self, event: impl FnOnce() -> BookEvent) {
+        if self.events.receiver_count() > 0 {
+            let _ = self.events.send(event());
+        }
+    }
+
+    // drop sync progress, a fresh snapshot is needed before deltas can be applied again
+    pub fn reset_sync(&mut self) {
+        self.metrics.resyncs += 1;
+        self.sync_state = SyncState::default();
+        self.auditor.reset();
+        self.crossed_since_ms = None;
+        self.is_syncing = true;
+        self.publish_snapshot();
+    }
+
+    // ask the engine for a fresh snapshot and stop applying deltas until it arrives
+    async fn request_resync(&mut self) -> Result<()> {
+        self.command_tx
+            .send(EngineCommand::RequestSnapshot(self.symbol.clone()))
+            .await?;
+        self.reset_sync();
+        Ok(())
+    }
+
+    // returns true once the book has been crossed for longer than the grace period
+    fn update_health(&mut self, event_time: u64) -> bool {
+        let health = BookHealth::of(&self.book);
+        if health != self.health {
+            match health {
+                BookHealth::Crossed => self.metrics.crossed_book_events += 1,
+                BookHealth::Locked => self.metrics.locked_book_events += 1,
+                BookHealth::Normal => {}
+            }
+            tracing::warn!("[{}] Book health: {:?}", self.symbol, health);
+        }
+        self.health = health;
+
+        if health != BookHealth::Crossed {
+            self.crossed_since_ms = None;
+            return false;
+        }
+        let since = *self.crossed_since_ms.get_or_insert(event_time);
+        event_time.saturating_sub(since) >= self.conf.crossed_book_grace_ms
+    }
+
+    fn update_rate_counter(&mut self) {
+        self.update_counter += 1;
+        let now = time::Instant::now();
+        let elapsed_secs = now.duration_since(self.last_rate_calc_time).as_secs_f64();
+
+        if elapsed_secs >= 1.0 {
+            self.updates_per_second = self.update_counter as f64 / elapsed_secs;
+            self.last_rate_calc_time = now;
+            self.update_counter = 0;
+        }
+    }
+
+    // call before `order` joins the 1 minute window
+    fn detect_significant_trade(&mut self, order: &TakerOrder, event_time: u64) {
+        // an order that grew is judged again as a whole, and keeps its seq if flagged again
+        let regrown = if self
+            .significant_trades
+            .back()
+            .is_some_and(|flagged| order.continues(&flagged.trade))
+        {
+            self.significant_trades
+                .pop_back()
+                .map(|flagged| flagged.seq)
+        } else {
+            None
+        };
+
+        let reasons = significance::evaluate(
+            &self.conf,
+            order,
+            &self.recent_trade_stats,
+            &self.book,
+            &self.scaler,
+        );
+
+        if !reasons.is_empty() {
+            let seq = regrown.unwrap_or_else(|| {
+                self.significant_seq += 1;
+                self.significant_seq
+            });
+            self.significant_trades.push_back(SignificantTrade::new(
+                order.trade.clone(),
+                order.notional,
+                reasons,
+                seq,
+            ));
+
+            // Prune old significant trades
+            let cutoff =
+                event_time.saturating_sub(self.conf.significant_trades_retention_secs * 1000);
+            while let Some(oldest) = self.significant_trades.front() {
+                if oldest.trade.trade_time < cutoff {
+                    self.significant_trades.pop_front();
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn handle_trade(&mut self, received: ReceivedTrade) {
+        self.metrics.messages_processed += 1;
+        self.total_trades += 1;
+        self.update_rate_counter();
+
+        let event_time = received.trade.trade_time;
+        let received_at = received.received_at;
+        let cutoff_time = event_time.saturating_sub(60_000);
+
+        while let Some(oldest) = self.recent_trades.front() {
+            if oldest.trade_time < cutoff_time {
+                self.recent_trade_stats.remove(oldest);
+                self.recent_trades.pop_front();
+            } else {
+                break;
+            }
+        }
+        while self
+            .recent_prints
+            .front()
+            .is_some_and(|oldest| oldest.trade_time < cutoff_time)
+        {
+            self.recent_prints.pop_front();
+        }
+        self.recent_prints.push_back(received.trade.clone());
+
+        let order = match self.open_order.take() {
+            Some(mut order) if order.continues(&received.trade) => {
+                // the open order is always the newest entry in the window
+                if let Some(previous) = self.recent_trades.pop_back() {
+                    self.recent_trade_stats.remove(&previous);
+                }
+                order.merge(&received.trade);
+                order
+            }
+            _ => TakerOrder::new(&received.trade),
+        };
+        self.detect_significant_trade(&order, event_time);
+        self.recent_trade_stats.add(&order.trade);
+        self.recent_trades.push_back(order.trade.clone());
+        self.open_order = Some(order);
+        self.candles.add_trade(&received.trade);
+        self.flow.add_trade(&received.trade);
+        self.levels.add_trade(&received.trade, &self.scaler);
+        self.queue.add_trade(&received.trade, &self.scaler);
+        self.last_event_time = event_time;
+        self.metrics.update_order_flow(&self.flow, &self.scaler);
+        self.emit(|| BookEvent::Trade {
+            symbol: self.symbol.clone(),
+            trade: received.trade.clone(),
+        });
+
+        //update metrics in place
+        self.metrics.compute_trade_metrics(
+            &self.recent_prints,
+            self.total_trades,
+            event_time,
+            received_at,
+        );
+
+        self.metrics
+            .update_performance_metrics(self.updates_per_second);
+
+        self.publish_snapshot();
+    }
+
+    pub fn place_virtual_order(&mut self, side: BookSide, price: Option<Decimal>, qty: Decimal) {
+        // a price or size the user didn't ask for is worse than no order
+        let price = match price {
+            Some(price) => match self.scaler.exact_price_ticks(price) {
+                Some(ticks) if ticks > 0 => Some(ticks),
+                _ => {
+                    tracing::warn!(
+                        "[{}] Invalid virtual order price {}, must be a positive multiple of {}",
+                        self.symbol,
+                        price,
+                        self.scaler.tick_size()
+                    );
+                    return;
+                }
+            },
+            None => None,
+        };
+        let qty_ticks = match self.scaler.exact_qty_ticks(qty) {
+            Some(ticks) if ticks > 0 => ticks,
+            _ => {
+                tracing::warn!(
+                    "[{}] Invalid virtual order quantity {}, must be a positive multiple of {}",
+                    self.symbol,
+                    qty,
+                    self.scaler.step_size()
+                );
+                return;
+            }
+        };
+        match self
+            .queue
+            .place(side, price, qty_ticks, &self.book, self.last_event_time)
+        {
+            Some(id) => tracing::info!(
+                "[{}] Virtual {} order #{} for {} placed",
+                self.symbol,
+                side,
+                id,
+                qty
+            ),
+            None => tracing::warn!(
+                "[{}] Could not place a virtual {} order for {}",
+                self.symbol,
+                side,
+                qty
+            ),
+        }
+        self.publish_snapshot();
+    }
+
+    pub fn clear_virtual_orders(&mut self) {
+        if !self.queue.is_empty() {
+            self.queue.clear();
+            self.publish_snapshot();
+        }
+    }
+
+    pub fn handle_mark_price(&mut self, received: ReceivedMarkPrice) {
+        self.metrics.messages_processed += 1;
+        self.metrics.update_mark_price(&received.update);
+        self.publish_snapshot();
+    }
+
+    pub async fn handle_depth_update(&mut self, received: ReceivedDepthUpdate) -> Result<()> {
+        self.metrics.messages_processed += 1;
+        self.update_rate_counter();
+        let event_time = received.update.event_time;
+        let received_at = received.received_at;
+
+        // held back until the snapshot containing them is published, so a subscriber
+        // that reads the snapshot never misses a delta it doesn't cover
+        let mut applied = Vec::new();
+        match self.sync_state.process_delta(received.update) {
+            SyncOutcome::Updates(updates) => {
+                let auditing = self.conf.audit_interval_secs > 0;
+                for update in updates {
+                    self.levels
+                        .observe_update(&self.book, &update, &self.scaler, &self.conf);
+                    self.queue
+                        .observe_level_events(self.levels.update_events(), &self.book);
+                    self.book.apply_update(&update, &self.scaler)?;
+                    if auditing {
+                        self.auditor.record(&update);
+                    }
+                    applied.push(update);
+                }
+                self.is_syncing = false;
+                self.last_event_time = event_time;
+                self.queue.observe_book(&self.book, event_time);
+                self.flow.observe_book(&self.book, event_time);
+                self.metrics.update_order_flow(&self.flow, &self.scaler);
+                self.metrics.update_level_events(&self.levels, &self.scaler);
+
+                if self.update_health(event_time) {
+                    tracing::warn!(
+                        "[{}] Book crossed for over {}ms, requesting new snapshot...",
+                        self.symbol,
+                        self.conf.crossed_book_grace_ms
+                    );
+                    self.request_resync().await?;
+                } else {
+                    self.run_audit().await?;
+                }
+            }
+            SyncOutcome::GapBetweenUpdates => {
+                self.metrics.gaps_detected += 1;
+                self.request_resync().await?
+            }
+            SyncOutcome::NoUpdates => {}
+        }
+
+        self.metrics
+            .compute_book_metrics(&self.book, &self.scaler, event_time, received_at);
+
+        self.publish_snapshot();
+
+        for update in applied {
+            self.emit(|| BookEvent::Depth {
+                symbol: self.symbol.clone(),
+                update,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_snapshot(&mut self, snapshot: DepthSnapshot) -> Result<()> {
+        tracing::info!(
+            "[{}] Received new snapshot, lastUpdateId: {}",
+            self.symbol,
+            snapshot.last_update_id
+        );
+
+        self.sync_state.set_last_update_id(snapshot.last_update_id);
+        self.book = OrderBook::from_snapshot(snapshot, &self.scaler)?;
+        self.flow.rebase(&self.book);
+        self.levels.rebase();
+        self.queue.observe_book(&self.book, self.last_event_time);
+        self.auditor.reset();
+        self.health = BookHealth::of(&self.book);
+        self.crossed_since_ms = None;
+        self.publish_snapshot();
+
+        self.is_syncing = false;
+        self.emit(|| BookEvent::Reset {
+            symbol: self.symbol.clone(),
+        });
+        Ok(())
+    }
+
+    // queue an independently fetched snapshot to check the local book against
+    pub async fn handle_audit_snapshot(&mut self, snapshot: DepthSnapshot) -> Result<()> {
+        if self.is_syncing {
+            tracing::debug!("[{}] Skipping audit while syncing", self.symbol);
+            return Ok(());
+        }
+        self.auditor.submit(snapshot);
+        self.run_audit().await
+    }
+
+    async fn run_audit(&mut self) -> Result<()> {
+        let Some(local_update_id) = self.sync_state.last_update_id() else {
+            return Ok(());
+        };
+
+        match self
+            .auditor
+            .check(&self.book, local_update_id, &self.scaler)?
+        {
+            AuditOutcome::Idle | AuditOutcome::Waiting => {}
+            AuditOutcome::Unaligned { snapshot_update_id } => {
+                tracing::warn!(
+                    "[{}] Audit snapshot {} could not be aligned with local book at {}, skipped",
+                    self.symbol,
+                    snapshot_update_id,
+                    local_update_id
+                );
+            }
+            AuditOutcome::Completed(report) => self.handle_audit_report(report).await?,
+        }
+        Ok(())
+    }
+
+    async fn handle_audit_report(&mut self, report: AuditReport) -> Result<()> {
+        self.metrics.record_audit(report.mismatches.len());
+
+        if report.mismatches.is_empty() {
+            tracing::info!(
+                "[{}] Audit at {} passed, {} levels compared",
+                self.symbol,
+                report.update_id,
+                report.levels_compared
+            );
+            self.publish_snapshot();
+            return Ok(());
+        }
+
+        tracing::warn!(
+            "[{}] Audit at {} found {}/{} diverged levels",
+            self.symbol,
+            report.update_id,
+            report.mismatches.len(),
+            report.levels_compared
+        );
+        for mismatch in report.mismatches.iter().take(AUDIT_LOGGED_MISMATCHES) {
+            let format_qty = |qty: Option<u64>| {
+                qty.map(|q| self.scaler.ticks_to_qty(q).to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            tracing::warn!(
+                "[{}]   {} {}: local {} vs reference {}",
+                self.symbol,
+                mismatch.side,
+                self.scaler.ticks_to_price(mismatch.price),
+                format_qty(mismatch.local_qty),
+                format_qty(mismatch.reference_qty)
+            );
+        }
+
+        if self.conf.audit_force_resync {
+            tracing::warn!("[{}] Forcing resync after failed audit", self.symbol);
+            self.request_resync().await?;
+        } else {
+            self.publish_snapshot();
+        }
+        Ok(())
+    }
+}
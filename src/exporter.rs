@@ -0,0 +1,336 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::fmt::Write;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::api::http;
+use crate::book::health::BookHealth;
+use crate::engine::metrics::{FeedStats, LagHistogram, MarketMetrics};
+use crate::engine::state::{MarketSnapshot, MarketState};
+
+const PREFIX: &str = "binance_terminal";
+
+// Serves GET /metrics in the Prometheus text format. Bound up front so a bad
+// or busy address fails startup instead of going unnoticed.
+pub struct MetricsExporter {
+    listener: TcpListener,
+    states: Vec<Arc<MarketState>>,
+    feed: Arc<FeedStats>,
+}
+
+impl MetricsExporter {
+    pub async fn bind(
+        addr: &str,
+        states: Vec<Arc<MarketState>>,
+        feed: Arc<FeedStats>,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind metrics exporter to {addr}"))?;
+        Ok(Self {
+            listener,
+            states,
+            feed,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    // runs until the task is dropped
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let Self {
+                listener,
+                states,
+                feed,
+            } = self;
+            let states = Arc::new(states);
+            loop {
+                match listener.accept().await {
+                    Ok((socket, _)) => {
+                        tokio::spawn(serve(socket, states.clone(), feed.clone()));
+                    }
+                    Err(e) => tracing::warn!("Metrics exporter failed to accept: {}", e),
+                }
+            }
+        })
+    }
+}
+
+async fn serve(mut socket: TcpStream, states: Arc<Vec<Arc<MarketState>>>, feed: Arc<FeedStats>) {
+    let Some(request) = http::read_request(&mut socket).await else {
+        return;
+    };
+
+    if request.method == "GET" && request.path == "/metrics" {
+        let body = render(&states, &feed);
+        http::write_response(
+            &mut socket,
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            &body,
+        )
+        .await;
+    } else {
+        http::write_response(&mut socket, "404 Not Found", "text/plain", "Not found\n").await;
+    }
+}
+
+// (symbol, its current snapshot)
+type Row<'a> = (&'a str, Arc<MarketSnapshot>);
+// (name, help, value) of a metric family
+type Gauge = (
+    &'static str,
+    &'static str,
+    fn(&MarketSnapshot) -> Option<f64>,
+);
+type Counter = (&'static str, &'static str, fn(&MarketMetrics) -> u64);
+
+// a metric family with one sample per symbol, skipping symbols without a value
+fn per_symbol(
+    out: &mut String,
+    rows: &[Row],
+    (name, kind, help): (&str, &str, &str),
+    value: impl Fn(&MarketSnapshot) -> Option<f64>,
+) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} {kind}");
+    for (symbol, snapshot) in rows {
+        if let Some(value) = value(snapshot) {
+            let _ = writeln!(out, "{PREFIX}_{name}{{symbol=\"{symbol}\"}} {value}");
+        }
+    }
+}
+
+fn histogram(
+    out: &mut String,
+    rows: &[Row],
+    (name, help): (&str, &str),
+    histogram: impl Fn(&MarketMetrics) -> &LagHistogram,
+) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} histogram");
+    for (symbol, snapshot) in rows {
+        let histogram = histogram(&snapshot.metrics);
+        for (bound, count) in histogram.cumulative() {
+            let le = bound.map_or("+Inf".to_string(), |b| b.to_string());
+            let _ = writeln!(
+                out,
+                "{PREFIX}_{name}_bucket{{symbol=\"{symbol}\",le=\"{le}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{PREFIX}_{name}_sum{{symbol=\"{symbol}\"}} {}",
+            histogram.sum_ms()
+        );
+        let _ = writeln!(
+            out,
+            "{PREFIX}_{name}_count{{symbol=\"{symbol}\"}} {}",
+            histogram.count()
+        );
+    }
+}
+
+fn decimal(value: Option<Decimal>) -> Option<f64> {
+    value.and_then(|v| v.to_f64())
+}
+
+fn flag(set: bool) -> Option<f64> {
+    Some(if set { 1.0 } else { 0.0 })
+}
+
+pub fn render(states: &[Arc<MarketState>], feed: &FeedStats) -> String {
+    let rows: Vec<Row> = states
+        .iter()
+        .map(|state| (state.symbol.as_str(), state.load()))
+        .collect();
+    let mut out = String::new();
+
+    let gauges: [Gauge; 7] = [
+        ("spread", "Best ask minus best bid", |s| {
+            decimal(s.metrics.spread)
+        }),
+        ("mid_price", "Mid price", |s| decimal(s.metrics.mid_price)),
+        (
+            "imbalance_ratio",
+            "Bid share of resting size over the imbalance depth",
+            |s| decimal(s.metrics.imbalance_ratio),
+        ),
+        ("last_price", "Last trade price", |s| {
+            decimal(s.metrics.last_price)
+        }),
+        (
+            "updates_per_second",
+            "Depth updates and trades handled per second",
+            |s| Some(s.metrics.updates_per_second),
+        ),
+        (
+            "book_syncing",
+            "1 while waiting for a snapshot before deltas can be applied",
+            |s| flag(s.is_syncing),
+        ),
+        (
+            "book_crossed",
+            "1 while the best bid is at or above the best ask",
+            |s| flag(s.health != BookHealth::Normal),
+        ),
+    ];
+    for (name, help, value) in gauges {
+        per_symbol(&mut out, &rows, (name, "gauge", help), value);
+    }
+
+    let counters: [Counter; 7] = [
+        (
+            "messages_processed_total",
+            "Depth updates, trades and mark prices handled",
+            |m| m.messages_processed,
+        ),
+        ("trades_total", "Trades received", |m| m.total_trades),
+        (
+            "gaps_detected_total",
+            "Sequence gaps found in the depth stream",
+            |m| m.gaps_detected,
+        ),
+        (
+            "resyncs_total",
+            "Times the book was discarded to wait for a fresh snapshot",
+            |m| m.resyncs,
+        ),
+        (
+            "crossed_book_events_total",
+            "Times the book became crossed",
+            |m| m.crossed_book_events,
+        ),
+        ("audits_total", "Book audits completed", |m| m.audits_run),
+        (
+            "audit_divergences_total",
+            "Book audits that found diverged levels",
+            |m| m.audit_divergences,
+        ),
+    ];
+    for (name, help, value) in counters {
+        per_symbol(&mut out, &rows, (name, "counter", help), |s| {
+            Some(value(&s.metrics) as f64)
+        });
+    }
+
+    histogram(
+        &mut out,
+        &rows,
+        (
+            "orderbook_lag_ms",
+            "Exchange event time to processing of depth updates, in ms",
+        ),
+        |m| &m.orderbook_lag_histogram,
+    );
+    histogram(
+        &mut out,
+        &rows,
+        (
+            "trade_lag_ms",
+            "Exchange trade time to processing of trades, in ms",
+        ),
+        |m| &m.trade_lag_histogram,
+    );
+
+    // the combined stream is shared, so these have no symbol label
+    for (name, help, value) in [
+        (
+            "stream_connections_total",
+            "Successful market stream connections",
+            feed.connections(),
+        ),
+        (
+            "reconnect_attempts_total",
+            "Market stream connection attempts after the first connection",
+            feed.reconnect_attempts(),
+        ),
+        (
+            "parse_errors_total",
+            "Stream frames that could not be parsed",
+            feed.parse_errors(),
+        ),
+    ] {
+        let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+        let _ = writeln!(out, "# TYPE {PREFIX}_{name} counter");
+        let _ = writeln!(out, "{PREFIX}_{name} {value}");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+    use crate::book::orderbook::OrderBook;
+    use crate::book::scaler::Scaler;
+
+    fn state() -> Arc<MarketState> {
+        let scaler = Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3));
+        let book = OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![["100.00".to_string(), "1.500".to_string()]],
+                asks: vec![["100.02".to_string(), "0.250".to_string()]],
+            },
+            &scaler,
+        )
+        .unwrap();
+        let state = MarketState::new(book, "BTCUSDT".to_string(), scaler);
+
+        let mut snapshot = (*state.load()).clone();
+        snapshot.metrics.spread = Some(Decimal::new(2, 2));
+        snapshot.metrics.gaps_detected = 3;
+        snapshot.metrics.orderbook_lag_histogram.observe(7);
+        state.snapshot.store(Arc::new(snapshot));
+        Arc::new(state)
+    }
+
+    #[test]
+    fn renders_gauges_counters_and_histograms() {
+        let feed = FeedStats::default();
+        feed.record_parse_error();
+
+        let text = render(&[state()], &feed);
+
+        assert!(text.contains("# TYPE binance_terminal_spread gauge\n"));
+        assert!(text.contains("binance_terminal_spread{symbol=\"BTCUSDT\"} 0.02\n"));
+        // no mid price has been computed, so no sample
+        assert!(!text.contains("binance_terminal_mid_price{"));
+        assert!(text.contains("binance_terminal_gaps_detected_total{symbol=\"BTCUSDT\"} 3\n"));
+        assert!(
+            text.contains(
+                "binance_terminal_orderbook_lag_ms_bucket{symbol=\"BTCUSDT\",le=\"5\"} 0\n"
+            )
+        );
+        assert!(text.contains(
+            "binance_terminal_orderbook_lag_ms_bucket{symbol=\"BTCUSDT\",le=\"+Inf\"} 1\n"
+        ));
+        assert!(text.contains("binance_terminal_orderbook_lag_ms_sum{symbol=\"BTCUSDT\"} 7\n"));
+        assert!(text.contains("binance_terminal_parse_errors_total 1\n"));
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_over_http() {
+        let exporter = MetricsExporter::bind("127.0.0.1:0", vec![state()], Arc::default())
+            .await
+            .unwrap();
+        let base = format!("http://{}", exporter.local_addr().unwrap());
+        let handle = exporter.spawn();
+
+        let response = reqwest::get(format!("{base}/metrics")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body = response.text().await.unwrap();
+        assert!(body.contains("binance_terminal_resyncs_total{symbol=\"BTCUSDT\"} 0"));
+
+        let missing = reqwest::get(format!("{base}/other")).await.unwrap();
+        assert_eq!(missing.status(), 404);
+        handle.abort();
+    }
+}
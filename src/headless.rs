@@ -0,0 +1,242 @@
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+
+use crate::binance::types::SignificantTrade;
+use crate::book::health::BookHealth;
+use crate::engine::metrics::MarketMetrics;
+use crate::engine::state::{MarketSnapshot, MarketState};
+
+#[derive(Serialize)]
+struct TopOfBook {
+    bid_price: Option<Decimal>,
+    bid_qty: Option<Decimal>,
+    ask_price: Option<Decimal>,
+    ask_qty: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+struct SignificantTradeRecord {
+    trade_time: u64,
+    side: String,
+    price: Decimal,
+    qty: Decimal,
+    notional: Decimal,
+    reason: String,
+}
+
+// one line of output per symbol per interval
+#[derive(Serialize)]
+struct SymbolRecord<'a> {
+    ts: u64,
+    symbol: &'a str,
+    syncing: bool,
+    health: BookHealth,
+    top_of_book: TopOfBook,
+    metrics: &'a MarketMetrics,
+    // only trades that weren't in a previous line
+    significant_trades: Vec<SignificantTradeRecord>,
+}
+
+// Writes every symbol's state to stdout as newline delimited JSON until
+// `shutdown` resolves or stdout is closed (e.g. `| head`). A last line per
+// symbol is written on shutdown so the final state isn't lost.
+pub async fn run(
+    states: &[Arc<MarketState>],
+    interval_ms: u64,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    let mut stdout = tokio::io::stdout();
+    let mut ticker = tokio::time::interval(Duration::from_millis(interval_ms.max(1)));
+    // seq of the newest significant trade already written, per symbol
+    let mut last_significant = vec![0u64; states.len()];
+
+    tokio::pin!(shutdown);
+
+    loop {
+        let stopping = tokio::select! {
+            _ = &mut shutdown => true,
+            _ = ticker.tick() => false,
+        };
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        let mut out = String::new();
+        for (state, last_seen) in states.iter().zip(last_significant.iter_mut()) {
+            let snapshot = state.load();
+            out.push_str(&symbol_line(now_ms, state, &snapshot, last_seen)?);
+            out.push('\n');
+        }
+
+        if let Err(e) = stdout.write_all(out.as_bytes()).await {
+            if e.kind() == std::io::ErrorKind::BrokenPipe {
+                tracing::info!("stdout closed, stopping headless output");
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+        stdout.flush().await?;
+
+        if stopping {
+            return Ok(());
+        }
+    }
+}
+
+fn symbol_line(
+    now_ms: u64,
+    state: &MarketState,
+    snapshot: &MarketSnapshot,
+    last_significant: &mut u64,
+) -> Result<String> {
+    let scaler = &state.scaler;
+    let best_bid = snapshot.book.best_bid();
+    let best_ask = snapshot.book.best_ask();
+
+    let significant_trades = snapshot
+        .significant_trades
+        .iter()
+        .filter(|t| t.seq > *last_significant)
+        .map(significant_trade_record)
+        .collect();
+    if let Some(newest) = snapshot.significant_trades.iter().map(|t| t.seq).max() {
+        *last_significant = (*last_significant).max(newest);
+    }
+
+    let record = SymbolRecord {
+        ts: now_ms,
+        symbol: &state.symbol,
+        syncing: snapshot.is_syncing,
+        health: snapshot.health,
+        top_of_book: TopOfBook {
+            bid_price: best_bid.map(|(p, _)| scaler.ticks_to_price(*p)),
+            bid_qty: best_bid.map(|(_, q)| scaler.ticks_to_qty(*q)),
+            ask_price: best_ask.map(|(p, _)| scaler.ticks_to_price(*p)),
+            ask_qty: best_ask.map(|(_, q)| scaler.ticks_to_qty(*q)),
+        },
+        metrics: &snapshot.metrics,
+        significant_trades,
+    };
+
+    Ok(serde_json::to_string(&record)?)
+}
+
+fn significant_trade_record(trade: &SignificantTrade) -> SignificantTradeRecord {
+    SignificantTradeRecord {
+        trade_time: trade.trade.trade_time,
+        side: trade.side().to_string(),
+        price: trade.trade.price,
+        qty: trade.trade.quantity,
+        notional: trade.notional_value,
+        reason: trade.reasons(),
+    }
+}
+
+// ctrl-c, or SIGTERM when running under a service manager
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::{DepthSnapshot, SignificanceReason, Trade};
+    use crate::book::orderbook::OrderBook;
+    use crate::book::scaler::Scaler;
+
+    fn state() -> MarketState {
+        let scaler = Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3));
+        let book = OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![["100.00".to_string(), "1.500".to_string()]],
+                asks: vec![["100.01".to_string(), "0.250".to_string()]],
+            },
+            &scaler,
+        )
+        .unwrap();
+        MarketState::new(book, "BTCUSDT".to_string(), scaler)
+    }
+
+    fn significant(trade_time: u64, seq: u64) -> SignificantTrade {
+        SignificantTrade::new(
+            Trade {
+                price: Decimal::new(10000, 2),
+                quantity: Decimal::ONE,
+                trade_time,
+                is_buyer_maker: true,
+            },
+            Decimal::new(10000, 2),
+            vec![
+                SignificanceReason::HighVolumePercent(12.5),
+                SignificanceReason::LevelSweep(3),
+            ],
+            seq,
+        )
+    }
+
+    #[test]
+    fn writes_top_of_book_and_metrics() {
+        let state = state();
+        let snapshot = state.load();
+
+        let line = symbol_line(42, &state, &snapshot, &mut 0).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(json["ts"], 42);
+        assert_eq!(json["symbol"], "BTCUSDT");
+        assert_eq!(json["syncing"], true);
+        assert_eq!(json["health"], "normal");
+        assert_eq!(json["top_of_book"]["bid_price"], "100.00");
+        assert_eq!(json["top_of_book"]["ask_qty"], "0.250");
+        assert_eq!(json["metrics"]["total_trades"], 0);
+        assert!(json["metrics"].get("imbalance_depth_levels").is_none());
+    }
+
+    #[test]
+    fn significant_trades_are_written_once() {
+        let state = state();
+        let mut snapshot = (*state.load()).clone();
+        snapshot.significant_trades = vec![significant(1, 1), significant(2, 2)].into();
+        let mut last_seen = 0;
+
+        let first = symbol_line(0, &state, &snapshot, &mut last_seen).unwrap();
+        snapshot.significant_trades.push_back(significant(3, 3));
+        let second = symbol_line(0, &state, &snapshot, &mut last_seen).unwrap();
+
+        let first: serde_json::Value = serde_json::from_str(&first).unwrap();
+        let second: serde_json::Value = serde_json::from_str(&second).unwrap();
+        assert_eq!(first["significant_trades"].as_array().unwrap().len(), 2);
+        assert_eq!(second["significant_trades"][0]["trade_time"], 3);
+        assert_eq!(second["significant_trades"][0]["side"], "SELL");
+        assert_eq!(
+            second["significant_trades"][0]["reason"],
+            "Vol: 12.5%, Sweep: 3"
+        );
+        assert_eq!(second["significant_trades"].as_array().unwrap().len(), 1);
+
+        // a later trade sharing the last one's trade time is still new
+        snapshot.significant_trades.push_back(significant(3, 4));
+        let third = symbol_line(0, &state, &snapshot, &mut last_seen).unwrap();
+        let third: serde_json::Value = serde_json::from_str(&third).unwrap();
+        assert_eq!(third["significant_trades"].as_array().unwrap().len(), 1);
+    }
+}
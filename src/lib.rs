@@ -1,5 +1,14 @@
+pub mod alerts;
+pub mod api;
 pub mod binance;
 pub mod book;
+pub mod cli;
 pub mod config;
+pub mod config_watcher;
 pub mod engine;
+pub mod exporter;
+pub mod headless;
+pub mod recording;
+pub mod shm;
+pub mod source;
 pub mod tui;
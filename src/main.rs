@@ -1,7 +1,16 @@
+mod alerts;
+mod api;
 mod binance;
 mod book;
+mod cli;
 mod config;
+mod config_watcher;
 mod engine;
+mod exporter;
+mod headless;
+mod recording;
+mod shm;
+mod source;
 mod tui;
 
 use std::sync::Arc;
@@ -11,9 +20,23 @@ use tracing::info;
 use tracing_appender::rolling;
 use tracing_subscriber::{EnvFilter, fmt};
 
-use crate::binance::snapshot;
-use crate::book::scaler;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::alerts::AlertMonitor;
+use crate::api::ApiServer;
+use crate::binance::source::BinanceSource;
+use crate::cli::{CliCommand, Mode};
+use crate::config_watcher::ConfigWatcher;
+use crate::engine::events::BookEvent;
+use crate::engine::metrics::FeedStats;
 use crate::engine::runtime::{EngineCommand, MarketDataEngine};
+use crate::engine::state::MarketState;
+use crate::engine::symbol::SymbolSetup;
+use crate::exporter::MetricsExporter;
+use crate::recording::recorder::Recorder;
+use crate::recording::replay::ReplaySource;
+use crate::shm::{ShmReader, ShmWriter};
+use crate::source::MarketDataSource;
 use crate::tui::App;
 
 fn init_logging() -> tracing_appender::non_blocking::WorkerGuard {
@@ -31,6 +54,62 @@ fn init_logging() -> tracing_appender::non_blocking::WorkerGuard {
     guard
 }
 
+// what the rest of the program needs from a started engine
+struct RunningEngine {
+    handle: tokio::task::JoinHandle<()>,
+    command_tx: mpsc::Sender<EngineCommand>,
+    states: Vec<Arc<MarketState>>,
+    feed: Arc<FeedStats>,
+    events: broadcast::Sender<BookEvent>,
+}
+
+// fetches every symbol's bootstrap data from the source and spawns the engine
+async fn start_engine<S: MarketDataSource>(
+    source: S,
+    symbols: &[String],
+    conf: Arc<config::Config>,
+) -> Result<RunningEngine> {
+    let setups =
+        futures_util::future::try_join_all(symbols.iter().map(|symbol| {
+            SymbolSetup::fetch(&source, symbol, conf.orderbook_initial_snapshot_depth)
+        }))
+        .await?;
+
+    let shm_writer = match &conf.shm_path {
+        Some(path) => {
+            info!("Publishing top of book to shared memory at {}", path);
+            Some(ShmWriter::create(
+                path,
+                setups.len(),
+                conf.shm_depth_levels,
+            )?)
+        }
+        None => None,
+    };
+
+    let (mut engine, command_tx, states) = MarketDataEngine::new(Arc::new(source), setups, conf)?;
+    if let Some(writer) = &shm_writer {
+        engine = engine.with_shared_memory(writer);
+    }
+    let feed = engine.feed_stats();
+    let events = engine.book_events();
+
+    // Spawn the engine in the background
+    let handle = tokio::spawn(async move {
+        if let Err(e) = engine.run().await {
+            tracing::error!("Engine error: {}", e);
+        }
+    });
+
+    Ok(RunningEngine {
+        handle,
+        command_tx,
+        states,
+        feed,
+        events,
+    })
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let _log_guard = init_logging();
@@ -40,48 +119,214 @@ async fn main() -> Result<()> {
         .install_default()
         .expect("Failed to install rustls crypto provider");
 
-    let symbol = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("Usage: binance-market-terminal <symbol>"))?;
+    let cli = cli::parse()?;
     // Add visual separator in logs
     info!("");
     info!("================================================");
     info!("");
     info!("[PROGRAM START]");
 
-    let conf = Arc::new(config::load_config());
+    let conf = Arc::new(config::load_config(
+        cli.config_path.as_deref(),
+        &cli.overrides,
+        cli.lenient,
+    )?);
     info!("{:?}", conf);
 
-    let snapshot = snapshot::fetch_snapshot(&symbol, conf.orderbook_initial_snapshot_depth).await?;
-    info!(
-        "[DEPTH SNAPSHOT_INFO] lastUpdateId: {}",
-        snapshot.last_update_id
-    );
+    match cli.command {
+        CliCommand::Tui { mode, headless } => {
+            // a missing file is watched where it would be searched for first
+            let path = config::find_config(cli.config_path.as_deref())
+                .unwrap_or_else(|| config::config_search_paths().remove(0));
+            let watcher = ConfigWatcher::new(path, cli.overrides, cli.lenient, conf.clone());
+            run_terminal(mode, headless, conf, watcher).await?
+        }
+        CliCommand::Snapshot { symbol, limit } => print_snapshot(&conf, &symbol, limit).await?,
+        CliCommand::Info { symbols } => print_info(&conf, &symbols).await?,
+        CliCommand::Shm { path, symbols } => print_shm(&conf, path, &symbols)?,
+    }
 
-    let (tick_size, step_size) = binance::exchange_info::fetch_tick_and_step_sizes(&symbol).await?;
-    let scaler = scaler::Scaler::new(tick_size, step_size);
+    info!("[PROGRAM END]");
+    Ok(())
+}
 
-    let (engine, command_tx, state) =
-        MarketDataEngine::new(symbol, snapshot, scaler, conf.clone())?;
+async fn print_snapshot(conf: &config::Config, symbol: &str, limit: u16) -> Result<()> {
+    let snapshot = BinanceSource::from_config(conf)
+        .fetch_snapshot(symbol, limit)
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    Ok(())
+}
 
-    // Spawn the engine in the background
-    let engine_handle = tokio::spawn(async move {
-        if let Err(e) = engine.run().await {
-            tracing::error!("Engine error: {}", e);
+// one line per filter, e.g. "PRICE_FILTER  minPrice=0.01 maxPrice=1000000.00 tickSize=0.01"
+async fn print_info(conf: &config::Config, symbols: &[String]) -> Result<()> {
+    let source = BinanceSource::from_config(conf);
+    for symbol in symbols {
+        let filters = source.fetch_symbol_filters(symbol).await?;
+        println!("{} ({})", symbol, source.market());
+        for filter in filters {
+            let Some(fields) = filter.as_object() else {
+                continue;
+            };
+            let filter_type = fields
+                .get("filterType")
+                .and_then(|t| t.as_str())
+                .unwrap_or("UNKNOWN");
+            let values: Vec<String> = fields
+                .iter()
+                .filter(|(key, _)| *key != "filterType")
+                .map(|(key, value)| match value.as_str() {
+                    Some(s) => format!("{key}={s}"),
+                    None => format!("{key}={value}"),
+                })
+                .collect();
+            println!("  {:<28}{}", filter_type, values.join(" "));
         }
-    });
+    }
+    Ok(())
+}
+
+// one JSON line per symbol, read the same way a co-located consumer would
+fn print_shm(conf: &config::Config, path: Option<String>, symbols: &[String]) -> Result<()> {
+    let Some(path) = path.or_else(|| conf.shm_path.clone()) else {
+        anyhow::bail!("No shared memory file, pass --path or set shm_path");
+    };
+    let reader = ShmReader::open(&path)?;
+    let indices = if symbols.is_empty() {
+        (0..reader.symbols().len()).collect()
+    } else {
+        symbols
+            .iter()
+            .map(|symbol| {
+                reader
+                    .index_of(symbol)
+                    .ok_or_else(|| anyhow::anyhow!("{symbol} is not in {path}"))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    for index in indices {
+        match reader.read(index) {
+            Some(snapshot) => println!("{}", serde_json::to_string(&snapshot)?),
+            None => anyhow::bail!("Slot {index} in {path} is still being written"),
+        }
+    }
+    Ok(())
+}
+
+async fn run_terminal(
+    mode: Mode,
+    headless: bool,
+    conf: Arc<config::Config>,
+    watcher: ConfigWatcher,
+) -> Result<()> {
+    let mut recording = None;
+
+    let mut replay_control = None;
+
+    let engine = match mode {
+        Mode::Live(symbols) => {
+            let mut source = BinanceSource::from_config(&conf);
+            if let Some(path) = &conf.record_path {
+                info!("Recording market data to {}", path);
+                let (recorder, writer_handle) = Recorder::open(path).await?;
+                source = source.with_recorder(recorder.clone());
+                recording = Some((recorder, writer_handle));
+            }
+            start_engine(source, &symbols, conf.clone()).await?
+        }
+        Mode::Replay { path, speed } => {
+            info!("Replaying {} at {}", path, speed);
+            let source = ReplaySource::open(&path, speed).await?;
+            let symbols = source.symbols();
+            replay_control = Some(source.control());
+            start_engine(source, &symbols, conf.clone()).await?
+        }
+    };
 
-    // Run the TUI in the main task
-    let mut app = App::new(state, conf);
-    app.run().await?;
+    let RunningEngine {
+        handle: engine_handle,
+        command_tx,
+        states,
+        feed,
+        events,
+    } = engine;
 
-    // TUI exited, engine will continue running until dropped
-    command_tx.send(EngineCommand::Shutdown).await?;
+    let config_updates = watcher.spawn(command_tx.clone());
+    let (alert_monitor, alerts) =
+        AlertMonitor::new(states.clone(), conf.clone()).spawn(config_updates.clone());
+
+    let exporter = match &conf.metrics_addr {
+        Some(addr) => {
+            let exporter = MetricsExporter::bind(addr, states.clone(), feed).await?;
+            info!(
+                "Serving Prometheus metrics on http://{}/metrics",
+                exporter.local_addr()?
+            );
+            Some(exporter.spawn())
+        }
+        None => None,
+    };
+
+    let api = match &conf.api_addr {
+        Some(addr) => {
+            let api = ApiServer::bind(addr, states.clone(), events).await?;
+            info!("Serving the API on http://{}/v1", api.local_addr()?);
+            Some(api.spawn())
+        }
+        None => None,
+    };
+
+    if headless {
+        info!(
+            "Running headless, writing JSON lines every {}ms",
+            conf.headless_interval_ms
+        );
+        // stop on a signal, once a replay has run out or if the engine has gone away
+        let stop = async {
+            tokio::select! {
+                _ = headless::shutdown_signal() => {}
+                _ = command_tx.closed() => {}
+                _ = async {
+                    match &replay_control {
+                        Some(control) => control.finished().await,
+                        None => std::future::pending().await,
+                    }
+                } => {}
+            }
+        };
+        headless::run(&states, conf.headless_interval_ms, stop).await?;
+    } else {
+        // Run the TUI in the main task
+        let mut app = App::new(states, conf)
+            .with_config_updates(config_updates)
+            .with_alerts(alerts)
+            .with_commands(command_tx.clone());
+        if let Some(control) = replay_control {
+            app = app.with_replay(control);
+        }
+        app.run().await?;
+    }
+
+    // TUI exited, engine will continue running until dropped.
+    // a headless run may have stopped because the engine already went away
+    let _ = command_tx.send(EngineCommand::Shutdown).await;
 
     if let Err(e) = engine_handle.await {
         tracing::error!("Engine task panicked: {}", e);
     }
+    if let Some(exporter) = exporter {
+        exporter.abort();
+    }
+    if let Some(api) = api {
+        api.abort();
+    }
+    alert_monitor.abort();
+
+    // the engine has dropped the source and its recorder clone, wait for the writer to flush
+    if let Some((recorder, writer_handle)) = recording {
+        drop(recorder);
+        let _ = writer_handle.await;
+    }
 
-    info!("[PROGRAM END]");
     Ok(())
 }
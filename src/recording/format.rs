@@ -0,0 +1,196 @@
+// Line based, append-only recording format. Every line starts with the wall
+// clock receive time in ms and a one letter tag:
+//
+//   <received_ms> I <SYMBOL> <tick_size> <step_size>   instrument metadata
+//   <received_ms> S <SYMBOL> <snapshot json>           REST depth snapshot
+//   <received_ms> F <raw frame json>                   combined stream frame, as received
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use std::time;
+
+use crate::binance::types::DepthSnapshot;
+
+pub const HEADER: &str = "#binance-market-terminal recording v1";
+
+pub fn now_ms() -> u64 {
+    time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[derive(Debug)]
+pub enum RecordEntry {
+    Instrument {
+        received_ms: u64,
+        symbol: String,
+        tick_size: Decimal,
+        step_size: Decimal,
+    },
+    Snapshot {
+        received_ms: u64,
+        symbol: String,
+        snapshot: DepthSnapshot,
+    },
+    Frame {
+        received_ms: u64,
+        text: String,
+    },
+}
+
+impl RecordEntry {
+    pub fn encode(&self) -> Result<String> {
+        let line = match self {
+            RecordEntry::Instrument {
+                received_ms,
+                symbol,
+                tick_size,
+                step_size,
+            } => format!("{} I {} {} {}", received_ms, symbol, tick_size, step_size),
+            RecordEntry::Snapshot {
+                received_ms,
+                symbol,
+                snapshot,
+            } => format!(
+                "{} S {} {}",
+                received_ms,
+                symbol,
+                serde_json::to_string(snapshot)?
+            ),
+            RecordEntry::Frame { received_ms, text } => {
+                // raw newlines can only be insignificant whitespace in valid json
+                if text.contains('\n') {
+                    format!("{} F {}", received_ms, text.replace('\n', " "))
+                } else {
+                    format!("{} F {}", received_ms, text)
+                }
+            }
+        };
+        Ok(line)
+    }
+
+    pub fn decode(line: &str) -> Result<Self> {
+        let (received_ms, rest) = line.split_once(' ').context("Missing receive timestamp")?;
+        let received_ms: u64 = received_ms
+            .parse()
+            .with_context(|| format!("Invalid receive timestamp \"{}\"", received_ms))?;
+        let (tag, body) = rest.split_once(' ').context("Missing entry tag")?;
+
+        match tag {
+            "I" => {
+                let mut parts = body.split(' ');
+                let (Some(symbol), Some(tick_size), Some(step_size), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    anyhow::bail!("Malformed instrument entry \"{}\"", body);
+                };
+                Ok(RecordEntry::Instrument {
+                    received_ms,
+                    symbol: symbol.to_string(),
+                    tick_size: tick_size.parse()?,
+                    step_size: step_size.parse()?,
+                })
+            }
+            "S" => {
+                let (symbol, json) = body.split_once(' ').context("Malformed snapshot entry")?;
+                Ok(RecordEntry::Snapshot {
+                    received_ms,
+                    symbol: symbol.to_string(),
+                    snapshot: serde_json::from_str(json)?,
+                })
+            }
+            "F" => Ok(RecordEntry::Frame {
+                received_ms,
+                text: body.to_string(),
+            }),
+            other => anyhow::bail!("Unknown entry tag \"{}\"", other),
+        }
+    }
+
+    pub fn received_ms(&self) -> u64 {
+        match self {
+            RecordEntry::Instrument { received_ms, .. }
+            | RecordEntry::Snapshot { received_ms, .. }
+            | RecordEntry::Frame { received_ms, .. } => *received_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_one_entry_per_line() {
+        let frame = RecordEntry::Frame {
+            received_ms: 42,
+            text: "{\"stream\":\"btcusdt@trade\",\n\"data\":{}}".to_string(),
+        };
+        assert_eq!(
+            frame.encode().unwrap(),
+            "42 F {\"stream\":\"btcusdt@trade\", \"data\":{}}"
+        );
+
+        let snapshot = RecordEntry::Snapshot {
+            received_ms: 7,
+            symbol: "BTCUSDT".to_string(),
+            snapshot: DepthSnapshot {
+                last_update_id: 100,
+                bids: vec![["1.00".to_string(), "2.0".to_string()]],
+                asks: vec![],
+            },
+        };
+        assert_eq!(
+            snapshot.encode().unwrap(),
+            "7 S BTCUSDT {\"lastUpdateId\":100,\"bids\":[[\"1.00\",\"2.0\"]],\"asks\":[]}"
+        );
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let line = RecordEntry::Instrument {
+            received_ms: 1,
+            symbol: "ETHUSDT".to_string(),
+            tick_size: "0.01".parse().unwrap(),
+            step_size: "0.0001".parse().unwrap(),
+        }
+        .encode()
+        .unwrap();
+
+        match RecordEntry::decode(&line).unwrap() {
+            RecordEntry::Instrument {
+                received_ms,
+                symbol,
+                tick_size,
+                step_size,
+            } => {
+                assert_eq!(received_ms, 1);
+                assert_eq!(symbol, "ETHUSDT");
+                assert_eq!(tick_size.to_string(), "0.01");
+                assert_eq!(step_size.to_string(), "0.0001");
+            }
+            other => panic!("expected instrument, got {other:?}"),
+        }
+
+        let snapshot =
+            RecordEntry::decode("5 S ETHUSDT {\"lastUpdateId\":9,\"bids\":[],\"asks\":[]}")
+                .unwrap();
+        assert!(matches!(
+            snapshot,
+            RecordEntry::Snapshot { received_ms: 5, ref symbol, ref snapshot }
+                if symbol == "ETHUSDT" && snapshot.last_update_id == 9
+        ));
+
+        let frame = RecordEntry::decode("6 F {\"stream\":\"x\"}").unwrap();
+        assert!(
+            matches!(frame, RecordEntry::Frame { received_ms: 6, ref text } if text == "{\"stream\":\"x\"}")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(RecordEntry::decode("not-a-number F {}").is_err());
+        assert!(RecordEntry::decode("5 X whatever").is_err());
+        assert!(RecordEntry::decode("5 I BTCUSDT 0.01").is_err());
+    }
+}
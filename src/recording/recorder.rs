@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::binance::types::DepthSnapshot;
+use crate::recording::format::{self, RecordEntry};
+
+// Cheap to clone handle, entries are sent to a background task that owns the file
+#[derive(Clone)]
+pub struct Recorder {
+    tx: mpsc::UnboundedSender<RecordEntry>,
+}
+
+impl Recorder {
+    // Opens (or appends to) the recording file and spawns the writer task.
+    // The task finishes and flushes once every Recorder clone has been dropped.
+    pub async fn open(path: &str) -> Result<(Self, JoinHandle<()>)> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open recording file \"{}\"", path))?;
+        let is_new = file.metadata().await?.len() == 0;
+
+        let mut writer = BufWriter::new(file);
+        if is_new {
+            writer.write_all(format::HEADER.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let path = path.to_string();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = write_entries(writer, rx).await {
+                tracing::error!("Recording to \"{}\" stopped: {}", path, e);
+            }
+        });
+
+        Ok((Self { tx }, handle))
+    }
+
+    fn send(&self, entry: RecordEntry) {
+        // writer task only goes away on an io error, which it has already logged
+        let _ = self.tx.send(entry);
+    }
+
+    pub fn record_frame(&self, text: &str) {
+        self.send(RecordEntry::Frame {
+            received_ms: format::now_ms(),
+            text: text.to_string(),
+        });
+    }
+
+    pub fn record_snapshot(&self, symbol: &str, snapshot: &DepthSnapshot) {
+        self.send(RecordEntry::Snapshot {
+            received_ms: format::now_ms(),
+            symbol: symbol.to_string(),
+            snapshot: snapshot.clone(),
+        });
+    }
+
+    pub fn record_instrument(&self, symbol: &str, tick_size: Decimal, step_size: Decimal) {
+        self.send(RecordEntry::Instrument {
+            received_ms: format::now_ms(),
+            symbol: symbol.to_string(),
+            tick_size,
+            step_size,
+        });
+    }
+}
+
+async fn write_entries(
+    mut writer: BufWriter<tokio::fs::File>,
+    mut rx: mpsc::UnboundedReceiver<RecordEntry>,
+) -> Result<()> {
+    while let Some(entry) = rx.recv().await {
+        write_entry(&mut writer, &entry).await?;
+
+        // drain whatever is already queued, then flush so the file stays current
+        while let Ok(entry) = rx.try_recv() {
+            write_entry(&mut writer, &entry).await?;
+        }
+        writer.flush().await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+async fn write_entry(writer: &mut BufWriter<tokio::fs::File>, entry: &RecordEntry) -> Result<()> {
+    writer.write_all(entry.encode()?.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    Ok(())
+}
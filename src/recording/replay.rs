@@ -0,0 +1,479 @@
+use anyhow::{Context, Result};
+use futures_util::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{self, Duration};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::sync::watch;
+
+use crate::binance::stream;
+use crate::binance::types::{DepthSnapshot, MarketEvent, ReceivedSnapshot};
+use crate::recording::format::RecordEntry;
+use crate::source::{EventStream, InstrumentInfo, MarketDataSource};
+
+const SPEED_STEPS: [f64; 7] = [1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    // recorded time / wall time, 1.0 is real-time
+    Multiplier(f64),
+    // no pacing at all
+    Max,
+}
+
+impl ReplaySpeed {
+    pub fn faster(self) -> Self {
+        match self {
+            ReplaySpeed::Multiplier(m) => SPEED_STEPS
+                .iter()
+                .find(|&&step| step > m)
+                .map(|&step| ReplaySpeed::Multiplier(step))
+                .unwrap_or(ReplaySpeed::Max),
+            ReplaySpeed::Max => ReplaySpeed::Max,
+        }
+    }
+
+    pub fn slower(self) -> Self {
+        match self {
+            ReplaySpeed::Multiplier(m) => SPEED_STEPS
+                .iter()
+                .rev()
+                .find(|&&step| step < m)
+                .map(|&step| ReplaySpeed::Multiplier(step))
+                .unwrap_or(ReplaySpeed::Multiplier(m)),
+            ReplaySpeed::Max => ReplaySpeed::Multiplier(SPEED_STEPS[SPEED_STEPS.len() - 1]),
+        }
+    }
+}
+
+impl std::str::FromStr for ReplaySpeed {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("max") {
+            return Ok(ReplaySpeed::Max);
+        }
+        let multiplier: f64 = s.trim_end_matches(['x', 'X']).parse().with_context(|| {
+            format!(
+                "Invalid replay speed \"{}\", expected e.g. 1, 10x or max",
+                s
+            )
+        })?;
+        anyhow::ensure!(multiplier > 0.0, "Replay speed must be positive");
+        Ok(ReplaySpeed::Multiplier(multiplier))
+    }
+}
+
+impl std::fmt::Display for ReplaySpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplaySpeed::Multiplier(m) => write!(f, "{}x", m),
+            ReplaySpeed::Max => write!(f, "MAX"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReplaySettings {
+    pub speed: ReplaySpeed,
+    pub paused: bool,
+    // bumped once per requested single step while paused
+    pub step_seq: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplayProgress {
+    pub entries_replayed: u64,
+    pub replay_time_ms: Option<u64>,
+    pub finished: bool,
+}
+
+// Shared between the replay stream and the TUI
+#[derive(Clone)]
+pub struct ReplayControl {
+    settings: Arc<watch::Sender<ReplaySettings>>,
+    progress: Arc<watch::Sender<ReplayProgress>>,
+}
+
+impl ReplayControl {
+    pub fn new(speed: ReplaySpeed) -> Self {
+        let (settings, _) = watch::channel(ReplaySettings {
+            speed,
+            paused: false,
+            step_seq: 0,
+        });
+        let (progress, _) = watch::channel(ReplayProgress::default());
+        Self {
+            settings: Arc::new(settings),
+            progress: Arc::new(progress),
+        }
+    }
+
+    pub fn settings(&self) -> ReplaySettings {
+        *self.settings.borrow()
+    }
+
+    pub fn progress(&self) -> ReplayProgress {
+        *self.progress.borrow()
+    }
+
+    // resolves once every recorded entry has been handed to the engine
+    pub async fn finished(&self) {
+        let mut progress = self.progress.subscribe();
+        let _ = progress.wait_for(|p| p.finished).await;
+    }
+
+    pub fn toggle_pause(&self) {
+        self.settings.send_modify(|s| s.paused = !s.paused);
+    }
+
+    // only has an effect while paused
+    pub fn step(&self) {
+        self.settings.send_if_modified(|s| {
+            if s.paused {
+                s.step_seq += 1;
+            }
+            s.paused
+        });
+    }
+
+    pub fn faster(&self) {
+        self.settings.send_modify(|s| s.speed = s.speed.faster());
+    }
+
+    pub fn slower(&self) {
+        self.settings.send_modify(|s| s.speed = s.speed.slower());
+    }
+}
+
+pub struct ReplayReader {
+    lines: Lines<BufReader<tokio::fs::File>>,
+    line_no: usize,
+    // first entry after the initial instrument/snapshot block
+    pending: Option<RecordEntry>,
+}
+
+impl ReplayReader {
+    async fn next_entry(&mut self) -> Result<Option<RecordEntry>> {
+        if let Some(entry) = self.pending.take() {
+            return Ok(Some(entry));
+        }
+
+        while let Some(line) = self.lines.next_line().await? {
+            self.line_no += 1;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            return RecordEntry::decode(&line)
+                .with_context(|| format!("Recording line {}", self.line_no))
+                .map(Some);
+        }
+        Ok(None)
+    }
+}
+
+// Source backed by a recording file. The leading instrument and snapshot
+// entries stand in for the REST calls, the rest is streamed as market events.
+pub struct ReplaySource {
+    symbols: Vec<String>,
+    instruments: HashMap<String, InstrumentInfo>,
+    snapshots: HashMap<String, DepthSnapshot>,
+    // handed out on the first connect, a recording can only be streamed once
+    reader: Mutex<Option<ReplayReader>>,
+    control: ReplayControl,
+}
+
+impl ReplaySource {
+    pub async fn open(path: &str, speed: ReplaySpeed) -> Result<Self> {
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open recording \"{}\"", path))?;
+        let mut reader = ReplayReader {
+            lines: BufReader::new(file).lines(),
+            line_no: 0,
+            pending: None,
+        };
+
+        let mut symbols = Vec::new();
+        let mut instruments = HashMap::new();
+        let mut snapshots = HashMap::new();
+
+        while let Some(entry) = reader.next_entry().await? {
+            match entry {
+                RecordEntry::Instrument {
+                    symbol,
+                    tick_size,
+                    step_size,
+                    ..
+                } => {
+                    if !instruments.contains_key(&symbol) {
+                        symbols.push(symbol.clone());
+                    }
+                    instruments.insert(
+                        symbol,
+                        InstrumentInfo {
+                            tick_size,
+                            step_size,
+                        },
+                    );
+                }
+                RecordEntry::Snapshot {
+                    symbol, snapshot, ..
+                } => {
+                    snapshots.insert(symbol, snapshot);
+                }
+                frame @ RecordEntry::Frame { .. } => {
+                    reader.pending = Some(frame);
+                    break;
+                }
+            }
+        }
+
+        anyhow::ensure!(!symbols.is_empty(), "Recording has no instrument entries");
+        if let Some(missing) = symbols.iter().find(|s| !snapshots.contains_key(*s)) {
+            anyhow::bail!("Recording has no initial snapshot for {}", missing);
+        }
+
+        Ok(Self {
+            symbols,
+            instruments,
+            snapshots,
+            reader: Mutex::new(Some(reader)),
+            control: ReplayControl::new(speed),
+        })
+    }
+
+    pub fn symbols(&self) -> Vec<String> {
+        self.symbols.clone()
+    }
+
+    pub fn control(&self) -> ReplayControl {
+        self.control.clone()
+    }
+}
+
+impl MarketDataSource for ReplaySource {
+    async fn fetch_snapshot(&self, symbol: &str, _limit: u16) -> Result<DepthSnapshot> {
+        self.snapshots
+            .get(symbol)
+            .cloned()
+            .with_context(|| format!("Recording has no snapshot for {}", symbol))
+    }
+
+    async fn fetch_instrument(&self, symbol: &str) -> Result<InstrumentInfo> {
+        self.instruments
+            .get(symbol)
+            .copied()
+            .with_context(|| format!("Recording has no instrument entry for {}", symbol))
+    }
+
+    async fn connect(&self, _symbols: &[String]) -> Result<EventStream> {
+        let reader = self
+            .reader
+            .lock()
+            .unwrap()
+            .take()
+            .context("Recording has already been replayed")?;
+        Ok(replay_stream(reader, self.control.clone()).boxed())
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+}
+
+struct ReplayState {
+    reader: ReplayReader,
+    control: ReplayControl,
+    settings_rx: watch::Receiver<ReplaySettings>,
+    last_received_ms: Option<u64>,
+    steps_taken: u64,
+}
+
+impl ReplayState {
+    // waits until the entry `gap_ms` of recorded time after the previous one is due
+    async fn wait_for_turn(&mut self, gap_ms: u64) {
+        let mut remaining = Duration::from_millis(gap_ms);
+
+        loop {
+            let settings = *self.settings_rx.borrow_and_update();
+
+            if settings.paused {
+                if settings.step_seq > self.steps_taken {
+                    self.steps_taken = settings.step_seq;
+                    return;
+                }
+                if self.settings_rx.changed().await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            // steps requested while running are meaningless
+            self.steps_taken = settings.step_seq;
+
+            let multiplier = match settings.speed {
+                ReplaySpeed::Max => return,
+                ReplaySpeed::Multiplier(m) => m,
+            };
+            if remaining.is_zero() {
+                return;
+            }
+
+            let started = tokio::time::Instant::now();
+            tokio::select! {
+                _ = tokio::time::sleep(remaining.div_f64(multiplier)) => return,
+                changed = self.settings_rx.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    remaining = remaining.saturating_sub(started.elapsed().mul_f64(multiplier));
+                }
+            }
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<Result<MarketEvent>> {
+        loop {
+            let entry = match self.reader.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    self.control.progress.send_modify(|p| p.finished = true);
+                    return None;
+                }
+                Err(e) => return Some(Err(e)),
+            };
+
+            let received_ms = entry.received_ms();
+            let gap_ms = self
+                .last_received_ms
+                .map(|last| received_ms.saturating_sub(last))
+                .unwrap_or(0);
+            self.wait_for_turn(gap_ms).await;
+            self.last_received_ms = Some(received_ms);
+
+            self.control.progress.send_modify(|p| {
+                p.entries_replayed += 1;
+                p.replay_time_ms = Some(received_ms);
+            });
+
+            let event = match entry {
+                RecordEntry::Frame { text, .. } => stream::parse_frame(&text, time::Instant::now()),
+                RecordEntry::Snapshot {
+                    symbol, snapshot, ..
+                } => Some(Ok(MarketEvent::Snapshot(ReceivedSnapshot {
+                    symbol,
+                    snapshot,
+                }))),
+                // appended sessions repeat the instrument block, tick sizes don't change mid-run
+                RecordEntry::Instrument { .. } => None,
+            };
+
+            if let Some(event) = event {
+                return Some(event);
+            }
+        }
+    }
+}
+
+// Replays the remaining entries as market events, paced by `control`
+fn replay_stream(
+    reader: ReplayReader,
+    control: ReplayControl,
+) -> impl Stream<Item = Result<MarketEvent>> {
+    let state = ReplayState {
+        reader,
+        settings_rx: control.settings.subscribe(),
+        control,
+        last_received_ms: None,
+        steps_taken: 0,
+    };
+
+    futures_util::stream::unfold(state, |mut state| async move {
+        let event = state.next_event().await?;
+        Some((event, state))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDING: &str = r#"#binance-market-terminal recording v1
+100 I BTCUSDT 0.01 0.001
+101 S BTCUSDT {"lastUpdateId":10,"bids":[["100.00","1.000"]],"asks":[["100.01","2.000"]]}
+200 F {"stream":"btcusdt@depth@100ms","data":{"E":200,"U":11,"u":12,"b":[["100.00","0.500"]],"a":[]}}
+300 F {"stream":"btcusdt@trade","data":{"p":"100.01","q":"0.100","T":300,"m":false}}
+400 S BTCUSDT {"lastUpdateId":20,"bids":[],"asks":[]}
+"#;
+
+    fn write_recording(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.rec", name, std::process::id()));
+        std::fs::write(&path, RECORDING).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn speed_steps_up_and_down() {
+        let speed = ReplaySpeed::Multiplier(1.0);
+        assert_eq!(speed.faster(), ReplaySpeed::Multiplier(2.0));
+        assert_eq!(speed.slower(), ReplaySpeed::Multiplier(1.0));
+        assert_eq!(ReplaySpeed::Multiplier(100.0).faster(), ReplaySpeed::Max);
+        assert_eq!(ReplaySpeed::Max.slower(), ReplaySpeed::Multiplier(100.0));
+        assert_eq!(
+            "10x".parse::<ReplaySpeed>().unwrap(),
+            ReplaySpeed::Multiplier(10.0)
+        );
+        assert_eq!("max".parse::<ReplaySpeed>().unwrap(), ReplaySpeed::Max);
+    }
+
+    #[tokio::test]
+    async fn replays_setup_then_events_in_order() {
+        let path = write_recording("replay-order");
+        let source = ReplaySource::open(&path, ReplaySpeed::Max).await.unwrap();
+
+        assert_eq!(source.symbols(), vec!["BTCUSDT".to_string()]);
+        let snapshot = source.fetch_snapshot("BTCUSDT", 1000).await.unwrap();
+        assert_eq!(snapshot.last_update_id, 10);
+        let instrument = source.fetch_instrument("BTCUSDT").await.unwrap();
+        assert_eq!(instrument.tick_size.to_string(), "0.01");
+
+        let control = source.control();
+        let events: Vec<_> = source.connect(&[]).await.unwrap().collect().await;
+        assert!(source.connect(&[]).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], Ok(MarketEvent::Depth(d)) if d.update.final_update_id == 12));
+        assert!(matches!(&events[1], Ok(MarketEvent::Trade(t)) if t.symbol == "btcusdt"));
+        assert!(
+            matches!(&events[2], Ok(MarketEvent::Snapshot(s)) if s.snapshot.last_update_id == 20)
+        );
+
+        let progress = control.progress();
+        assert_eq!(progress.entries_replayed, 3);
+        assert_eq!(progress.replay_time_ms, Some(400));
+        assert!(progress.finished);
+    }
+
+    #[tokio::test]
+    async fn paused_replay_only_advances_on_step() {
+        let path = write_recording("replay-step");
+        let source = ReplaySource::open(&path, ReplaySpeed::Max).await.unwrap();
+
+        let control = source.control();
+        control.toggle_pause();
+        let mut events = source.connect(&[]).await.unwrap();
+
+        let pending = tokio::time::timeout(Duration::from_millis(50), events.next()).await;
+        assert!(pending.is_err(), "paused replay should not emit");
+
+        control.step();
+        let first = tokio::time::timeout(Duration::from_millis(500), events.next())
+            .await
+            .unwrap();
+        assert!(matches!(first, Some(Ok(MarketEvent::Depth(_)))));
+        assert_eq!(control.progress().entries_replayed, 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
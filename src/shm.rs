@@ -0,0 +1,476 @@
+use anyhow::{Context, Result};
+use memmap2::{Mmap, MmapMut};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::book::health::BookHealth;
+use crate::book::orderbook::OrderBook;
+use crate::book::scaler::Scaler;
+
+// Top of book published into a memory-mapped file for processes on the same
+// machine. Every field is a little-endian u64 word:
+//
+//   header   magic, version, slot count, levels per side, words per slot, 3 unused
+//   slot     sequence, symbol (16 bytes), last update id, flags, tick size
+//            (mantissa, scale), step size (mantissa, scale), publish time in
+//            unix ns, bid count, ask count, then `levels` bid (price, qty)
+//            pairs and `levels` ask pairs, as integer ticks
+//
+// One slot per symbol, each guarded by a seqlock: the sequence is odd while
+// the engine is writing, so a reader that sees the same even sequence before
+// and after copying a slot has a consistent snapshot.
+const MAGIC: u64 = u64::from_le_bytes(*b"BMTSHM01");
+const VERSION: u64 = 1;
+const HEADER_WORDS: usize = 8;
+const SLOT_HEADER_WORDS: usize = 12;
+const SYMBOL_BYTES: usize = 16;
+
+// slot word offsets
+const SEQUENCE: usize = 0;
+const SYMBOL: usize = 1;
+const LAST_UPDATE_ID: usize = 3;
+const FLAGS: usize = 4;
+const TICK_SIZE: usize = 5;
+const STEP_SIZE: usize = 7;
+const PUBLISHED_AT: usize = 9;
+const BID_COUNT: usize = 10;
+const ASK_COUNT: usize = 11;
+
+const FLAG_SYNCING: u64 = 1;
+const FLAG_CROSSED: u64 = 1 << 1;
+// stored in place of a last update id while waiting for a snapshot
+const NO_UPDATE_ID: u64 = u64::MAX;
+
+// a torn read is retried this many times before giving up
+const MAX_READ_ATTEMPTS: usize = 1000;
+
+fn slot_words(levels: usize) -> usize {
+    SLOT_HEADER_WORDS + 4 * levels
+}
+
+// the mapping viewed as words, shared by the writer's slots
+struct Words {
+    ptr: *const AtomicU64,
+    len: usize,
+    // keeps the mapping alive, only ever accessed through `ptr`
+    _map: Box<dyn std::any::Any + Send + Sync>,
+}
+
+// SAFETY: all access to the mapping goes through atomics
+unsafe impl Send for Words {}
+unsafe impl Sync for Words {}
+
+impl Words {
+    fn new(
+        ptr: *const u8,
+        bytes: usize,
+        map: Box<dyn std::any::Any + Send + Sync>,
+    ) -> Result<Self> {
+        anyhow::ensure!(
+            (ptr as usize).is_multiple_of(align_of::<AtomicU64>()),
+            "Shared memory mapping is not 8 byte aligned"
+        );
+        Ok(Self {
+            ptr: ptr as *const AtomicU64,
+            len: bytes / size_of::<u64>(),
+            _map: map,
+        })
+    }
+
+    fn word(&self, index: usize) -> &AtomicU64 {
+        assert!(index < self.len, "shared memory word {index} out of bounds");
+        // SAFETY: in bounds, aligned, and the mapping lives as long as self
+        unsafe { &*self.ptr.add(index) }
+    }
+
+    fn load(&self, index: usize) -> u64 {
+        u64::from_le(self.word(index).load(Ordering::Relaxed))
+    }
+
+    fn store(&self, index: usize, value: u64) {
+        self.word(index).store(value.to_le(), Ordering::Relaxed);
+    }
+}
+
+// Creates the file and hands out one slot per symbol to the symbol engines
+pub struct ShmWriter {
+    words: Arc<Words>,
+    levels: usize,
+}
+
+impl ShmWriter {
+    // replaces whatever is at `path`, readers that had it open keep the old mapping
+    pub fn create(path: impl AsRef<Path>, symbol_count: usize, levels: usize) -> Result<Self> {
+        let path = path.as_ref();
+        anyhow::ensure!(
+            levels > 0,
+            "Shared memory needs at least one level per side"
+        );
+        let _ = std::fs::remove_file(path);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("Failed to create shared memory file {}", path.display()))?;
+        let words = HEADER_WORDS + symbol_count * slot_words(levels);
+        file.set_len((words * size_of::<u64>()) as u64)?;
+
+        // SAFETY: the file was just created and only this process writes to it
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        let words = Words::new(map.as_mut_ptr(), map.len(), Box::new(map))?;
+
+        // the magic goes in last, a reader opening the file earlier sees it as not ready
+        words.store(1, VERSION);
+        words.store(2, symbol_count as u64);
+        words.store(3, levels as u64);
+        words.store(4, slot_words(levels) as u64);
+        fence(Ordering::Release);
+        words.store(0, MAGIC);
+
+        Ok(Self {
+            words: Arc::new(words),
+            levels,
+        })
+    }
+
+    pub fn slot(&self, index: usize, symbol: &str, scaler: &Scaler) -> ShmSlot {
+        let slot = ShmSlot {
+            words: self.words.clone(),
+            base: HEADER_WORDS + index * slot_words(self.levels),
+            levels: self.levels,
+        };
+
+        let mut name = [0u8; SYMBOL_BYTES];
+        let len = symbol.len().min(SYMBOL_BYTES);
+        name[..len].copy_from_slice(&symbol.as_bytes()[..len]);
+        slot.write(|words, base| {
+            for (i, chunk) in name.chunks(8).enumerate() {
+                words.store(
+                    base + SYMBOL + i,
+                    u64::from_le_bytes(chunk.try_into().unwrap()),
+                );
+            }
+            let (tick, step) = (scaler.tick_size(), scaler.step_size());
+            words.store(base + TICK_SIZE, tick.mantissa() as u64);
+            words.store(base + TICK_SIZE + 1, tick.scale() as u64);
+            words.store(base + STEP_SIZE, step.mantissa() as u64);
+            words.store(base + STEP_SIZE + 1, step.scale() as u64);
+            words.store(base + LAST_UPDATE_ID, NO_UPDATE_ID);
+            words.store(base + FLAGS, FLAG_SYNCING);
+        });
+        slot
+    }
+}
+
+// One symbol's slot, written by its symbol engine after every change to the book
+pub struct ShmSlot {
+    words: Arc<Words>,
+    base: usize,
+    levels: usize,
+}
+
+impl ShmSlot {
+    pub fn publish(
+        &self,
+        book: &OrderBook,
+        last_update_id: Option<u64>,
+        syncing: bool,
+        health: BookHealth,
+    ) {
+        let (bids, asks) = book.top_n_depth(self.levels);
+        let published_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+
+        self.write(|words, base| {
+            words.store(
+                base + LAST_UPDATE_ID,
+                last_update_id.unwrap_or(NO_UPDATE_ID),
+            );
+            let mut flags = 0;
+            if syncing {
+                flags |= FLAG_SYNCING;
+            }
+            if health != BookHealth::Normal {
+                flags |= FLAG_CROSSED;
+            }
+            words.store(base + FLAGS, flags);
+            words.store(base + PUBLISHED_AT, published_at);
+            words.store(base + BID_COUNT, bids.len() as u64);
+            words.store(base + ASK_COUNT, asks.len() as u64);
+
+            let bids_at = base + SLOT_HEADER_WORDS;
+            let asks_at = bids_at + 2 * self.levels;
+            for (start, levels) in [(bids_at, &bids), (asks_at, &asks)] {
+                for (i, (price, qty)) in levels.iter().enumerate() {
+                    words.store(start + 2 * i, *price);
+                    words.store(start + 2 * i + 1, *qty);
+                }
+            }
+        });
+    }
+
+    fn write(&self, fields: impl FnOnce(&Words, usize)) {
+        let sequence = self.words.word(self.base + SEQUENCE);
+        let start = sequence.load(Ordering::Relaxed);
+        sequence.store(start.wrapping_add(1), Ordering::Relaxed);
+        // the odd sequence must be visible before any field changes
+        fence(Ordering::Release);
+        fields(&self.words, self.base);
+        sequence.store(start.wrapping_add(2), Ordering::Release);
+    }
+}
+
+// A consistent copy of one symbol's slot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShmSnapshot {
+    pub symbol: String,
+    // bumped twice per publish, so it also tells a reader whether anything changed
+    pub sequence: u64,
+    pub last_update_id: Option<u64>,
+    pub syncing: bool,
+    pub crossed: bool,
+    pub published_at_ns: u64,
+    // best first, (price, qty)
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+// Maps a file written by the engine read-only. After `open`, reads are plain
+// memory loads with no syscalls.
+pub struct ShmReader {
+    words: Words,
+    slot_count: usize,
+    levels: usize,
+    slot_words: usize,
+}
+
+impl ShmReader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open shared memory file {}", path.display()))?;
+        // SAFETY: the engine only changes the file through atomics, and
+        // replaces it with a new file rather than truncating it
+        let map = unsafe { Mmap::map(&file)? };
+        let words = Words::new(map.as_ptr(), map.len(), Box::new(map))?;
+        anyhow::ensure!(
+            words.len >= HEADER_WORDS && words.load(0) == MAGIC,
+            "{} is not a market terminal shared memory file",
+            path.display()
+        );
+        fence(Ordering::Acquire);
+        anyhow::ensure!(
+            words.load(1) == VERSION,
+            "Unsupported shared memory version {}",
+            words.load(1)
+        );
+
+        let slot_count = words.load(2) as usize;
+        let levels = words.load(3) as usize;
+        let slot_words = words.load(4) as usize;
+        anyhow::ensure!(
+            slot_words == self::slot_words(levels)
+                && words.len >= HEADER_WORDS + slot_count * slot_words,
+            "Shared memory file {} is truncated",
+            path.display()
+        );
+
+        Ok(Self {
+            words,
+            slot_count,
+            levels,
+            slot_words,
+        })
+    }
+
+    // in slot order, so a symbol's position is the index to `read`
+    pub fn symbols(&self) -> Vec<String> {
+        (0..self.slot_count)
+            .map(|index| self.read(index).map(|s| s.symbol).unwrap_or_default())
+            .collect()
+    }
+
+    // look this up once, slots never move while the engine runs
+    pub fn index_of(&self, symbol: &str) -> Option<usize> {
+        self.symbols()
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case(symbol))
+    }
+
+    // None if the slot doesn't exist or the writer never let go of it
+    pub fn read(&self, index: usize) -> Option<ShmSnapshot> {
+        if index >= self.slot_count {
+            return None;
+        }
+        let base = HEADER_WORDS + index * self.slot_words;
+        let sequence = self.words.word(base + SEQUENCE);
+
+        for _ in 0..MAX_READ_ATTEMPTS {
+            let before = sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let snapshot = self.copy_slot(base, before);
+            fence(Ordering::Acquire);
+            if sequence.load(Ordering::Relaxed) == before {
+                return Some(snapshot);
+            }
+        }
+        None
+    }
+
+    // may be torn, only trusted once the sequence is found unchanged
+    fn copy_slot(&self, base: usize, sequence: u64) -> ShmSnapshot {
+        let words = &self.words;
+        let mut name = Vec::with_capacity(SYMBOL_BYTES);
+        for i in 0..SYMBOL_BYTES / 8 {
+            name.extend_from_slice(&words.load(base + SYMBOL + i).to_le_bytes());
+        }
+        let name_len = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+        let decimal = |at: usize| {
+            Decimal::try_from_i128_with_scale(
+                words.load(at) as i64 as i128,
+                words.load(at + 1) as u32,
+            )
+            .unwrap_or_default()
+        };
+        let scaler = Scaler::new(decimal(base + TICK_SIZE), decimal(base + STEP_SIZE));
+
+        let levels = |start: usize, count: usize| {
+            (0..count.min(self.levels))
+                .map(|i| {
+                    (
+                        scaler.ticks_to_price(words.load(start + 2 * i)),
+                        scaler.ticks_to_qty(words.load(start + 2 * i + 1)),
+                    )
+                })
+                .collect()
+        };
+        let bids_at = base + SLOT_HEADER_WORDS;
+        let asks_at = bids_at + 2 * self.levels;
+        let flags = words.load(base + FLAGS);
+        let last_update_id = words.load(base + LAST_UPDATE_ID);
+
+        ShmSnapshot {
+            symbol: String::from_utf8_lossy(&name[..name_len]).into_owned(),
+            sequence,
+            last_update_id: (last_update_id != NO_UPDATE_ID).then_some(last_update_id),
+            syncing: flags & FLAG_SYNCING != 0,
+            crossed: flags & FLAG_CROSSED != 0,
+            published_at_ns: words.load(base + PUBLISHED_AT),
+            bids: levels(bids_at, words.load(base + BID_COUNT) as usize),
+            asks: levels(asks_at, words.load(base + ASK_COUNT) as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("bmt-shm-{}-{name}", std::process::id()))
+    }
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::new(1, 2), Decimal::new(1, 3))
+    }
+
+    fn book(bid_qty: &str) -> OrderBook {
+        OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![
+                    ["100.00".to_string(), bid_qty.to_string()],
+                    ["99.99".to_string(), "2.000".to_string()],
+                ],
+                asks: vec![["100.02".to_string(), "0.250".to_string()]],
+            },
+            &scaler(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reader_sees_what_the_slots_publish() {
+        let path = temp_path("roundtrip");
+        let writer = ShmWriter::create(&path, 2, 1).unwrap();
+        let btc = writer.slot(0, "BTCUSDT", &scaler());
+        let _eth = writer.slot(1, "ETHUSDT", &scaler());
+
+        let reader = ShmReader::open(&path).unwrap();
+        assert_eq!(reader.symbols(), vec!["BTCUSDT", "ETHUSDT"]);
+        let index = reader.index_of("btcusdt").unwrap();
+        let before = reader.read(index).unwrap();
+        assert!(before.syncing);
+        assert_eq!(before.last_update_id, None);
+
+        btc.publish(&book("1.500"), Some(42), false, BookHealth::Normal);
+        let after = reader.read(index).unwrap();
+
+        assert_eq!(after.sequence, before.sequence + 2);
+        assert_eq!(after.last_update_id, Some(42));
+        assert!(!after.syncing && !after.crossed);
+        // only one level per side fits
+        assert_eq!(
+            after.bids,
+            vec![(Decimal::new(10000, 2), Decimal::new(1500, 3))]
+        );
+        assert_eq!(
+            after.asks,
+            vec![(Decimal::new(10002, 2), Decimal::new(250, 3))]
+        );
+        assert!(reader.read(2).is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_are_never_torn() {
+        let path = temp_path("torn");
+        let writer = ShmWriter::create(&path, 1, 2).unwrap();
+        let slot = writer.slot(0, "BTCUSDT", &scaler());
+        let books = [book("1.000"), book("3.000")];
+
+        let publisher = std::thread::spawn(move || {
+            for i in 0..20_000u64 {
+                slot.publish(&books[i as usize % 2], Some(i), false, BookHealth::Normal);
+            }
+        });
+
+        // the update id and the best bid size are written separately, a torn
+        // read would pair one with the other book
+        let reader = ShmReader::open(&path).unwrap();
+        while !publisher.is_finished() {
+            let Some(snapshot) = reader.read(0) else {
+                continue;
+            };
+            let Some(id) = snapshot.last_update_id else {
+                continue;
+            };
+            let expected = if id % 2 == 0 { "1.000" } else { "3.000" };
+            assert_eq!(snapshot.bids[0].1, expected.parse::<Decimal>().unwrap());
+        }
+        publisher.join().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_files_it_did_not_write() {
+        let path = temp_path("foreign");
+        std::fs::write(&path, [0u8; 128]).unwrap();
+        assert!(ShmReader::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
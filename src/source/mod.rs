@@ -0,0 +1,47 @@
+use anyhow::Result;
+use futures_util::stream::BoxStream;
+use rust_decimal::Decimal;
+use std::future::Future;
+
+use crate::binance::types::{DepthSnapshot, MarketEvent};
+
+pub type EventStream = BoxStream<'static, Result<MarketEvent>>;
+
+// Price and quantity increments of an instrument
+#[derive(Debug, Clone, Copy)]
+pub struct InstrumentInfo {
+    pub tick_size: Decimal,
+    pub step_size: Decimal,
+}
+
+// Everything the engine needs from a venue (or a stand-in for one)
+pub trait MarketDataSource: Send + Sync + 'static {
+    fn fetch_snapshot(
+        &self,
+        symbol: &str,
+        limit: u16,
+    ) -> impl Future<Output = Result<DepthSnapshot>> + Send;
+
+    // Snapshot used only to check the local book against. Unlike fetch_snapshot
+    // it is not part of the market data, so recording sources leave it out.
+    fn fetch_audit_snapshot(
+        &self,
+        symbol: &str,
+        limit: u16,
+    ) -> impl Future<Output = Result<DepthSnapshot>> + Send {
+        self.fetch_snapshot(symbol, limit)
+    }
+
+    fn fetch_instrument(&self, symbol: &str)
+    -> impl Future<Output = Result<InstrumentInfo>> + Send;
+
+    // one stream carrying depth updates and trades for all symbols
+    fn connect(&self, symbols: &[String]) -> impl Future<Output = Result<EventStream>> + Send;
+
+    // Live sources are watched for stalls and reconnected on errors, and gaps are
+    // recovered by fetching a snapshot. Recorded sources deliver snapshots in-band
+    // and simply end.
+    fn is_live(&self) -> bool {
+        true
+    }
+}
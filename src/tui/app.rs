@@ -1,32 +1,194 @@
+use crate::alerts::Alert;
+use crate::book::orderbook::BookSide;
 use crate::config::Config;
+use crate::engine::runtime::EngineCommand;
 use crate::engine::state::MarketState;
+use crate::recording::replay::ReplayControl;
+use crate::tui::candles::CandlePanelState;
+use crate::tui::heatmap::HeatmapState;
+use crate::tui::ladder::{Ladder, LadderState};
 use crossterm::{
     ExecutableCommand,
     event::{self, Event, KeyCode, KeyEventKind},
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
+use std::collections::VecDeque;
 use std::io;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+
+// how long an alert toast stays up, and how many are shown at once
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+const MAX_TOASTS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    // full book and trade flow for the selected symbol
+    Symbol,
+    // one summary row per symbol
+    Overview,
+}
 
 pub struct App {
-    pub state: Arc<MarketState>,
+    pub states: Vec<Arc<MarketState>>,
+    pub selected: usize,
+    pub view: View,
+    pub ladder: LadderState,
+    pub depth_chart: bool,
+    pub heatmap: HeatmapState,
+    pub candles: CandlePanelState,
     pub config: Arc<Config>,
     pub should_quit: bool,
     pub frozen: bool,
     pub update_interval_ms: u64,
     pub start_time: std::time::Instant,
+    pub replay: Option<ReplayControl>,
+    // newest last, with the time each arrived
+    pub toasts: VecDeque<(Instant, Alert)>,
+    config_updates: Option<watch::Receiver<Arc<Config>>>,
+    alerts: Option<mpsc::Receiver<Alert>>,
+    commands: Option<mpsc::Sender<EngineCommand>>,
 }
 
 impl App {
-    pub fn new(state: Arc<MarketState>, config: Arc<Config>) -> Self {
+    pub fn new(states: Vec<Arc<MarketState>>, config: Arc<Config>) -> Self {
+        let heatmap = HeatmapState::new(
+            states.len(),
+            config.heatmap_history_len,
+            config.heatmap_sample_ms,
+        );
         Self {
-            state,
+            states,
+            selected: 0,
+            view: View::Symbol,
+            ladder: LadderState::default(),
+            depth_chart: false,
+            heatmap,
+            candles: CandlePanelState::default(),
             config,
             should_quit: false,
             frozen: false,
             update_interval_ms: 500,
             start_time: std::time::Instant::now(),
+            replay: None,
+            toasts: VecDeque::new(),
+            config_updates: None,
+            alerts: None,
+            commands: None,
+        }
+    }
+
+    // enables the pause/step/speed controls for a replay session
+    pub fn with_replay(mut self, control: ReplayControl) -> Self {
+        self.replay = Some(control);
+        self
+    }
+
+    // picks up config.toml edits made while running
+    pub fn with_config_updates(mut self, updates: watch::Receiver<Arc<Config>>) -> Self {
+        self.config_updates = Some(updates);
+        self
+    }
+
+    // shows alerts as toasts
+    pub fn with_alerts(mut self, alerts: mpsc::Receiver<Alert>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    // lets the TUI place and clear virtual orders in the engine
+    pub fn with_commands(mut self, commands: mpsc::Sender<EngineCommand>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+
+    fn send_command(&self, command: EngineCommand) {
+        if let Some(commands) = &self.commands
+            && commands.try_send(command).is_err()
+        {
+            tracing::warn!("Engine command channel full or closed, command dropped");
+        }
+    }
+
+    // places `virtual_order_qty` for the selected symbol, at the ladder cursor's
+    // price when `at_cursor` and joining the touch otherwise
+    fn place_virtual_order(&self, side: BookSide, at_cursor: bool) {
+        let state = self.selected_state();
+        let price = if at_cursor {
+            let snapshot = state.load();
+            let ladder = Ladder::build(&snapshot.book, self.ladder.group_ticks(), &state.scaler);
+            match ladder.selected(self.ladder.scroll) {
+                Some(level) => Some(state.scaler.ticks_to_price(level.price)),
+                None => return,
+            }
+        } else {
+            None
+        };
+        self.send_command(EngineCommand::PlaceVirtualOrder {
+            symbol: state.symbol.clone(),
+            side,
+            price,
+            qty: self.config.virtual_order_qty,
+        });
+    }
+
+    fn receive_alerts(&mut self) {
+        let now = Instant::now();
+        if let Some(alerts) = &mut self.alerts {
+            while let Ok(alert) = alerts.try_recv() {
+                self.toasts.push_back((now, alert));
+            }
+        }
+        while self.toasts.len() > MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts
+            .retain(|(arrived, _)| now.duration_since(*arrived) < TOAST_DURATION);
+    }
+
+    fn apply_config_updates(&mut self) {
+        let Some(updates) = &mut self.config_updates else {
+            return;
+        };
+        if !updates.has_changed().unwrap_or(false) {
+            return;
+        }
+        self.config = updates.borrow_and_update().clone();
+        self.heatmap.reconfigure(
+            self.config.heatmap_history_len,
+            self.config.heatmap_sample_ms,
+        );
+    }
+
+    pub fn selected_state(&self) -> &Arc<MarketState> {
+        &self.states[self.selected]
+    }
+
+    fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.states.len();
+        self.ladder.recenter();
+    }
+
+    fn select_prev(&mut self) {
+        self.selected = (self.selected + self.states.len() - 1) % self.states.len();
+        self.ladder.recenter();
+    }
+
+    fn scroll_ladder(&mut self, rows: isize) {
+        let snapshot = self.selected_state().load();
+        let max_rows = snapshot.book.bids().len() + snapshot.book.asks().len();
+        self.ladder.scroll_by(rows, max_rows);
+    }
+
+    // samples every symbol so switching to one shows its history straight away
+    fn sample_heatmap(&mut self) {
+        if !self.heatmap.sample_due(std::time::Instant::now()) {
+            return;
+        }
+        for (idx, state) in self.states.iter().enumerate() {
+            self.heatmap.record(idx, &state.load(), &state.scaler);
         }
     }
 
@@ -76,6 +238,9 @@ impl App {
         terminal: &mut Terminal<B>,
     ) -> io::Result<()> {
         loop {
+            self.apply_config_updates();
+            self.receive_alerts();
+            self.sample_heatmap();
             if !self.frozen {
                 terminal.draw(|f| super::ui::render(f, self))?;
             }
@@ -93,6 +258,76 @@ impl App {
                         self.frozen = !self.frozen;
                         terminal.draw(|f| super::ui::render(f, self))?;
                     }
+                    KeyCode::Tab | KeyCode::Right => self.select_next(),
+                    KeyCode::BackTab | KeyCode::Left => self.select_prev(),
+                    KeyCode::Char(c @ '1'..='9') => {
+                        let idx = c as usize - '1' as usize;
+                        if idx < self.states.len() {
+                            self.selected = idx;
+                            self.view = View::Symbol;
+                        }
+                    }
+                    KeyCode::Char('o') | KeyCode::Char('O') => {
+                        self.view = match self.view {
+                            View::Symbol => View::Overview,
+                            View::Overview => View::Symbol,
+                        };
+                    }
+                    KeyCode::Char('l') | KeyCode::Char('L') => self.ladder.toggle(),
+                    KeyCode::Char('d') | KeyCode::Char('D') => {
+                        self.depth_chart = !self.depth_chart;
+                    }
+                    KeyCode::Char('h') | KeyCode::Char('H') => self.heatmap.toggle(),
+                    KeyCode::Char('c') | KeyCode::Char('C') => self.candles.toggle(),
+                    KeyCode::Char('i') | KeyCode::Char('I') if self.candles.enabled => {
+                        self.candles.next_interval()
+                    }
+                    KeyCode::Char('k') | KeyCode::Char('K') if self.ladder.enabled => {
+                        self.scroll_ladder(1)
+                    }
+                    KeyCode::Char('j') | KeyCode::Char('J') if self.ladder.enabled => {
+                        self.scroll_ladder(-1)
+                    }
+                    KeyCode::PageUp if self.ladder.enabled => self.scroll_ladder(10),
+                    KeyCode::PageDown if self.ladder.enabled => self.scroll_ladder(-10),
+                    KeyCode::Home if self.ladder.enabled => self.ladder.recenter(),
+                    KeyCode::Char(']') if self.ladder.enabled => self.ladder.coarser(),
+                    KeyCode::Char('[') if self.ladder.enabled => self.ladder.finer(),
+                    KeyCode::Char('B') if self.ladder.enabled => {
+                        self.place_virtual_order(BookSide::Bid, true)
+                    }
+                    KeyCode::Char('A') if self.ladder.enabled => {
+                        self.place_virtual_order(BookSide::Ask, true)
+                    }
+                    KeyCode::Char('b') | KeyCode::Char('B') => {
+                        self.place_virtual_order(BookSide::Bid, false)
+                    }
+                    KeyCode::Char('a') | KeyCode::Char('A') => {
+                        self.place_virtual_order(BookSide::Ask, false)
+                    }
+                    KeyCode::Char('x') | KeyCode::Char('X') => self.send_command(
+                        EngineCommand::ClearVirtualOrders(self.selected_state().symbol.clone()),
+                    ),
+                    KeyCode::Char(' ') => {
+                        if let Some(replay) = &self.replay {
+                            replay.toggle_pause();
+                        }
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') => {
+                        if let Some(replay) = &self.replay {
+                            replay.step();
+                        }
+                    }
+                    KeyCode::Char('+') | KeyCode::Char('=') => {
+                        if let Some(replay) = &self.replay {
+                            replay.faster();
+                        }
+                    }
+                    KeyCode::Char('-') => {
+                        if let Some(replay) = &self.replay {
+                            replay.slower();
+                        }
+                    }
                     KeyCode::Up => {
                         self.update_interval_ms = (self.update_interval_ms + 100).min(2000);
                     }
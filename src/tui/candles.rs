@@ -0,0 +1,123 @@
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::engine::candles::{Candle, CandleInterval};
+
+#[derive(Default)]
+pub struct CandlePanelState {
+    pub enabled: bool,
+    interval: usize,
+}
+
+impl CandlePanelState {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn next_interval(&mut self) {
+        self.interval = (self.interval + 1) % CandleInterval::ALL.len();
+    }
+
+    pub fn interval(&self) -> CandleInterval {
+        CandleInterval::ALL[self.interval]
+    }
+}
+
+// maps prices onto `rows` text rows, row 0 at the top
+pub struct PriceScale {
+    low: f64,
+    high: f64,
+    rows: usize,
+}
+
+impl PriceScale {
+    pub fn new(candles: &[&Candle], rows: usize) -> Option<Self> {
+        let low = candles.iter().map(|c| c.low).min()?.to_f64()?;
+        let high = candles.iter().map(|c| c.high).max()?.to_f64()?;
+        (rows > 0).then_some(Self { low, high, rows })
+    }
+
+    pub fn row(&self, price: Decimal) -> usize {
+        let price = price.to_f64().unwrap_or(self.low);
+        if self.high <= self.low {
+            return self.rows / 2;
+        }
+        let from_top = (self.high - price) / (self.high - self.low);
+        ((from_top * (self.rows - 1) as f64).round() as usize).min(self.rows - 1)
+    }
+
+    // the character drawn for `candle` in text row `row`
+    pub fn glyph(&self, candle: &Candle, row: usize) -> char {
+        let body_top = self.row(candle.open.max(candle.close));
+        let body_bottom = self.row(candle.open.min(candle.close));
+        if (body_top..=body_bottom).contains(&row) {
+            '█'
+        } else if (self.row(candle.high)..=self.row(candle.low)).contains(&row) {
+            '│'
+        } else {
+            ' '
+        }
+    }
+}
+
+// rows of the volume histogram filled for `candle`, any volume gets at least one
+pub fn volume_height(candle: &Candle, max_volume: Decimal, rows: usize) -> usize {
+    if max_volume.is_zero() {
+        return 0;
+    }
+    let filled = (candle.volume / max_volume * Decimal::from(rows)).ceil();
+    filled.to_usize().unwrap_or(0).min(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(open: i64, high: i64, low: i64, close: i64) -> Candle {
+        Candle {
+            open_time: 0,
+            open: Decimal::new(open, 0),
+            high: Decimal::new(high, 0),
+            low: Decimal::new(low, 0),
+            close: Decimal::new(close, 0),
+            volume: Decimal::ONE,
+            buy_volume: Decimal::ONE,
+            sell_volume: Decimal::ZERO,
+            trade_count: 1,
+        }
+    }
+
+    #[test]
+    fn draws_wicks_outside_the_body() {
+        let up = candle(102, 104, 100, 103);
+        let scale = PriceScale::new(&[&up], 5).unwrap();
+
+        let column: String = (0..5).map(|row| scale.glyph(&up, row)).collect();
+
+        // 104 103 102 101 100
+        assert_eq!(column, "│██││");
+    }
+
+    #[test]
+    fn volume_bars_scale_to_the_largest() {
+        let mut small = candle(1, 1, 1, 1);
+        small.volume = Decimal::new(1, 1);
+        let mut large = candle(1, 1, 1, 1);
+        large.volume = Decimal::new(10, 0);
+
+        assert_eq!(volume_height(&large, large.volume, 4), 4);
+        assert_eq!(volume_height(&small, large.volume, 4), 1);
+        assert_eq!(volume_height(&small, Decimal::ZERO, 4), 0);
+    }
+
+    #[test]
+    fn interval_switcher_wraps() {
+        let mut state = CandlePanelState::default();
+        for _ in 0..CandleInterval::ALL.len() {
+            state.next_interval();
+        }
+        assert_eq!(state.interval(), CandleInterval::S1);
+        state.next_interval();
+        assert_eq!(state.interval(), CandleInterval::S5);
+    }
+}
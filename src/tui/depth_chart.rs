@@ -0,0 +1,124 @@
+use num_traits::ToPrimitive;
+
+use crate::book::orderbook::OrderBook;
+use crate::book::scaler::Scaler;
+
+// cumulative quantity against price, walking out from the touch on each side
+#[derive(Debug, Default)]
+pub struct DepthCurve {
+    pub mid: f64,
+    pub min_price: f64,
+    pub max_price: f64,
+    pub max_qty: f64,
+    // stepped (price, cumulative qty) points, best price first
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+impl DepthCurve {
+    // levels within ±`range_pct` percent of the mid. None for a one sided or empty book
+    pub fn build(book: &OrderBook, scaler: &Scaler, range_pct: f64) -> Option<Self> {
+        let mid = scaler.ticks_to_price(book.mid_price()?).to_f64()?;
+        let min_price = mid * (1.0 - range_pct / 100.0);
+        let max_price = mid * (1.0 + range_pct / 100.0);
+
+        let to_f64 = |price: u64, qty: u64| {
+            (
+                scaler.ticks_to_price(price).to_f64().unwrap_or(0.0),
+                scaler.ticks_to_qty(qty).to_f64().unwrap_or(0.0),
+            )
+        };
+
+        let bids = stepped(
+            book.bids()
+                .iter()
+                .rev()
+                .map(|(p, q)| to_f64(*p, *q))
+                .take_while(|(price, _)| *price >= min_price),
+        );
+        let asks = stepped(
+            book.asks()
+                .iter()
+                .map(|(p, q)| to_f64(*p, *q))
+                .take_while(|(price, _)| *price <= max_price),
+        );
+
+        let max_qty = bids
+            .last()
+            .map(|(_, q)| *q)
+            .unwrap_or(0.0)
+            .max(asks.last().map(|(_, q)| *q).unwrap_or(0.0));
+
+        Some(Self {
+            mid,
+            min_price,
+            max_price,
+            max_qty,
+            bids,
+            asks,
+        })
+    }
+}
+
+// each level adds a vertical step at its price so the line reads as a staircase
+fn stepped(levels: impl Iterator<Item = (f64, f64)>) -> Vec<(f64, f64)> {
+    let mut points = Vec::new();
+    let mut cum_qty = 0.0;
+    for (price, qty) in levels {
+        points.push((price, cum_qty));
+        cum_qty += qty;
+        points.push((price, cum_qty));
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+    use rust_decimal::Decimal;
+
+    fn book(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> OrderBook {
+        let levels = |levels: &[(&str, &str)]| {
+            levels
+                .iter()
+                .map(|(p, q)| [p.to_string(), q.to_string()])
+                .collect()
+        };
+        let snapshot = DepthSnapshot {
+            last_update_id: 1,
+            bids: levels(bids),
+            asks: levels(asks),
+        };
+        OrderBook::from_snapshot(snapshot, &Scaler::new(Decimal::ONE, Decimal::ONE)).unwrap()
+    }
+
+    #[test]
+    fn accumulates_outward_within_range() {
+        let book = book(
+            &[("99", "1"), ("98", "2"), ("50", "100")],
+            &[("101", "3"), ("102", "1"), ("200", "100")],
+        );
+
+        let curve =
+            DepthCurve::build(&book, &Scaler::new(Decimal::ONE, Decimal::ONE), 5.0).unwrap();
+
+        assert_eq!(curve.mid, 100.0);
+        assert_eq!(
+            curve.bids,
+            vec![(99.0, 0.0), (99.0, 1.0), (98.0, 1.0), (98.0, 3.0)]
+        );
+        assert_eq!(
+            curve.asks,
+            vec![(101.0, 0.0), (101.0, 3.0), (102.0, 3.0), (102.0, 4.0)]
+        );
+        assert_eq!(curve.max_qty, 4.0);
+    }
+
+    #[test]
+    fn one_sided_book_has_no_curve() {
+        let book = book(&[("99", "1")], &[]);
+
+        assert!(DepthCurve::build(&book, &Scaler::new(Decimal::ONE, Decimal::ONE), 1.0).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+// heights are scaled to this so small moves still get distinct bars
+const BAR_SCALE: f64 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowBar {
+    pub height: u64,
+    pub negative: bool,
+}
+
+// a running total (cvd) measured up from its lowest point over the last
+// `width` points, so a steady fall still shows its shape
+pub fn level_bars(values: &VecDeque<f64>, width: usize) -> Vec<FlowBar> {
+    let visible = values.iter().skip(values.len().saturating_sub(width));
+    let (min, max) = visible.clone().fold((f64::MAX, f64::MIN), |(min, max), v| {
+        (min.min(*v), max.max(*v))
+    });
+    let range = max - min;
+
+    visible
+        .map(|v| FlowBar {
+            // the lowest point keeps a sliver so the line never disappears
+            height: if range > 0.0 {
+                ((v - min) / range * BAR_SCALE).round().max(1.0) as u64
+            } else {
+                1
+            },
+            negative: *v < 0.0,
+        })
+        .collect()
+}
+
+// per second amounts (ofi) drawn by size, the sign is left to the colour
+pub fn magnitude_bars(values: &VecDeque<f64>, width: usize) -> Vec<FlowBar> {
+    let visible = values.iter().skip(values.len().saturating_sub(width));
+    let max = visible.clone().fold(0.0_f64, |max, v| max.max(v.abs()));
+
+    visible
+        .map(|v| FlowBar {
+            height: if max > 0.0 {
+                (v.abs() / max * BAR_SCALE).round() as u64
+            } else {
+                0
+            },
+            negative: *v < 0.0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_bars_start_from_the_lowest_visible_point() {
+        let values = VecDeque::from(vec![100.0, -4.0, -2.0, 0.0]);
+
+        let bars = level_bars(&values, 3);
+
+        assert_eq!(
+            bars.iter().map(|b| b.height).collect::<Vec<_>>(),
+            vec![1, 500, 1000]
+        );
+        assert!(bars[0].negative && !bars[2].negative);
+        assert_eq!(level_bars(&VecDeque::from(vec![3.0, 3.0]), 5)[0].height, 1);
+    }
+
+    #[test]
+    fn magnitude_bars_scale_to_the_largest_move_either_way() {
+        let values = VecDeque::from(vec![2.0, -4.0, 0.0]);
+
+        let bars = magnitude_bars(&values, 10);
+
+        assert_eq!(
+            bars,
+            vec![
+                FlowBar {
+                    height: 500,
+                    negative: false
+                },
+                FlowBar {
+                    height: 1000,
+                    negative: true
+                },
+                FlowBar {
+                    height: 0,
+                    negative: false
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::binance::types::Side;
+use crate::book::scaler::Scaler;
+use crate::engine::state::MarketSnapshot;
+
+// levels kept per side in each sample, plenty for the price range shown around the mid
+const SAMPLE_LEVELS: usize = 200;
+
+// one column of the heatmap
+#[derive(Debug, Default)]
+pub struct BookSample {
+    pub mid: Option<u64>,
+    pub bids: Vec<(u64, u64)>,
+    pub asks: Vec<(u64, u64)>,
+    // (price ticks, aggressive buy) for trades printed since the previous sample
+    pub trades: Vec<(u64, bool)>,
+}
+
+#[derive(Default)]
+struct SymbolHistory {
+    samples: VecDeque<BookSample>,
+    last_trade_time: u64,
+}
+
+// Periodic copies of each symbol's book near the touch, kept whether or not
+// the panel is showing so the history is already there when it's opened
+pub struct HeatmapState {
+    pub enabled: bool,
+    history_len: usize,
+    sample_interval: Duration,
+    last_sample: Option<Instant>,
+    histories: Vec<SymbolHistory>,
+}
+
+impl HeatmapState {
+    pub fn new(symbol_count: usize, history_len: usize, sample_interval_ms: u64) -> Self {
+        Self {
+            enabled: false,
+            history_len: history_len.max(1),
+            sample_interval: Duration::from_millis(sample_interval_ms),
+            last_sample: None,
+            histories: (0..symbol_count)
+                .map(|_| SymbolHistory::default())
+                .collect(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    // applies a reloaded config, dropping the oldest samples if the history shrank
+    pub fn reconfigure(&mut self, history_len: usize, sample_interval_ms: u64) {
+        self.history_len = history_len.max(1);
+        self.sample_interval = Duration::from_millis(sample_interval_ms);
+        for history in &mut self.histories {
+            let excess = history.samples.len().saturating_sub(self.history_len);
+            history.samples.drain(..excess);
+        }
+    }
+
+    pub fn sample_interval(&self) -> Duration {
+        self.sample_interval
+    }
+
+    // marks a sample as taken when one is due
+    pub fn sample_due(&mut self, now: Instant) -> bool {
+        let due = self
+            .last_sample
+            .is_none_or(|last| now.duration_since(last) >= self.sample_interval);
+        if due {
+            self.last_sample = Some(now);
+        }
+        due
+    }
+
+    pub fn record(&mut self, symbol_idx: usize, snapshot: &MarketSnapshot, scaler: &Scaler) {
+        let Some(history) = self.histories.get_mut(symbol_idx) else {
+            return;
+        };
+
+        let (bids, asks) = snapshot.book.top_n_depth(SAMPLE_LEVELS);
+
+        // trades sharing a millisecond with the last one already sampled are skipped
+        let trades = snapshot
+            .recent_trades
+            .iter()
+            .filter(|trade| trade.trade_time > history.last_trade_time)
+            .filter_map(|trade| {
+                let price = scaler.price_to_ticks(&trade.price.to_string())?;
+                Some((price, matches!(trade.side(), Side::Buy)))
+            })
+            .collect();
+        if let Some(last) = snapshot.recent_trades.back() {
+            history.last_trade_time = history.last_trade_time.max(last.trade_time);
+        }
+
+        if history.samples.len() >= self.history_len {
+            history.samples.pop_front();
+        }
+        history.samples.push_back(BookSample {
+            mid: snapshot.book.mid_price(),
+            bids,
+            asks,
+            trades,
+        });
+    }
+
+    pub fn samples(&self, symbol_idx: usize) -> Option<&VecDeque<BookSample>> {
+        self.histories.get(symbol_idx).map(|h| &h.samples)
+    }
+}
+
+// samples bucketed into a rows x cols grid, newest sample in the rightmost column
+#[derive(Debug)]
+pub struct HeatmapGrid {
+    // price of the top row in ticks, rows step down by `row_ticks`
+    pub top_price: u64,
+    pub row_ticks: u64,
+    // resting quantity per cell scaled to 0..=1, indexed [row][col]
+    pub cells: Vec<Vec<f64>>,
+    // (row, col, aggressive buy)
+    pub trades: Vec<(usize, usize, bool)>,
+}
+
+impl HeatmapGrid {
+    // centred on the latest mid and spanning ±`range_pct` percent of it
+    pub fn build(
+        samples: &VecDeque<BookSample>,
+        rows: usize,
+        cols: usize,
+        range_pct: f64,
+    ) -> Option<Self> {
+        if rows == 0 || cols == 0 {
+            return None;
+        }
+        let mid = samples.back()?.mid?;
+
+        let range_ticks = (mid as f64 * range_pct / 100.0) as u64;
+        let row_ticks = (2 * range_ticks).div_ceil(rows as u64).max(1);
+        let top_price = mid + row_ticks * (rows as u64 / 2);
+
+        let row_of = |price: u64| {
+            let row = top_price.checked_sub(price)? / row_ticks;
+            (row < rows as u64).then_some(row as usize)
+        };
+
+        let mut qty = vec![vec![0u64; cols]; rows];
+        let mut trades = Vec::new();
+        let first_col = cols.saturating_sub(samples.len());
+        let visible = samples.iter().skip(samples.len().saturating_sub(cols));
+
+        for (col, sample) in (first_col..).zip(visible) {
+            for &(price, level_qty) in sample.bids.iter().chain(&sample.asks) {
+                if let Some(row) = row_of(price) {
+                    qty[row][col] += level_qty;
+                }
+            }
+            for &(price, is_buy) in &sample.trades {
+                if let Some(row) = row_of(price) {
+                    trades.push((row, col, is_buy));
+                }
+            }
+        }
+
+        // square root so thinner levels still show up next to a wall
+        let max_qty = qty.iter().flatten().copied().max().unwrap_or(0).max(1) as f64;
+        let cells = qty
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|q| (q as f64 / max_qty).sqrt())
+                    .collect()
+            })
+            .collect();
+
+        Some(Self {
+            top_price,
+            row_ticks,
+            cells,
+            trades,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::{DepthSnapshot, Trade};
+    use crate::book::health::BookHealth;
+    use crate::book::orderbook::OrderBook;
+    use crate::engine::candles::CandleSeries;
+    use crate::engine::metrics::MarketMetrics;
+    use rust_decimal::Decimal;
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::ONE, Decimal::ONE)
+    }
+
+    fn trade(price: i64, trade_time: u64, is_buyer_maker: bool) -> Trade {
+        Trade {
+            price: Decimal::new(price, 0),
+            quantity: Decimal::ONE,
+            trade_time,
+            is_buyer_maker,
+        }
+    }
+
+    fn snapshot(trades: Vec<Trade>) -> MarketSnapshot {
+        let book = OrderBook::from_snapshot(
+            DepthSnapshot {
+                last_update_id: 1,
+                bids: vec![["99".to_string(), "4".to_string()]],
+                asks: vec![["101".to_string(), "1".to_string()]],
+            },
+            &scaler(),
+        )
+        .unwrap();
+        MarketSnapshot {
+            book,
+            metrics: MarketMetrics::default(),
+            recent_trades: trades.into(),
+            significant_trades: VecDeque::new(),
+            candles: CandleSeries::default(),
+            level_activity: Default::default(),
+            virtual_orders: Vec::new(),
+            is_syncing: false,
+            health: BookHealth::default(),
+            last_update_id: None,
+        }
+    }
+
+    #[test]
+    fn samples_only_carry_new_trades_and_history_is_bounded() {
+        let mut state = HeatmapState::new(1, 2, 100);
+
+        state.record(0, &snapshot(vec![trade(100, 1, false)]), &scaler());
+        state.record(
+            0,
+            &snapshot(vec![trade(100, 1, false), trade(101, 2, true)]),
+            &scaler(),
+        );
+        state.record(0, &snapshot(vec![trade(101, 2, true)]), &scaler());
+
+        let samples = state.samples(0).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].trades, vec![(101, false)]);
+        assert!(samples[1].trades.is_empty());
+        assert_eq!(samples[1].mid, Some(100));
+    }
+
+    #[test]
+    fn grid_buckets_levels_and_trades_around_the_mid() {
+        let mut state = HeatmapState::new(1, 10, 100);
+        state.record(0, &snapshot(vec![trade(101, 1, false)]), &scaler());
+        state.record(0, &snapshot(vec![]), &scaler());
+
+        // ±2% of 100 over 4 rows = 1 tick per row, top row at 102
+        let grid = HeatmapGrid::build(state.samples(0).unwrap(), 4, 3, 2.0).unwrap();
+
+        assert_eq!(grid.top_price, 102);
+        assert_eq!(grid.row_ticks, 1);
+        // oldest column is empty, the two samples are right aligned
+        assert_eq!(grid.cells[3], vec![0.0, 1.0, 1.0]);
+        assert_eq!(grid.cells[1], vec![0.0, 0.5, 0.5]);
+        assert_eq!(grid.trades, vec![(1, 1, true)]);
+    }
+
+    #[test]
+    fn sampling_is_rate_limited() {
+        let mut state = HeatmapState::new(1, 10, 100);
+        let start = Instant::now();
+
+        assert!(state.sample_due(start));
+        assert!(!state.sample_due(start + Duration::from_millis(50)));
+        assert!(state.sample_due(start + Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,282 @@
+use rust_decimal::Decimal;
+
+use crate::book::orderbook::OrderBook;
+use crate::book::scaler::Scaler;
+
+// bucket sizes in ticks, e.g. 0.01 -> 0.1 -> 1 -> 10 -> 100 for a 0.01 tick
+const GROUPINGS: [u64; 5] = [1, 10, 100, 1_000, 10_000];
+
+// scroll and grouping for the full depth ladder
+#[derive(Default)]
+pub struct LadderState {
+    pub enabled: bool,
+    // rows scrolled away from the touch, positive = towards higher prices
+    pub scroll: isize,
+    grouping: usize,
+}
+
+impl LadderState {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.scroll = 0;
+    }
+
+    // `max_rows` keeps the offset from running away past the ends of the book
+    pub fn scroll_by(&mut self, rows: isize, max_rows: usize) {
+        let max_rows = max_rows as isize;
+        self.scroll = (self.scroll + rows).clamp(-max_rows, max_rows);
+    }
+
+    pub fn recenter(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn coarser(&mut self) {
+        self.grouping = (self.grouping + 1).min(GROUPINGS.len() - 1);
+        self.scroll = 0;
+    }
+
+    pub fn finer(&mut self) {
+        self.grouping = self.grouping.saturating_sub(1);
+        self.scroll = 0;
+    }
+
+    pub fn group_ticks(&self) -> u64 {
+        GROUPINGS[self.grouping]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderLevel {
+    // bucket price in ticks
+    pub price: u64,
+    pub qty: u64,
+    // running totals from the touch out to and including this level
+    pub cum_qty: u64,
+    pub cum_notional: Decimal,
+}
+
+#[derive(Debug)]
+pub struct Ladder {
+    // best ask first, moving away from the touch
+    pub asks: Vec<LadderLevel>,
+    // best bid first, moving away from the touch
+    pub bids: Vec<LadderLevel>,
+}
+
+impl Ladder {
+    // bids round down and asks round up, so a bucket never claims a better price than its levels
+    pub fn build(book: &OrderBook, group_ticks: u64, scaler: &Scaler) -> Self {
+        let group_ticks = group_ticks.max(1);
+        let bids = aggregate(
+            book.bids().iter().rev(),
+            |price| price / group_ticks * group_ticks,
+            scaler,
+        );
+        let asks = aggregate(
+            book.asks().iter(),
+            |price| price.div_ceil(group_ticks) * group_ticks,
+            scaler,
+        );
+        Self { asks, bids }
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.asks.len() + self.bids.len()
+    }
+
+    // first row of a `height` row window over asks (descending) followed by bids,
+    // centred on the touch and shifted by `scroll`
+    pub fn window_start(&self, height: usize, scroll: isize) -> usize {
+        let max_start = self.row_count().saturating_sub(height) as isize;
+        let centred = self.asks.len() as isize - (height / 2) as isize;
+        (centred - scroll).clamp(0, max_start) as usize
+    }
+
+    // row under the cursor, which sits where the window is centred: the best bid
+    // at scroll 0, one row further towards higher prices per row scrolled up
+    pub fn selected_index(&self, scroll: isize) -> Option<usize> {
+        let last = self.row_count().checked_sub(1)? as isize;
+        Some((self.asks.len() as isize - scroll).clamp(0, last) as usize)
+    }
+
+    pub fn selected(&self, scroll: isize) -> Option<&LadderLevel> {
+        let index = self.selected_index(scroll)?;
+        match index.checked_sub(self.asks.len()) {
+            Some(bid) => self.bids.get(bid),
+            None => self.asks.get(self.asks.len() - 1 - index),
+        }
+    }
+}
+
+fn aggregate<'a>(
+    levels: impl Iterator<Item = (&'a u64, &'a u64)>,
+    bucket: impl Fn(u64) -> u64,
+    scaler: &Scaler,
+) -> Vec<LadderLevel> {
+    let mut out: Vec<LadderLevel> = Vec::new();
+    let mut cum_qty = 0;
+    let mut cum_notional = Decimal::ZERO;
+
+    for (&price, &qty) in levels {
+        cum_qty += qty;
+        cum_notional += scaler.ticks_to_price(price) * scaler.ticks_to_qty(qty);
+
+        let bucket_price = bucket(price);
+        match out.last_mut() {
+            Some(last) if last.price == bucket_price => {
+                last.qty += qty;
+                last.cum_qty = cum_qty;
+                last.cum_notional = cum_notional;
+            }
+            _ => out.push(LadderLevel {
+                price: bucket_price,
+                qty,
+                cum_qty,
+                cum_notional,
+            }),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance::types::DepthSnapshot;
+
+    fn scaler() -> Scaler {
+        Scaler::new(Decimal::new(1, 2), Decimal::ONE)
+    }
+
+    fn book(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> OrderBook {
+        let levels = |levels: &[(&str, &str)]| {
+            levels
+                .iter()
+                .map(|(p, q)| [p.to_string(), q.to_string()])
+                .collect()
+        };
+        let snapshot = DepthSnapshot {
+            last_update_id: 1,
+            bids: levels(bids),
+            asks: levels(asks),
+        };
+        OrderBook::from_snapshot(snapshot, &scaler()).unwrap()
+    }
+
+    #[test]
+    fn ungrouped_ladder_keeps_every_level_with_running_totals() {
+        let book = book(&[("99.99", "1"), ("99.98", "2")], &[("100.01", "3")]);
+
+        let ladder = Ladder::build(&book, 1, &scaler());
+
+        assert_eq!(
+            ladder.bids,
+            vec![
+                LadderLevel {
+                    price: 9999,
+                    qty: 1,
+                    cum_qty: 1,
+                    cum_notional: Decimal::new(9999, 2),
+                },
+                LadderLevel {
+                    price: 9998,
+                    qty: 2,
+                    cum_qty: 3,
+                    cum_notional: Decimal::new(9999 + 2 * 9998, 2),
+                },
+            ]
+        );
+        assert_eq!(ladder.asks.len(), 1);
+        assert_eq!(ladder.asks[0].cum_notional, Decimal::new(30003, 2));
+    }
+
+    #[test]
+    fn groups_bids_down_and_asks_up() {
+        let book = book(
+            &[("99.99", "1"), ("99.91", "1"), ("99.89", "5")],
+            &[("100.01", "1"), ("100.10", "2"), ("100.11", "4")],
+        );
+
+        // 10 ticks = 0.10 buckets
+        let ladder = Ladder::build(&book, 10, &scaler());
+
+        let bids: Vec<(u64, u64, u64)> = ladder
+            .bids
+            .iter()
+            .map(|l| (l.price, l.qty, l.cum_qty))
+            .collect();
+        let asks: Vec<(u64, u64, u64)> = ladder
+            .asks
+            .iter()
+            .map(|l| (l.price, l.qty, l.cum_qty))
+            .collect();
+
+        assert_eq!(bids, vec![(9990, 2, 2), (9980, 5, 7)]);
+        assert_eq!(asks, vec![(10010, 3, 3), (10020, 4, 7)]);
+    }
+
+    #[test]
+    fn window_is_centred_on_the_touch_and_clamped() {
+        let ladder = Ladder {
+            asks: vec![
+                LadderLevel {
+                    price: 0,
+                    qty: 0,
+                    cum_qty: 0,
+                    cum_notional: Decimal::ZERO,
+                };
+                10
+            ],
+            bids: vec![
+                LadderLevel {
+                    price: 0,
+                    qty: 0,
+                    cum_qty: 0,
+                    cum_notional: Decimal::ZERO,
+                };
+                10
+            ],
+        };
+
+        assert_eq!(ladder.window_start(6, 0), 7);
+        assert_eq!(ladder.window_start(6, 3), 4);
+        assert_eq!(ladder.window_start(6, 100), 0);
+        assert_eq!(ladder.window_start(6, -100), 14);
+        assert_eq!(ladder.window_start(40, 0), 0);
+    }
+
+    #[test]
+    fn cursor_starts_on_the_best_bid_and_stops_at_the_ends() {
+        let book = book(
+            &[("99.99", "1"), ("99.98", "2")],
+            &[("100.01", "3"), ("100.02", "4")],
+        );
+        let ladder = Ladder::build(&book, 1, &scaler());
+
+        let selected = |scroll| ladder.selected(scroll).map(|l| l.price);
+        assert_eq!(selected(0), Some(9999));
+        assert_eq!(selected(-1), Some(9998));
+        assert_eq!(selected(1), Some(10001));
+        assert_eq!(selected(2), Some(10002));
+        assert_eq!(selected(100), Some(10002));
+        assert_eq!(selected(-100), Some(9998));
+
+        let empty = Ladder {
+            asks: Vec::new(),
+            bids: Vec::new(),
+        };
+        assert!(empty.selected(0).is_none());
+    }
+
+    #[test]
+    fn grouping_steps_are_bounded() {
+        let mut state = LadderState::default();
+        state.finer();
+        assert_eq!(state.group_ticks(), 1);
+        for _ in 0..10 {
+            state.coarser();
+        }
+        assert_eq!(state.group_ticks(), 10_000);
+    }
+}
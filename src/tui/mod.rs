@@ -1,4 +1,9 @@
 mod app;
+mod candles;
+mod depth_chart;
+mod flow;
+mod heatmap;
+mod ladder;
 mod ui;
 
-pub use app::App;
+pub use app::{App, View};
@@ -1,40 +1,85 @@
-use crate::{book::scaler::Scaler, config::Config, engine::state::MarketSnapshot};
+use super::View;
+use super::candles::{PriceScale, volume_height};
+use super::depth_chart::DepthCurve;
+use super::flow;
+use super::heatmap::HeatmapGrid;
+use super::ladder::{Ladder, LadderLevel, LadderState};
+use crate::{
+    book::{health::BookHealth, orderbook::BookSide, scaler::Scaler},
+    config::Config,
+    engine::{
+        candles::{Candle, CandleInterval},
+        levels::LevelActivity,
+        metrics::MarketMetrics,
+        queue::VirtualOrderEstimate,
+        state::{MarketSnapshot, MarketState},
+    },
+};
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols::Marker,
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    widgets::{
+        Axis, Block, Borders, Cell, Chart, Clear, Dataset, GraphType, Paragraph, Row, Sparkline,
+        SparklineBar, Table,
+    },
 };
+use std::sync::Arc;
 
 pub fn render(frame: &mut Frame, app_data: &super::App) {
-    let snapshot = app_data.state.load();
+    let state = app_data.selected_state();
+    let snapshot = state.load();
+    let market = app_data.config.market;
+
+    // futures get a second header line for mark price and funding
+    let header_height = if market.is_futures() { 4 } else { 3 };
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),
+            Constraint::Length(header_height),
             Constraint::Min(0),
             Constraint::Length(1),
         ])
         .split(frame.area());
 
-    render_header(
-        frame,
-        chunks[0],
-        &app_data.state.symbol,
-        &snapshot,
-        app_data.frozen,
-        app_data.start_time.elapsed(),
-    );
-    render_main(
-        frame,
-        chunks[1],
-        &app_data.state.scaler,
-        &snapshot,
-        &app_data.config,
-    );
-    render_footer(frame, chunks[2], app_data.update_interval_ms);
+    render_header(frame, chunks[0], &state.symbol, &snapshot, app_data);
+    match app_data.view {
+        View::Symbol => render_main(frame, chunks[1], &snapshot, app_data),
+        View::Overview => render_overview(frame, chunks[1], &app_data.states, app_data.selected),
+    }
+    render_footer(frame, chunks[2], app_data);
+    render_toasts(frame, chunks[1], app_data);
+}
+
+// recent alerts stacked in the top right corner, over whatever is there
+fn render_toasts(frame: &mut Frame, area: Rect, app_data: &super::App) {
+    let width = area.width.min(60);
+    let mut y = area.y;
+    for (_, alert) in app_data.toasts.iter().rev() {
+        if y + 4 > area.bottom() {
+            break;
+        }
+        let toast = Rect::new(area.right() - width, y, width, 4);
+        let paragraph = Paragraph::new(alert.message.as_str())
+            .wrap(ratatui::widgets::Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(Span::styled(
+                        format!(" {} {} ", alert.symbol, alert.rule),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    )),
+            );
+        frame.render_widget(Clear, toast);
+        frame.render_widget(paragraph, toast);
+        y += 4;
+    }
 }
 
 fn render_header(
@@ -42,10 +87,14 @@ fn render_header(
     area: Rect,
     symbol: &str,
     snapshot: &MarketSnapshot,
-    frozen: bool,
-    uptime: std::time::Duration,
+    app_data: &super::App,
 ) {
+    let market = app_data.config.market;
+    let frozen = app_data.frozen;
+    let uptime = app_data.start_time.elapsed();
+    let replay = app_data.replay.as_ref();
     let metrics = &snapshot.metrics;
+    let replay_paused = replay.is_some_and(|r| r.settings().paused);
 
     let status = if frozen {
         Span::styled(
@@ -54,10 +103,24 @@ fn render_header(
                 .fg(Color::Blue)
                 .add_modifier(Modifier::BOLD),
         )
+    } else if replay_paused {
+        Span::styled(
+            "PAUSED",
+            Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+        )
     } else if snapshot.is_syncing {
         Span::styled("SYNCING", Style::default().fg(Color::Yellow))
     } else {
-        Span::styled("LIVE", Style::default().fg(Color::Green))
+        match snapshot.health {
+            BookHealth::Crossed => Span::styled(
+                "CROSSED",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            BookHealth::Locked => Span::styled("LOCKED", Style::default().fg(Color::Yellow)),
+            BookHealth::Normal => Span::styled("LIVE", Style::default().fg(Color::Green)),
+        }
     };
 
     let format_symbol = Span::styled(
@@ -97,8 +160,10 @@ fn render_header(
         }
     };
 
-    let left_header_text = vec![Line::from(vec![
+    let mut left_header_text = vec![Line::from(vec![
         format_symbol,
+        Span::raw(" "),
+        Span::styled(market.to_string(), Style::default().fg(Color::DarkGray)),
         Span::raw(" | "),
         status,
         Span::raw(" | "),
@@ -111,10 +176,33 @@ fn render_header(
         Span::raw(format!("{:.0}/s", metrics.updates_per_second)),
     ])];
 
-    let right_header_text = vec![Line::from(vec![
-        Span::styled("Uptime: ", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(duration_to_string(uptime)),
-    ])];
+    if market.is_futures() {
+        left_header_text.push(futures_line(metrics));
+    }
+
+    let right_header_text = match replay {
+        Some(replay) => {
+            let progress = replay.progress();
+            let clock = progress
+                .replay_time_ms
+                .map(format_clock_ms)
+                .unwrap_or_else(|| "--:--:--".to_string());
+            let speed = if progress.finished {
+                "DONE".to_string()
+            } else {
+                replay.settings().speed.to_string()
+            };
+            vec![Line::from(vec![
+                Span::styled("Replay ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(speed, Style::default().fg(Color::Magenta)),
+                Span::raw(format!(" | {} UTC", clock)),
+            ])]
+        }
+        None => vec![Line::from(vec![
+            Span::styled("Uptime: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(duration_to_string(uptime)),
+        ])],
+    };
 
     let header_chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -135,34 +223,202 @@ fn render_header(
     frame.render_widget(right_header, header_chunks[1]);
 }
 
-fn render_main(
-    frame: &mut Frame,
-    area: Rect,
-    scaler: &Scaler,
-    snapshot: &MarketSnapshot,
-    config: &Config,
-) {
+fn futures_line(metrics: &MarketMetrics) -> Line<'static> {
+    let format_price = |price: Option<rust_decimal::Decimal>| {
+        price
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "--".to_string())
+    };
+
+    let funding = match metrics.funding_rate {
+        Some(rate) => {
+            let color = if rate.is_sign_negative() {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            Span::styled(
+                format!("{:.4}%", rate * rust_decimal::Decimal::ONE_HUNDRED),
+                Style::default().fg(color),
+            )
+        }
+        None => Span::styled("--", Style::default().fg(Color::DarkGray)),
+    };
+
+    // countdown to the next funding event
+    let next_funding = metrics
+        .next_funding_time
+        .map(|next_ms| {
+            let now_ms = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            duration_to_string(std::time::Duration::from_millis(
+                next_ms.saturating_sub(now_ms),
+            ))
+        })
+        .unwrap_or_else(|| "--".to_string());
+
+    Line::from(vec![
+        Span::raw("Mark: "),
+        Span::styled(
+            format_price(metrics.mark_price),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::raw(" | Index: "),
+        Span::raw(format_price(metrics.index_price)),
+        Span::raw(" | Funding: "),
+        funding,
+        Span::raw(format!(" in {}", next_funding)),
+    ])
+}
+
+fn render_main(frame: &mut Frame, area: Rect, snapshot: &MarketSnapshot, app_data: &super::App) {
+    let scaler = &app_data.selected_state().scaler;
+    let config = &app_data.config;
+    let ladder = &app_data.ladder;
+
+    // the heatmap and candles share a strip along the bottom
+    let bottom_panels = app_data.heatmap.enabled as u32 + app_data.candles.enabled as u32;
+    let area = if bottom_panels > 0 {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(area);
+        let panels = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Ratio(1, bottom_panels);
+                bottom_panels as usize
+            ])
+            .split(rows[1]);
+        let mut panels = panels.iter();
+        if app_data.heatmap.enabled
+            && let Some(panel) = panels.next()
+        {
+            render_heatmap(frame, *panel, scaler, app_data);
+        }
+        if app_data.candles.enabled
+            && let Some(panel) = panels.next()
+        {
+            render_candles(frame, *panel, snapshot, app_data.candles.interval());
+        }
+        rows[0]
+    } else {
+        area
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(area);
 
-    render_orderbook(
-        frame,
-        chunks[0],
-        scaler,
-        snapshot,
-        config.orderbook_depth_display_count,
-    );
+    // the depth chart takes the lower half of the book column
+    let book_area = if app_data.depth_chart {
+        let book_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[0]);
+        render_depth_chart(
+            frame,
+            book_chunks[1],
+            scaler,
+            snapshot,
+            config.depth_chart_range_pct,
+        );
+        book_chunks[0]
+    } else {
+        chunks[0]
+    };
+
+    if ladder.enabled {
+        render_ladder(frame, book_area, scaler, snapshot, ladder);
+    } else {
+        render_orderbook(
+            frame,
+            book_area,
+            scaler,
+            snapshot,
+            config.orderbook_depth_display_count,
+            config.virtual_order_horizon_secs,
+        );
+    }
     render_trade_flow(frame, chunks[1], snapshot, config);
 }
 
+// cumulative size either side of the mid, walls show up as steep steps
+fn render_depth_chart(
+    frame: &mut Frame,
+    area: Rect,
+    scaler: &Scaler,
+    snapshot: &MarketSnapshot,
+    range_pct: f64,
+) {
+    let title = format!("Depth (±{}%)", range_pct);
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    let Some(curve) = DepthCurve::build(&snapshot.book, scaler, range_pct) else {
+        frame.render_widget(Paragraph::new("Waiting for book...").block(block), area);
+        return;
+    };
+
+    let price_decimals = scaler.ticks_to_price(1).normalize().scale() as usize;
+    let price_label = |price: f64| Span::raw(format!("{:.*}", price_decimals, price));
+
+    let datasets = vec![
+        Dataset::default()
+            .name("Bids")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&curve.bids),
+        Dataset::default()
+            .name("Asks")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Red))
+            .data(&curve.asks),
+    ];
+
+    let max_qty = if curve.max_qty > 0.0 {
+        curve.max_qty
+    } else {
+        1.0
+    };
+
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds([curve.min_price, curve.max_price])
+                .labels(vec![
+                    price_label(curve.min_price),
+                    price_label(curve.mid),
+                    price_label(curve.max_price),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds([0.0, max_qty])
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.2}", max_qty / 2.0)),
+                    Span::raw(format!("{:.2}", max_qty)),
+                ]),
+        );
+
+    frame.render_widget(chart, area);
+}
+
 fn render_orderbook(
     frame: &mut Frame,
     area: Rect,
     scaler: &Scaler,
     snapshot: &MarketSnapshot,
     orderbook_depth_display_count: usize,
+    virtual_order_horizon_secs: u64,
 ) {
     let mut lines = vec![];
 
@@ -178,17 +434,25 @@ fn render_orderbook(
             "Quantity",
             Style::default().add_modifier(Modifier::UNDERLINED),
         ),
+        Span::raw(" ".repeat(6)),
+        Span::styled(
+            "Added/Cancelled",
+            Style::default().add_modifier(Modifier::UNDERLINED),
+        ),
     ]));
 
     let (bids, asks) = snapshot.top_n_depth(orderbook_depth_display_count, scaler);
+    let activity = &snapshot.level_activity;
 
     for (price, qty) in asks.iter().rev() {
-        lines.push(Line::from(vec![
+        let mut spans = vec![
             Span::raw("  "),
             Span::styled(format!("{:>12}", price), Style::default().fg(Color::Red)),
             Span::raw("  │  "),
             Span::raw(format!("{:>12}", qty)),
-        ]));
+        ];
+        spans.extend(level_flow_spans(activity, BookSide::Ask, *price, scaler));
+        lines.push(Line::from(spans));
     }
 
     lines.push(Line::from(vec![Span::styled(
@@ -197,12 +461,14 @@ fn render_orderbook(
     )]));
 
     for (price, qty) in bids.iter() {
-        lines.push(Line::from(vec![
+        let mut spans = vec![
             Span::raw("  "),
             Span::styled(format!("{:>12}", price), Style::default().fg(Color::Green)),
             Span::raw("  │  "),
             Span::raw(format!("{:>12}", qty)),
-        ]));
+        ];
+        spans.extend(level_flow_spans(activity, BookSide::Bid, *price, scaler));
+        lines.push(Line::from(spans));
     }
 
     lines.push(Line::from(vec![Span::styled(
@@ -263,6 +529,56 @@ fn render_orderbook(
         ),
     ]));
 
+    let window_secs = activity.window_ms / 1000;
+    let rates = [
+        ("Adds/s:", metrics.bid_add_rate, metrics.ask_add_rate),
+        (
+            "Cancels/s:",
+            metrics.bid_cancel_rate,
+            metrics.ask_cancel_rate,
+        ),
+        ("Fills/s:", metrics.bid_fill_rate, metrics.ask_fill_rate),
+    ];
+    for (label, bid_rate, ask_rate) in rates {
+        lines.push(Line::from(vec![
+            Span::raw(format!("  {:<12}", label)),
+            Span::styled(
+                format!("B {:<10.4}", bid_rate),
+                Style::default().fg(Color::Green),
+            ),
+            Span::styled(
+                format!("A {:<10.4}", ask_rate),
+                Style::default().fg(Color::Red),
+            ),
+            Span::styled(
+                format!("({}s)", window_secs),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::raw(format!("  {:<12}", "Pulls:")),
+        Span::styled(
+            format!("B {:<10}", metrics.bid_pulls),
+            Style::default().fg(Color::Green),
+        ),
+        Span::styled(
+            format!("A {:<10}", metrics.ask_pulls),
+            Style::default().fg(Color::Red),
+        ),
+    ]));
+
+    if !snapshot.virtual_orders.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "  Virtual Orders",
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+    }
+    for order in &snapshot.virtual_orders {
+        lines.push(virtual_order_line(order, virtual_order_horizon_secs));
+    }
+
     let paragraph = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
@@ -272,6 +588,282 @@ fn render_orderbook(
     frame.render_widget(paragraph, area);
 }
 
+// OHLC bars over a volume histogram, one column per bar
+fn render_candles(
+    frame: &mut Frame,
+    area: Rect,
+    snapshot: &MarketSnapshot,
+    interval: CandleInterval,
+) {
+    let all = snapshot.candles.candles(interval);
+    let title = match all.last() {
+        Some(last) => format!(
+            "Candles {} | O {} H {} L {} C {} | Vol {} (B {} / S {}) | {} trades",
+            interval,
+            last.open,
+            last.high,
+            last.low,
+            last.close,
+            last.volume.normalize(),
+            last.buy_volume.normalize(),
+            last.sell_volume.normalize(),
+            last.trade_count
+        ),
+        None => format!("Candles {}", interval),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let label_width = 12;
+    let cols = (inner.width as usize).saturating_sub(label_width + 1);
+    let candles = &all[all.len().saturating_sub(cols)..];
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
+        .split(inner);
+    let price_rows = sections[0].height as usize;
+    let volume_rows = sections[1].height as usize;
+
+    let Some(scale) = PriceScale::new(candles, price_rows) else {
+        frame.render_widget(Paragraph::new("Waiting for trades..."), inner);
+        return;
+    };
+
+    let candle_color = |candle: &Candle| {
+        if candle.close >= candle.open {
+            Color::Green
+        } else {
+            Color::Red
+        }
+    };
+
+    let high = candles.iter().map(|c| c.high).max().unwrap_or_default();
+    let low = candles.iter().map(|c| c.low).min().unwrap_or_default();
+    let price_lines: Vec<Line> = (0..price_rows)
+        .map(|row| {
+            let label = if row == 0 {
+                format!("{:>width$} ", high, width = label_width)
+            } else if row == price_rows - 1 {
+                format!("{:>width$} ", low, width = label_width)
+            } else {
+                " ".repeat(label_width + 1)
+            };
+            let mut spans = vec![Span::styled(label, Style::default().fg(Color::DarkGray))];
+            spans.extend(candles.iter().map(|candle| {
+                Span::styled(
+                    scale.glyph(candle, row).to_string(),
+                    Style::default().fg(candle_color(candle)),
+                )
+            }));
+            Line::from(spans)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(price_lines), sections[0]);
+
+    let max_volume = candles.iter().map(|c| c.volume).max().unwrap_or_default();
+    let volume_lines: Vec<Line> = (0..volume_rows)
+        .map(|row| {
+            let label = if row == 0 {
+                format!("{:>width$} ", "vol", width = label_width)
+            } else {
+                " ".repeat(label_width + 1)
+            };
+            let mut spans = vec![Span::styled(label, Style::default().fg(Color::DarkGray))];
+            spans.extend(candles.iter().map(|candle| {
+                let filled = volume_height(candle, max_volume, volume_rows);
+                let glyph = if row >= volume_rows - filled {
+                    "█"
+                } else {
+                    " "
+                };
+                Span::styled(glyph, Style::default().fg(candle_color(candle)))
+            }));
+            Line::from(spans)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(volume_lines), sections[1]);
+}
+
+// resting size over time, brighter = more size. trades are drawn over the top
+fn render_heatmap(frame: &mut Frame, area: Rect, scaler: &Scaler, app_data: &super::App) {
+    let heatmap = &app_data.heatmap;
+    let history_secs = heatmap.sample_interval().as_secs_f64()
+        * heatmap
+            .samples(app_data.selected)
+            .map_or(0, |samples| samples.len()) as f64;
+    let title = format!(
+        "Heatmap (±{}%, last {:.0}s)",
+        app_data.config.heatmap_range_pct, history_secs
+    );
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let price_decimals = scaler.ticks_to_price(1).normalize().scale() as usize;
+    let label_width = 12;
+    let rows = inner.height as usize;
+    let cols = (inner.width as usize).saturating_sub(label_width + 1);
+
+    let grid = heatmap.samples(app_data.selected).and_then(|samples| {
+        HeatmapGrid::build(samples, rows, cols, app_data.config.heatmap_range_pct)
+    });
+    let Some(grid) = grid else {
+        frame.render_widget(Paragraph::new("Waiting for book..."), inner);
+        return;
+    };
+
+    let mut markers = vec![vec![None; cols]; rows];
+    for &(row, col, is_buy) in &grid.trades {
+        markers[row][col] = Some(is_buy);
+    }
+
+    let lines: Vec<Line> = grid
+        .cells
+        .iter()
+        .zip(&markers)
+        .enumerate()
+        .map(|(row, (cells, markers))| {
+            // price labels on every fourth row
+            let label = if row % 4 == 0 {
+                let price = grid.top_price - row as u64 * grid.row_ticks;
+                format!(
+                    "{:>width$.*} ",
+                    price_decimals,
+                    scaler.ticks_to_price(price),
+                    width = label_width
+                )
+            } else {
+                " ".repeat(label_width + 1)
+            };
+            let mut spans = vec![Span::styled(label, Style::default().fg(Color::DarkGray))];
+            spans.extend(cells.iter().zip(markers).map(|(&intensity, marker)| {
+                let style = Style::default().bg(heat_color(intensity));
+                match marker {
+                    Some(true) => Span::styled("●", style.fg(Color::Green)),
+                    Some(false) => Span::styled("●", style.fg(Color::Red)),
+                    None => Span::styled(" ", style),
+                }
+            }));
+            Line::from(spans)
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+// black -> blue -> yellow -> white
+fn heat_color(intensity: f64) -> Color {
+    const STOPS: [(f64, f64, f64); 4] = [
+        (0.0, 0.0, 0.0),
+        (0.0, 90.0, 200.0),
+        (240.0, 200.0, 0.0),
+        (255.0, 255.0, 255.0),
+    ];
+    let scaled = intensity.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let idx = (scaled as usize).min(STOPS.len() - 2);
+    let t = scaled - idx as f64;
+    let (r0, g0, b0) = STOPS[idx];
+    let (r1, g1, b1) = STOPS[idx + 1];
+    Color::Rgb(
+        (r0 + (r1 - r0) * t) as u8,
+        (g0 + (g1 - g0) * t) as u8,
+        (b0 + (b1 - b0) * t) as u8,
+    )
+}
+
+// full depth, scrollable and grouped into price buckets
+fn render_ladder(
+    frame: &mut Frame,
+    area: Rect,
+    scaler: &Scaler,
+    snapshot: &MarketSnapshot,
+    state: &LadderState,
+) {
+    let ladder = Ladder::build(&snapshot.book, state.group_ticks(), scaler);
+
+    // borders and the header row
+    let height = area.height.saturating_sub(3) as usize;
+    let start = ladder.window_start(height, state.scroll);
+
+    let ladder_row = |level: &LadderLevel, color: Color| {
+        Row::new(vec![
+            Cell::from(format!("{}", scaler.ticks_to_price(level.price)))
+                .style(Style::default().fg(color)),
+            Cell::from(format!("{}", scaler.ticks_to_qty(level.qty))),
+            Cell::from(format!("{}", scaler.ticks_to_qty(level.cum_qty)))
+                .style(Style::default().fg(Color::DarkGray)),
+            Cell::from(format!("{:.2}", level.cum_notional))
+                .style(Style::default().fg(Color::DarkGray)),
+        ])
+    };
+
+    let best_ask = ladder.asks.first().map(|l| l.price);
+    let best_bid = ladder.bids.first().map(|l| l.price);
+    let selected = ladder.selected_index(state.scroll);
+    let rows: Vec<Row> = ladder
+        .asks
+        .iter()
+        .rev()
+        .map(|level| (level, Color::Red, Some(level.price) == best_ask))
+        .chain(
+            ladder
+                .bids
+                .iter()
+                .map(|level| (level, Color::Green, Some(level.price) == best_bid)),
+        )
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(index, (level, color, is_touch))| {
+            let mut style = Style::default();
+            if is_touch {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            // virtual orders placed with 'B'/'A' go to this row's price
+            if Some(index) == selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            ladder_row(level, color).style(style)
+        })
+        .collect();
+
+    let header = Row::new(vec![
+        Cell::from("Price"),
+        Cell::from("Size"),
+        Cell::from("Cum Size"),
+        Cell::from("Cum Notional"),
+    ])
+    .style(
+        Style::default()
+            .add_modifier(Modifier::BOLD)
+            .add_modifier(Modifier::UNDERLINED),
+    );
+
+    let title = format!(
+        "Ladder (group {}, rows {}-{} of {})",
+        scaler.ticks_to_price(state.group_ticks()).normalize(),
+        (start + 1).min(ladder.row_count()),
+        (start + height).min(ladder.row_count()),
+        ladder.row_count()
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Min(14),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_widget(table, area);
+}
+
 fn render_trade_flow(frame: &mut Frame, area: Rect, snapshot: &MarketSnapshot, config: &Config) {
     let metrics = &snapshot.metrics;
     let recent_trades = &snapshot.recent_trades;
@@ -291,7 +883,7 @@ fn render_trade_flow(frame: &mut Frame, area: Rect, snapshot: &MarketSnapshot, c
             Constraint::Length(1),                                // Gap
             Constraint::Length((sig_trades_count + 2) as u16), // Significant trades table + header
             Constraint::Length(5),                             // Metrics section
-            Constraint::Min(0),                                // Spacer
+            Constraint::Min(0),                                // Order flow
         ])
         .split(
             Block::default()
@@ -378,8 +970,7 @@ fn render_trade_flow(frame: &mut Frame, area: Rect, snapshot: &MarketSnapshot, c
                 ),
                 Cell::from(format!("{}", sig_trade.trade.quantity))
                     .style(Style::default().add_modifier(Modifier::BOLD)),
-                Cell::from(sig_trade.significance_reason.display())
-                    .style(Style::default().fg(Color::Yellow)),
+                Cell::from(sig_trade.short_reason()).style(Style::default().fg(Color::Yellow)),
                 Cell::from(format!("{:.2}", sig_trade.notional_value))
                     .style(Style::default().fg(Color::Cyan)),
             ])
@@ -392,7 +983,7 @@ fn render_trade_flow(frame: &mut Frame, area: Rect, snapshot: &MarketSnapshot, c
             Constraint::Length(6),
             Constraint::Length(14),
             Constraint::Length(14),
-            Constraint::Length(12),
+            Constraint::Length(16),
             Constraint::Length(14),
         ],
     )
@@ -455,8 +1046,8 @@ fn render_trade_flow(frame: &mut Frame, area: Rect, snapshot: &MarketSnapshot, c
                     .fg(Color::White)
                     .add_modifier(Modifier::BOLD),
             ),
-            Cell::from(""),
-            Cell::from(""),
+            Cell::from("Audits"),
+            audit_cell(metrics),
         ]),
     ];
 
@@ -476,20 +1067,243 @@ fn render_trade_flow(frame: &mut Frame, area: Rect, snapshot: &MarketSnapshot, c
     );
 
     frame.render_widget(metrics_table, chunks[3]);
+
+    render_order_flow(frame, chunks[4], metrics);
+}
+
+// cvd and ofi figures over the sparklines, which get whatever height is left
+fn render_order_flow(frame: &mut Frame, area: Rect, metrics: &MarketMetrics) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(area);
+
+    let signed = |value: rust_decimal::Decimal| {
+        let color = if value.is_sign_negative() && !value.is_zero() {
+            Color::Red
+        } else {
+            Color::Green
+        };
+        Cell::from(format!("{:+.4}", value)).style(Style::default().fg(color))
+    };
+    let rows = vec![
+        Row::new(vec![
+            Cell::from("CVD"),
+            signed(metrics.cvd_qty),
+            signed(metrics.cvd_qty_1m),
+            signed(metrics.cvd_qty_5m),
+        ]),
+        Row::new(vec![
+            Cell::from("OFI"),
+            signed(metrics.ofi),
+            signed(metrics.ofi_1m),
+            Cell::from(""),
+        ]),
+    ];
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(6),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(14),
+        ],
+    )
+    .header(
+        Row::new(vec!["", "Session", "1m", "5m"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+    frame.render_widget(table, chunks[0]);
+
+    let charts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+    let sparklines = [
+        (
+            "CVD",
+            flow::level_bars(&metrics.cvd_history, charts[0].width as usize),
+        ),
+        (
+            "OFI /s",
+            flow::magnitude_bars(&metrics.ofi_history, charts[1].width as usize),
+        ),
+    ];
+    for ((title, bars), chart) in sparklines.into_iter().zip(charts.iter()) {
+        let bars: Vec<SparklineBar> = bars
+            .into_iter()
+            .map(|bar| {
+                let color = if bar.negative {
+                    Color::Red
+                } else {
+                    Color::Green
+                };
+                SparklineBar::from(bar.height).style(Some(Style::default().fg(color)))
+            })
+            .collect();
+        let sparkline = Sparkline::default()
+            .block(Block::default().title(Span::styled(
+                format!("{title} ({}s)", bars.len()),
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .data(bars);
+        frame.render_widget(sparkline, *chart);
+    }
+}
+
+// passed/run, red once any audit has diverged
+fn audit_cell(metrics: &MarketMetrics) -> Cell<'static> {
+    if metrics.audits_run == 0 {
+        return Cell::from("--").style(Style::default().fg(Color::DarkGray));
+    }
+    let passed = metrics.audits_run - metrics.audit_divergences;
+    let color = if metrics.audit_divergences > 0 {
+        Color::Red
+    } else {
+        Color::Green
+    };
+    Cell::from(format!("{}/{}", passed, metrics.audits_run)).style(Style::default().fg(color))
 }
 
-fn render_footer(frame: &mut Frame, area: Rect, update_interval_ms: u64) {
+fn render_overview(frame: &mut Frame, area: Rect, states: &[Arc<MarketState>], selected: usize) {
+    let header = Row::new(vec![
+        Cell::from("#"),
+        Cell::from("Symbol"),
+        Cell::from("Status"),
+        Cell::from("Mid Price"),
+        Cell::from("Spread"),
+        Cell::from("Imbalance"),
+        Cell::from("Last"),
+        Cell::from("Volume (1m)"),
+        Cell::from("Trades (1m)"),
+        Cell::from("Buy %"),
+        Cell::from("Book lag"),
+    ])
+    .style(
+        Style::default()
+            .add_modifier(Modifier::BOLD)
+            .add_modifier(Modifier::UNDERLINED),
+    );
+
+    let rows: Vec<Row> = states
+        .iter()
+        .enumerate()
+        .map(|(idx, state)| {
+            let snapshot = state.load();
+            let metrics = &snapshot.metrics;
+
+            let status = match (snapshot.is_syncing, snapshot.health) {
+                (true, _) => Cell::from("SYNCING").style(Style::default().fg(Color::Yellow)),
+                (false, BookHealth::Crossed) => {
+                    Cell::from("CROSSED").style(Style::default().fg(Color::Red))
+                }
+                (false, BookHealth::Locked) => {
+                    Cell::from("LOCKED").style(Style::default().fg(Color::Yellow))
+                }
+                (false, BookHealth::Normal) => {
+                    Cell::from("LIVE").style(Style::default().fg(Color::Green))
+                }
+            };
+
+            let row = Row::new(vec![
+                Cell::from(format!("{}", idx + 1)),
+                Cell::from(state.symbol.clone())
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                status,
+                Cell::from(format_opt_decimal(metrics.mid_price, 2))
+                    .style(Style::default().fg(Color::Cyan)),
+                Cell::from(format_opt_decimal(metrics.spread, 4))
+                    .style(Style::default().fg(Color::Yellow)),
+                Cell::from(format_opt_decimal(metrics.imbalance_ratio, 3)),
+                Cell::from(format_opt_decimal(metrics.last_price, 2)),
+                Cell::from(format!("{:.4}", metrics.volume_1m)),
+                Cell::from(format!("{}", metrics.trade_count_1m)),
+                Cell::from(format_opt_int(
+                    metrics.buy_ratio_1m.map(|a| (a * 100.0).round() as u32),
+                )),
+                Cell::from(format!("{} ms", format_opt_int(metrics.orderbook_lag_ms))),
+            ]);
+
+            if idx == selected {
+                row.style(Style::default().bg(Color::DarkGray))
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Length(14),
+            Constraint::Length(9),
+            Constraint::Length(14),
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(12),
+            Constraint::Length(7),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Overview ({} symbols)", states.len())),
+    );
+
+    frame.render_widget(table, area);
+}
+
+fn render_footer(frame: &mut Frame, area: Rect, app_data: &super::App) {
     let footer_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(80), Constraint::Percentage(20)])
         .split(area);
-    let left_footer = Paragraph::new(
-        "'q' or 'Esc' to quit | 'f' to freeze/unfreeze | '↑/↓' to adjust display speed ",
+    let mut footer_text = String::from(
+        "'q' or 'Esc' to quit | 'f' to freeze/unfreeze | '↑/↓' to adjust display speed",
     );
+    if app_data.states.len() > 1 {
+        footer_text.push_str(" | 'Tab'/'←/→' or '1-9' to switch symbol | 'o' for overview");
+    }
+    if app_data.replay.is_some() {
+        footer_text.push_str(" | 'Space' pause | 'n' step | '+/-' replay speed");
+    }
+    footer_text.push_str(" | 'b/a' virtual order at best bid/ask | 'x' clear");
+    if app_data.ladder.enabled {
+        footer_text
+            .push_str(" | 'j/k' 'PgUp/PgDn' scroll | 'Home' recenter | '[/]' grouping | 'B/A' virtual order at cursor | 'l' book");
+    } else {
+        footer_text.push_str(" | 'l' ladder");
+    }
+    if app_data.depth_chart {
+        footer_text.push_str(" | 'd' hide depth");
+    } else {
+        footer_text.push_str(" | 'd' depth chart");
+    }
+    if app_data.heatmap.enabled {
+        footer_text.push_str(" | 'h' hide heatmap");
+    } else {
+        footer_text.push_str(" | 'h' heatmap");
+    }
+    if app_data.candles.enabled {
+        footer_text.push_str(" | 'i' interval | 'c' hide candles");
+    } else {
+        footer_text.push_str(" | 'c' candles");
+    }
+    let left_footer = Paragraph::new(footer_text);
 
     let right_footer = Paragraph::new(format!(
         "Display update interval: ({}ms)",
-        update_interval_ms
+        app_data.update_interval_ms
     ))
     .alignment(ratatui::layout::Alignment::Right);
 
@@ -510,6 +1324,95 @@ fn duration_to_string(dur: std::time::Duration) -> String {
     }
 }
 
+// wall clock time of day for a unix ms timestamp
+fn format_clock_ms(ms: u64) -> String {
+    let secs_of_day = (ms / 1000) % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs_of_day / 3600,
+        (secs_of_day / 60) % 60,
+        secs_of_day % 60,
+        ms % 1000
+    )
+}
+
+fn virtual_order_line(order: &VirtualOrderEstimate, horizon_secs: u64) -> Line<'static> {
+    let color = match order.side {
+        BookSide::Bid => Color::Green,
+        BookSide::Ask => Color::Red,
+    };
+    let mut spans = vec![Span::styled(
+        format!(
+            "  #{} {} {} @ {}",
+            order.id,
+            order.side.to_string().to_uppercase(),
+            order.qty.normalize(),
+            order.price
+        ),
+        Style::default().fg(color),
+    )];
+
+    if let Some(filled_at) = order.filled_at {
+        spans.push(Span::styled(
+            format!(
+                "  filled after {:.1}s",
+                filled_at.saturating_sub(order.placed_at) as f64 / 1000.0
+            ),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+        return Line::from(spans);
+    }
+
+    spans.push(Span::raw(format!("  ahead {}", order.ahead.normalize())));
+    if !order.filled.is_zero() {
+        spans.push(Span::raw(format!("  filled {}", order.filled.normalize())));
+    }
+    let eta = order
+        .eta_ms
+        .map(|eta| format!("{:.0}s", eta as f64 / 1000.0))
+        .unwrap_or_else(|| "--".to_string());
+    let probability = order
+        .fill_probability
+        .map(|p| format!("{:.0}%", p * 100.0))
+        .unwrap_or_else(|| "--".to_string());
+    spans.push(Span::styled(
+        format!("  ETA {}  P({}s) {}", eta, horizon_secs, probability),
+        Style::default().fg(Color::Cyan),
+    ));
+    Line::from(spans)
+}
+
+// qty added and cancelled at `price` over the level events window, blank when untouched
+fn level_flow_spans(
+    activity: &LevelActivity,
+    side: BookSide,
+    price: rust_decimal::Decimal,
+    scaler: &Scaler,
+) -> Vec<Span<'static>> {
+    let flow = scaler
+        .price_to_ticks(&price.to_string())
+        .map(|ticks| activity.level(side, ticks))
+        .unwrap_or_default();
+    let format_qty = |ticks: u64, sign: char| match ticks {
+        0 => String::new(),
+        ticks => format!("{}{}", sign, scaler.ticks_to_qty(ticks).normalize()),
+    };
+    vec![
+        Span::raw("  "),
+        Span::styled(
+            format!("{:>10}", format_qty(flow.added, '+')),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            format!("{:<10}", format_qty(flow.cancelled, '-')),
+            Style::default().fg(Color::Magenta),
+        ),
+    ]
+}
+
 fn format_opt_decimal(opt: Option<rust_decimal::Decimal>, precision: u32) -> String {
     opt.map(|d| format!("{:.1$}", d, precision as usize))
         .unwrap_or_else(|| "--".to_string())
@@ -0,0 +1,370 @@
+// Local mock of the Binance endpoints the engine talks to. Serves the depth
+// and exchangeInfo REST endpoints (spot and futures paths) over plain HTTP and
+// a combined /stream websocket that plays one scripted scenario per connection.
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+use binance_market_terminal::config::Config;
+use binance_market_terminal::engine::state::MarketState;
+
+// one step of a websocket scenario. once a script runs out the connection
+// stays open but silent (a stall) until the client goes away
+#[derive(Clone, Debug)]
+pub enum Step {
+    Send(String),
+    Sleep(Duration),
+    // close the socket without a close handshake
+    Disconnect,
+}
+
+struct MockState {
+    symbol: String,
+    // served in order, the last one repeats forever
+    snapshots: Mutex<VecDeque<String>>,
+    // one script per websocket connection, later connections stall
+    scripts: Mutex<VecDeque<Vec<Step>>>,
+    // how long depth snapshot responses are held back
+    snapshot_delay: Mutex<Duration>,
+    depth_requests: AtomicUsize,
+    ws_connections: AtomicUsize,
+    // request path of the latest websocket connection
+    ws_path: Mutex<Option<String>>,
+}
+
+pub struct MockBinance {
+    pub rest_base_url: String,
+    pub ws_base_url: String,
+    state: Arc<MockState>,
+}
+
+impl MockBinance {
+    pub async fn start(symbol: &str) -> Result<Self> {
+        let state = Arc::new(MockState {
+            symbol: symbol.to_uppercase(),
+            snapshots: Mutex::new(VecDeque::new()),
+            scripts: Mutex::new(VecDeque::new()),
+            snapshot_delay: Mutex::new(Duration::ZERO),
+            depth_requests: AtomicUsize::new(0),
+            ws_connections: AtomicUsize::new(0),
+            ws_path: Mutex::new(None),
+        });
+
+        let rest_listener = TcpListener::bind("127.0.0.1:0").await?;
+        let ws_listener = TcpListener::bind("127.0.0.1:0").await?;
+        let rest_base_url = format!("http://{}", rest_listener.local_addr()?);
+        let ws_base_url = format!("ws://{}", ws_listener.local_addr()?);
+
+        let rest_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = rest_listener.accept().await {
+                tokio::spawn(serve_http(socket, rest_state.clone()));
+            }
+        });
+
+        let ws_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = ws_listener.accept().await {
+                tokio::spawn(serve_ws(socket, ws_state.clone()));
+            }
+        });
+
+        Ok(Self {
+            rest_base_url,
+            ws_base_url,
+            state,
+        })
+    }
+
+    pub fn with_snapshot(
+        self,
+        last_update_id: u64,
+        bids: &[(&str, &str)],
+        asks: &[(&str, &str)],
+    ) -> Self {
+        self.state
+            .snapshots
+            .lock()
+            .unwrap()
+            .push_back(snapshot_json(last_update_id, bids, asks));
+        self
+    }
+
+    pub fn with_script(self, steps: Vec<Step>) -> Self {
+        self.state.scripts.lock().unwrap().push_back(steps);
+        self
+    }
+
+    pub fn with_snapshot_delay(self, delay: Duration) -> Self {
+        *self.state.snapshot_delay.lock().unwrap() = delay;
+        self
+    }
+
+    pub fn depth_requests(&self) -> usize {
+        self.state.depth_requests.load(Ordering::SeqCst)
+    }
+
+    pub fn ws_connections(&self) -> usize {
+        self.state.ws_connections.load(Ordering::SeqCst)
+    }
+
+    pub fn ws_path(&self) -> Option<String> {
+        self.state.ws_path.lock().unwrap().clone()
+    }
+
+    // engine config pointed at this mock, with short timeouts so scenarios run quickly
+    pub fn config(&self) -> Config {
+        Config {
+            rest_base_url: Some(self.rest_base_url.clone()),
+            ws_base_url: Some(self.ws_base_url.clone()),
+            message_timeout_ms: 5000,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 100,
+            ..Config::default()
+        }
+    }
+
+    pub fn depth(
+        &self,
+        first: u64,
+        last: u64,
+        bids: &[(&str, &str)],
+        asks: &[(&str, &str)],
+    ) -> Step {
+        self.depth_at(0, first, last, bids, asks)
+    }
+
+    // depth delta with an explicit exchange event time
+    pub fn depth_at(
+        &self,
+        event_time: u64,
+        first: u64,
+        last: u64,
+        bids: &[(&str, &str)],
+        asks: &[(&str, &str)],
+    ) -> Step {
+        let data = serde_json::json!({
+            "e": "depthUpdate",
+            "E": event_time,
+            "s": self.state.symbol,
+            "U": first,
+            "u": last,
+            "b": levels(bids),
+            "a": levels(asks),
+        });
+        Step::Send(self.combined("depth@100ms", data))
+    }
+
+    // futures depth delta, chained to the previous one through pu
+    pub fn futures_depth(
+        &self,
+        first: u64,
+        last: u64,
+        prev_last: u64,
+        bids: &[(&str, &str)],
+        asks: &[(&str, &str)],
+    ) -> Step {
+        let data = serde_json::json!({
+            "e": "depthUpdate",
+            "E": 0,
+            "T": 0,
+            "s": self.state.symbol,
+            "U": first,
+            "u": last,
+            "pu": prev_last,
+            "b": levels(bids),
+            "a": levels(asks),
+        });
+        Step::Send(self.combined("depth@100ms", data))
+    }
+
+    pub fn agg_trade(&self, price: &str, qty: &str, trade_time: u64, is_buyer_maker: bool) -> Step {
+        let data = serde_json::json!({
+            "e": "aggTrade",
+            "E": trade_time,
+            "a": 1,
+            "s": self.state.symbol,
+            "p": price,
+            "q": qty,
+            "f": 1,
+            "l": 1,
+            "T": trade_time,
+            "m": is_buyer_maker,
+        });
+        Step::Send(self.combined("aggTrade", data))
+    }
+
+    pub fn mark_price(
+        &self,
+        mark: &str,
+        index: &str,
+        funding_rate: &str,
+        next_funding: u64,
+    ) -> Step {
+        let data = serde_json::json!({
+            "e": "markPriceUpdate",
+            "E": 0,
+            "s": self.state.symbol,
+            "p": mark,
+            "i": index,
+            "P": index,
+            "r": funding_rate,
+            "T": next_funding,
+        });
+        Step::Send(self.combined("markPrice@1s", data))
+    }
+
+    pub fn trade(&self, price: &str, qty: &str, trade_time: u64, is_buyer_maker: bool) -> Step {
+        let data = serde_json::json!({
+            "e": "trade",
+            "E": trade_time,
+            "s": self.state.symbol,
+            "p": price,
+            "q": qty,
+            "T": trade_time,
+            "m": is_buyer_maker,
+        });
+        Step::Send(self.combined("trade", data))
+    }
+
+    fn combined(&self, stream: &str, data: serde_json::Value) -> String {
+        serde_json::json!({
+            "stream": format!("{}@{}", self.state.symbol.to_lowercase(), stream),
+            "data": data,
+        })
+        .to_string()
+    }
+}
+
+fn levels(levels: &[(&str, &str)]) -> serde_json::Value {
+    levels
+        .iter()
+        .map(|(price, qty)| serde_json::json!([price, qty]))
+        .collect()
+}
+
+fn snapshot_json(last_update_id: u64, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> String {
+    serde_json::json!({
+        "lastUpdateId": last_update_id,
+        "bids": levels(bids),
+        "asks": levels(asks),
+    })
+    .to_string()
+}
+
+fn exchange_info_json(symbol: &str) -> String {
+    serde_json::json!({
+        "symbols": [{
+            "symbol": symbol,
+            "filters": [
+                { "filterType": "PRICE_FILTER", "tickSize": "0.01" },
+                { "filterType": "LOT_SIZE", "stepSize": "0.001" },
+            ],
+        }],
+    })
+    .to_string()
+}
+
+async fn serve_http(mut socket: TcpStream, state: Arc<MockState>) {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, body) = if path.contains("/depth") {
+        state.depth_requests.fetch_add(1, Ordering::SeqCst);
+        let delay = *state.snapshot_delay.lock().unwrap();
+        tokio::time::sleep(delay).await;
+        let mut snapshots = state.snapshots.lock().unwrap();
+        let body = if snapshots.len() > 1 {
+            snapshots.pop_front()
+        } else {
+            snapshots.front().cloned()
+        };
+        match body {
+            Some(body) => ("200 OK", body),
+            None => ("500 Internal Server Error", "{}".to_string()),
+        }
+    } else if path.contains("/exchangeInfo") {
+        ("200 OK", exchange_info_json(&state.symbol))
+    } else {
+        ("404 Not Found", "{}".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+async fn serve_ws(socket: TcpStream, state: Arc<MockState>) {
+    // "GET <path> HTTP/1.1", read ahead of the handshake
+    let mut head = [0u8; 1024];
+    let read = socket.peek(&mut head).await.unwrap_or(0);
+    let path = String::from_utf8_lossy(&head[..read])
+        .split_whitespace()
+        .nth(1)
+        .map(str::to_string);
+    *state.ws_path.lock().unwrap() = path;
+
+    let Ok(mut ws) = tokio_tungstenite::accept_async(socket).await else {
+        return;
+    };
+    state.ws_connections.fetch_add(1, Ordering::SeqCst);
+
+    let script = state
+        .scripts
+        .lock()
+        .unwrap()
+        .pop_front()
+        .unwrap_or_default();
+    for step in script {
+        match step {
+            Step::Send(text) => {
+                if ws.send(Message::text(text)).await.is_err() {
+                    return;
+                }
+            }
+            Step::Sleep(duration) => tokio::time::sleep(duration).await,
+            Step::Disconnect => return,
+        }
+    }
+
+    // stall: keep the connection open until the client drops it
+    while let Some(Ok(_)) = ws.next().await {}
+}
+
+// polls the published snapshot until the predicate holds or the timeout elapses
+pub async fn wait_for<F>(state: &MarketState, timeout: Duration, predicate: F) -> bool
+where
+    F: Fn(&binance_market_terminal::engine::state::MarketSnapshot) -> bool,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        if predicate(&state.load()) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    predicate(&state.load())
+}
@@ -0,0 +1,772 @@
+// End to end engine scenarios against the local mock Binance server
+mod common;
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use tokio::sync::mpsc;
+
+use binance_market_terminal::binance::market::{Market, TradeStream};
+use binance_market_terminal::binance::source::BinanceSource;
+use binance_market_terminal::book::health::BookHealth;
+use binance_market_terminal::book::orderbook::BookSide;
+use binance_market_terminal::config::Config;
+use binance_market_terminal::engine::candles::CandleInterval;
+use binance_market_terminal::engine::events::BookEvent;
+use binance_market_terminal::engine::runtime::{EngineCommand, MarketDataEngine};
+use binance_market_terminal::engine::state::{MarketSnapshot, MarketState};
+use binance_market_terminal::engine::symbol::SymbolSetup;
+use binance_market_terminal::recording::recorder::Recorder;
+use binance_market_terminal::recording::replay::{ReplaySource, ReplaySpeed};
+
+use common::{MockBinance, Step, wait_for};
+
+const SYMBOL: &str = "BTCUSDT";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn start_engine(conf: Config) -> (mpsc::Sender<EngineCommand>, Arc<MarketState>) {
+    let conf = Arc::new(conf);
+    let source = BinanceSource::from_config(&conf);
+    let setup = SymbolSetup::fetch(&source, SYMBOL, conf.orderbook_initial_snapshot_depth)
+        .await
+        .expect("bootstrap from mock");
+
+    let (engine, command_tx, mut states) =
+        MarketDataEngine::new(Arc::new(source), vec![setup], conf).expect("engine");
+    tokio::spawn(engine.run());
+
+    (command_tx, states.remove(0))
+}
+
+fn best_bid(state: &MarketState, snapshot: &MarketSnapshot) -> Option<Decimal> {
+    snapshot
+        .book
+        .best_bid()
+        .map(|(price, _)| state.scaler.ticks_to_price(*price))
+}
+
+fn price(s: &str) -> Option<Decimal> {
+    Some(Decimal::from_str(s).unwrap())
+}
+
+#[tokio::test]
+async fn applies_deltas_and_trades_on_top_of_snapshot() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+    let script = vec![
+        mock.depth(101, 102, &[("100.50", "2")], &[]),
+        mock.trade("100.60", "0.5", 1, false),
+    ];
+    let mock = mock.with_script(script);
+
+    let (_tx, state) = start_engine(mock.config()).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("100.50") && s.metrics.total_trades == 1 && !s.is_syncing
+        })
+        .await
+    );
+    assert_eq!(mock.depth_requests(), 1);
+    assert_eq!(mock.ws_connections(), 1);
+
+    let snapshot = state.load();
+    let candle = snapshot.candles.candles(CandleInterval::M1).last().copied();
+    assert_eq!(candle.as_ref().map(|c| c.close), price("100.60"));
+    assert_eq!(candle.map(|c| c.buy_volume), price("0.5"));
+
+    // a taker buy, and a better bid joining the book
+    assert_eq!(Some(snapshot.metrics.cvd_qty), price("0.5"));
+    assert_eq!(Some(snapshot.metrics.cvd_notional), price("50.30"));
+    assert_eq!(Some(snapshot.metrics.ofi), price("2"));
+}
+
+#[tokio::test]
+async fn clusters_aggregate_trades_into_taker_orders() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1"), ("102.00", "1"), ("103.00", "1")],
+    );
+    let script = vec![
+        mock.agg_trade("101.00", "1", 1, false),
+        mock.agg_trade("102.00", "1", 1, false),
+        mock.agg_trade("103.00", "2", 1, false),
+        mock.agg_trade("100.00", "0.5", 1, true),
+    ];
+    let mock = mock.with_script(script);
+
+    let conf = Config {
+        trade_stream: TradeStream::AggTrade,
+        min_trades_for_significance: 1,
+        significant_trade_volume_pct: 1.0,
+        significant_sweep_levels: Some(2),
+        ..mock.config()
+    };
+    let (_tx, state) = start_engine(conf).await;
+
+    assert!(wait_for(&state, TIMEOUT, |s| s.metrics.total_trades == 4).await);
+    assert!(mock.ws_path().unwrap().contains("btcusdt@aggTrade"));
+
+    // three buy prints make one order, the sell starts another
+    let snapshot = state.load();
+    let prices: Vec<_> = snapshot.recent_trades.iter().map(|t| t.price).collect();
+    assert_eq!(prices, vec![Decimal::new(10225, 2), Decimal::new(10000, 2)]);
+    assert_eq!(snapshot.recent_trades[0].quantity, Decimal::from(4));
+
+    // while the 1 minute metrics still count every print at its own price
+    assert_eq!(snapshot.metrics.trade_count_1m, 4);
+    assert_eq!(snapshot.metrics.vwap_1m, Some(Decimal::from(102)));
+    assert_eq!(snapshot.metrics.buy_ratio_1m, Some(0.75));
+
+    assert_eq!(snapshot.significant_trades.len(), 1);
+    let flagged = &snapshot.significant_trades[0];
+    assert_eq!(flagged.notional_value, Decimal::from(409));
+    assert_eq!(flagged.short_reason(), "Vol: 100.0% +1");
+    assert_eq!(flagged.reasons(), "Vol: 100.0%, Sweep: 3");
+}
+
+#[tokio::test]
+async fn ignores_stale_deltas_from_before_the_snapshot() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+    let script = vec![
+        mock.depth(95, 98, &[("100.90", "1")], &[]),
+        mock.depth(99, 101, &[("100.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script);
+
+    let (_tx, state) = start_engine(mock.config()).await;
+
+    assert!(wait_for(&state, TIMEOUT, |s| best_bid(&state, s) == price("100.50")).await);
+    assert_eq!(mock.depth_requests(), 1);
+}
+
+#[tokio::test]
+async fn gap_triggers_snapshot_refetch() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(200, &[("99.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        mock.depth(101, 102, &[("100.50", "1")], &[]),
+        mock.depth(110, 111, &[("100.70", "1")], &[]),
+        Step::Sleep(Duration::from_millis(300)),
+        mock.depth(201, 202, &[("99.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script);
+
+    let (_tx, state) = start_engine(mock.config()).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("99.50") && !s.is_syncing
+        })
+        .await
+    );
+    assert_eq!(mock.depth_requests(), 2);
+    assert_eq!(mock.ws_connections(), 1);
+
+    let snapshot = state.load();
+    assert_eq!(snapshot.metrics.gaps_detected, 1);
+    assert_eq!(snapshot.metrics.resyncs, 1);
+    assert_eq!(snapshot.metrics.messages_processed, 3);
+}
+
+#[tokio::test]
+async fn buffered_out_of_order_deltas_apply_in_sequence() {
+    // the refetched snapshot is slow, so deltas pile up out of order in the meantime
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(200, &[("99.00", "1")], &[("101.00", "1")])
+        .with_snapshot_delay(Duration::from_millis(300));
+    let script = vec![
+        mock.depth(101, 102, &[("100.50", "1")], &[]),
+        mock.depth(110, 111, &[("100.70", "1")], &[]),
+        mock.depth(203, 204, &[("99.70", "0"), ("99.60", "1")], &[]),
+        mock.depth(201, 202, &[("99.70", "1")], &[]),
+        Step::Sleep(Duration::from_millis(600)),
+        mock.depth(205, 206, &[], &[("100.90", "1")]),
+    ];
+    let mock = mock.with_script(script);
+
+    let (_tx, state) = start_engine(mock.config()).await;
+
+    // applied in arrival order 99.70 would survive, in sequence order it is removed
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("99.60") && !s.is_syncing
+        })
+        .await
+    );
+    assert_eq!(mock.depth_requests(), 2);
+}
+
+#[tokio::test]
+async fn reconnects_and_resyncs_after_disconnect() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(300, &[("98.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        mock.depth(101, 102, &[("100.50", "1")], &[]),
+        Step::Disconnect,
+    ];
+    let reconnect_script = vec![
+        Step::Sleep(Duration::from_millis(300)),
+        mock.depth(301, 302, &[("98.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script).with_script(reconnect_script);
+
+    let (_tx, state) = start_engine(mock.config()).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("98.50") && !s.is_syncing
+        })
+        .await
+    );
+    assert_eq!(mock.ws_connections(), 2);
+    assert_eq!(mock.depth_requests(), 2);
+}
+
+#[tokio::test]
+async fn reconnects_after_stall() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(300, &[("98.00", "1")], &[("101.00", "1")]);
+    // first connection goes silent after one delta
+    let script = vec![mock.depth(101, 102, &[("100.50", "1")], &[])];
+    let reconnect_script = vec![
+        Step::Sleep(Duration::from_millis(100)),
+        mock.depth(301, 302, &[("98.50", "1")], &[]),
+    ];
+    let conf = Config {
+        message_timeout_ms: 300,
+        ..mock.config()
+    };
+    let mock = mock.with_script(script).with_script(reconnect_script);
+
+    let (_tx, state) = start_engine(conf).await;
+
+    assert!(wait_for(&state, TIMEOUT, |s| best_bid(&state, s) == price("98.50")).await);
+    assert!(mock.ws_connections() >= 2);
+}
+
+#[tokio::test]
+async fn malformed_frame_forces_reconnect() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(300, &[("98.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        mock.depth(101, 102, &[("100.50", "1")], &[]),
+        Step::Send("{\"stream\": \"btcusdt@depth@100ms\", \"data\": ".to_string()),
+    ];
+    let reconnect_script = vec![
+        Step::Sleep(Duration::from_millis(300)),
+        mock.depth(301, 302, &[("98.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script).with_script(reconnect_script);
+
+    let (_tx, state) = start_engine(mock.config()).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("98.50") && !s.is_syncing
+        })
+        .await
+    );
+    assert_eq!(mock.ws_connections(), 2);
+    assert_eq!(mock.depth_requests(), 2);
+}
+
+#[tokio::test]
+async fn futures_deltas_chain_through_pu_and_feed_mark_price() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+    let script = vec![
+        mock.futures_depth(95, 105, 90, &[("100.50", "1")], &[]),
+        // ids jump but pu links back, so this is not a gap
+        mock.futures_depth(120, 130, 105, &[("100.60", "1")], &[]),
+        mock.agg_trade("100.70", "0.5", 1, false),
+        mock.mark_price("100.55", "100.50", "0.00010000", 1_700_000_000_000),
+    ];
+    let mock = mock.with_script(script);
+    let conf = Config {
+        market: Market::UsdM,
+        ..mock.config()
+    };
+
+    let (_tx, state) = start_engine(conf).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("100.60")
+                && s.metrics.total_trades == 1
+                && s.metrics.mark_price == price("100.55")
+                && s.metrics.funding_rate == price("0.0001")
+                && s.metrics.next_funding_time == Some(1_700_000_000_000)
+        })
+        .await
+    );
+    assert_eq!(mock.depth_requests(), 1);
+}
+
+#[tokio::test]
+async fn futures_pu_mismatch_triggers_snapshot_refetch() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(200, &[("99.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        mock.futures_depth(95, 105, 90, &[("100.50", "1")], &[]),
+        mock.futures_depth(106, 110, 104, &[("100.60", "1")], &[]),
+        Step::Sleep(Duration::from_millis(300)),
+        mock.futures_depth(199, 205, 198, &[("99.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script);
+    let conf = Config {
+        market: Market::UsdM,
+        ..mock.config()
+    };
+
+    let (_tx, state) = start_engine(conf).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("99.50") && !s.is_syncing
+        })
+        .await
+    );
+    assert_eq!(mock.depth_requests(), 2);
+}
+
+#[tokio::test]
+async fn crossed_book_is_flagged_then_resynced_after_grace() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(300, &[("98.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        // bid through the ask, then still crossed a second later
+        mock.depth_at(1_000, 101, 102, &[("101.50", "1")], &[]),
+        Step::Sleep(Duration::from_millis(200)),
+        mock.depth_at(2_500, 103, 104, &[("101.60", "1")], &[]),
+        Step::Sleep(Duration::from_millis(300)),
+        mock.depth(301, 302, &[("98.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script);
+    let conf = Config {
+        crossed_book_grace_ms: 1_000,
+        ..mock.config()
+    };
+
+    let (_tx, state) = start_engine(conf).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            s.health == BookHealth::Crossed && s.metrics.spread.is_none()
+        })
+        .await
+    );
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("98.50") && s.health == BookHealth::Normal
+        })
+        .await
+    );
+    assert_eq!(state.load().metrics.crossed_book_events, 1);
+    assert_eq!(mock.depth_requests(), 2);
+}
+
+#[tokio::test]
+async fn locked_book_within_grace_is_only_counted() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+    let script = vec![
+        mock.depth_at(1_000, 101, 102, &[("101.00", "1")], &[]),
+        mock.depth_at(1_100, 103, 104, &[("101.00", "0")], &[]),
+    ];
+    let mock = mock.with_script(script);
+
+    let (_tx, state) = start_engine(mock.config()).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            s.metrics.locked_book_events == 1 && s.health == BookHealth::Normal
+        })
+        .await
+    );
+    assert_eq!(mock.depth_requests(), 1);
+}
+
+#[tokio::test]
+async fn audit_passes_on_consistent_book() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(101, &[("100.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        mock.depth(101, 101, &[], &[]),
+        mock.depth(102, 103, &[("100.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script);
+    let conf = Config {
+        audit_interval_secs: 1,
+        ..mock.config()
+    };
+
+    let (_tx, state) = start_engine(conf).await;
+
+    assert!(wait_for(&state, TIMEOUT, |s| s.metrics.audits_run >= 1).await);
+    assert_eq!(state.load().metrics.audit_divergences, 0);
+}
+
+#[tokio::test]
+async fn diverged_audit_forces_resync() {
+    // the audit snapshot disagrees with what the stream built
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(102, &[("100.00", "5")], &[("101.00", "1")])
+        .with_snapshot(300, &[("98.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        mock.depth(101, 102, &[("100.50", "1")], &[]),
+        Step::Sleep(Duration::from_millis(1500)),
+        mock.depth(301, 302, &[("98.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script);
+    let conf = Config {
+        audit_interval_secs: 1,
+        audit_force_resync: true,
+        ..mock.config()
+    };
+
+    let (_tx, state) = start_engine(conf).await;
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            best_bid(&state, s) == price("98.50") && s.metrics.audit_divergences == 1
+        })
+        .await
+    );
+    assert_eq!(state.load().metrics.last_audit_mismatches, 2);
+    assert!(mock.depth_requests() >= 3);
+}
+
+#[tokio::test]
+async fn reloaded_config_turns_on_audits() {
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1")], &[("101.00", "1")])
+        .with_snapshot(101, &[("100.00", "1")], &[("101.00", "1")]);
+    let script = vec![mock.depth(101, 101, &[], &[])];
+    let mock = mock.with_script(script);
+
+    let (tx, state) = start_engine(mock.config()).await;
+    assert!(wait_for(&state, TIMEOUT, |s| !s.is_syncing).await);
+
+    let conf = Config {
+        audit_interval_secs: 1,
+        ..mock.config()
+    };
+    tx.send(EngineCommand::UpdateConfig(Arc::new(conf)))
+        .await
+        .unwrap();
+
+    assert!(wait_for(&state, TIMEOUT, |s| s.metrics.audits_run >= 1).await);
+}
+
+#[tokio::test]
+async fn audit_snapshots_are_left_out_of_recordings() {
+    // the audit snapshot is shallower than the book, replaying it would drop 99.00
+    let mock = MockBinance::start(SYMBOL)
+        .await
+        .unwrap()
+        .with_snapshot(100, &[("100.00", "1"), ("99.00", "1")], &[("101.00", "1")])
+        .with_snapshot(101, &[("100.00", "1")], &[("101.00", "1")]);
+    let script = vec![
+        mock.depth(101, 101, &[], &[]),
+        Step::Sleep(Duration::from_millis(1500)),
+        mock.depth(102, 103, &[("100.50", "1")], &[]),
+    ];
+    let mock = mock.with_script(script);
+    let conf = Arc::new(Config {
+        audit_interval_secs: 1,
+        ..mock.config()
+    });
+
+    let path = std::env::temp_dir().join(format!("audit-record-{}.rec", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let _ = std::fs::remove_file(&path);
+
+    let (recorder, writer_handle) = Recorder::open(&path).await.unwrap();
+    let source = BinanceSource::from_config(&conf).with_recorder(recorder);
+    let setup = SymbolSetup::fetch(&source, SYMBOL, conf.orderbook_initial_snapshot_depth)
+        .await
+        .unwrap();
+    let (engine, command_tx, mut states) =
+        MarketDataEngine::new(Arc::new(source), vec![setup], conf.clone()).unwrap();
+    let handle = tokio::spawn(engine.run());
+    let state = states.remove(0);
+
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            s.metrics.audits_run >= 1 && best_bid(&state, s) == price("100.50")
+        })
+        .await
+    );
+    command_tx.send(EngineCommand::Shutdown).await.unwrap();
+    handle.await.unwrap().unwrap();
+    tokio::time::timeout(TIMEOUT, writer_handle)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let recording = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(recording.lines().filter(|l| l.contains(" S ")).count(), 1);
+
+    let source = ReplaySource::open(&path, ReplaySpeed::Max).await.unwrap();
+    let control = source.control();
+    let setup = SymbolSetup::fetch(&source, SYMBOL, conf.orderbook_initial_snapshot_depth)
+        .await
+        .unwrap();
+    let (engine, _command_tx, mut states) =
+        MarketDataEngine::new(Arc::new(source), vec![setup], conf).unwrap();
+    tokio::spawn(engine.run());
+    let state = states.remove(0);
+
+    tokio::time::timeout(TIMEOUT, control.finished())
+        .await
+        .unwrap();
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            !s.is_syncing
+                && best_bid(&state, s) == price("100.50")
+                && s.book
+                    .bids()
+                    .contains_key(&state.scaler.price_to_ticks("99.00").unwrap())
+        })
+        .await
+    );
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn virtual_orders_queue_behind_resting_size_and_fill() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+    let script = vec![
+        mock.depth(101, 101, &[], &[]),
+        Step::Sleep(Duration::from_millis(500)),
+        mock.trade("100.00", "1.2", 1, true),
+    ];
+    let mock = mock.with_script(script);
+
+    let (tx, state) = start_engine(mock.config()).await;
+    assert!(wait_for(&state, TIMEOUT, |s| !s.is_syncing).await);
+
+    tx.send(EngineCommand::PlaceVirtualOrder {
+        symbol: SYMBOL.to_string(),
+        side: BookSide::Bid,
+        price: None,
+        qty: Decimal::new(5, 1),
+    })
+    .await
+    .unwrap();
+    assert!(wait_for(&state, TIMEOUT, |s| s.virtual_orders.len() == 1).await);
+    let order = state.load().virtual_orders[0].clone();
+    assert_eq!(Some(order.price), price("100.00"));
+    assert_eq!(order.ahead, Decimal::ONE);
+
+    // the sell takes the 1 resting ahead, then part of the virtual order
+    assert!(wait_for(&state, TIMEOUT, |s| s.metrics.total_trades == 1).await);
+    let order = state.load().virtual_orders[0].clone();
+    assert_eq!(order.ahead, Decimal::ZERO);
+    assert_eq!(Some(order.filled), price("0.2"));
+
+    tx.send(EngineCommand::ClearVirtualOrders(SYMBOL.to_string()))
+        .await
+        .unwrap();
+    assert!(wait_for(&state, TIMEOUT, |s| s.virtual_orders.is_empty()).await);
+}
+
+#[tokio::test]
+async fn virtual_orders_rest_at_a_chosen_price() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1"), ("99.50", "2")],
+        &[("101.00", "1")],
+    );
+    let script = vec![mock.depth(101, 101, &[], &[])];
+    let mock = mock.with_script(script);
+
+    let (tx, state) = start_engine(mock.config()).await;
+    assert!(wait_for(&state, TIMEOUT, |s| !s.is_syncing).await);
+
+    // off the 0.01 tick grid, off the 0.001 step grid, and empty: all refused
+    for (order_price, qty) in [("99.505", "0.5"), ("99.50", "0.0005"), ("99.50", "0")] {
+        tx.send(EngineCommand::PlaceVirtualOrder {
+            symbol: SYMBOL.to_string(),
+            side: BookSide::Bid,
+            price: price(order_price),
+            qty: Decimal::from_str(qty).unwrap(),
+        })
+        .await
+        .unwrap();
+    }
+    tx.send(EngineCommand::PlaceVirtualOrder {
+        symbol: SYMBOL.to_string(),
+        side: BookSide::Bid,
+        price: price("99.50"),
+        qty: Decimal::new(7, 1),
+    })
+    .await
+    .unwrap();
+    // commands are handled in order, so the refused ones are done once this shows up
+    assert!(
+        wait_for(&state, TIMEOUT, |s| {
+            s.virtual_orders.iter().any(|o| o.qty == Decimal::new(7, 1))
+        })
+        .await
+    );
+    let snapshot = state.load();
+    assert_eq!(snapshot.virtual_orders.len(), 1);
+    let order = snapshot.virtual_orders[0].clone();
+    assert_eq!(Some(order.price), price("99.50"));
+    assert_eq!(order.ahead, Decimal::TWO);
+    assert_eq!(order.filled, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn publishes_applied_deltas_and_trades() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+    let script = vec![
+        mock.depth(95, 98, &[("100.90", "1")], &[]),
+        mock.depth(99, 101, &[("100.50", "1")], &[]),
+        mock.trade("100.60", "0.5", 1, false),
+    ];
+    let mock = mock.with_script(script);
+
+    let conf = Arc::new(mock.config());
+    let source = BinanceSource::from_config(&conf);
+    let setup = SymbolSetup::fetch(&source, SYMBOL, conf.orderbook_initial_snapshot_depth)
+        .await
+        .unwrap();
+    let (engine, _command_tx, _states) =
+        MarketDataEngine::new(Arc::new(source), vec![setup], conf).unwrap();
+    let mut events = engine.book_events().subscribe();
+    tokio::spawn(engine.run());
+
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        let event = tokio::time::timeout(TIMEOUT, events.recv()).await.unwrap();
+        received.push(event.unwrap());
+    }
+
+    // the stale delta from before the snapshot is never published
+    assert!(matches!(
+        &received[0],
+        BookEvent::Depth { symbol, update } if symbol == SYMBOL && update.final_update_id == 101
+    ));
+    assert!(matches!(
+        &received[1],
+        BookEvent::Trade { trade, .. } if trade.price == Decimal::from_str("100.60").unwrap()
+    ));
+}
+
+// the subscriber runs on its own worker so it can observe the engine mid-update
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn publishes_the_snapshot_before_its_deltas() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+    let script = (0..50)
+        .map(|i| mock.depth(101 + i * 2, 102 + i * 2, &[("99.00", "1")], &[]))
+        .collect();
+    let mock = mock.with_script(script);
+
+    let conf = Arc::new(mock.config());
+    let source = BinanceSource::from_config(&conf);
+    let setup = SymbolSetup::fetch(&source, SYMBOL, conf.orderbook_initial_snapshot_depth)
+        .await
+        .unwrap();
+    let (engine, _command_tx, mut states) =
+        MarketDataEngine::new(Arc::new(source), vec![setup], conf).unwrap();
+    let mut events = engine.book_events().subscribe();
+    let state = states.remove(0);
+    tokio::spawn(engine.run());
+
+    for _ in 0..50 {
+        let event = tokio::time::timeout(TIMEOUT, events.recv()).await.unwrap();
+        let BookEvent::Depth { update, .. } = event.unwrap() else {
+            continue;
+        };
+        // a client that took the snapshot now must not need this delta on top of it
+        let published = state.load().last_update_id;
+        assert!(
+            published >= Some(update.final_update_id),
+            "delta {} emitted before snapshot {:?} was published",
+            update.final_update_id,
+            published
+        );
+    }
+}
+
+#[tokio::test]
+async fn shuts_down_on_command() {
+    let mock = MockBinance::start(SYMBOL).await.unwrap().with_snapshot(
+        100,
+        &[("100.00", "1")],
+        &[("101.00", "1")],
+    );
+
+    let conf = Arc::new(mock.config());
+    let source = BinanceSource::from_config(&conf);
+    let setup = SymbolSetup::fetch(&source, SYMBOL, conf.orderbook_initial_snapshot_depth)
+        .await
+        .unwrap();
+    let (engine, command_tx, _states) =
+        MarketDataEngine::new(Arc::new(source), vec![setup], conf).unwrap();
+    let handle = tokio::spawn(engine.run());
+
+    command_tx.send(EngineCommand::Shutdown).await.unwrap();
+    let result = tokio::time::timeout(TIMEOUT, handle).await;
+    assert!(matches!(result, Ok(Ok(Ok(())))));
+}